@@ -16,6 +16,7 @@ fn main() {
                 uid: 1234,
                 user: "Username".to_string(),
             }),
+            ..Default::default()
         },
     });
 
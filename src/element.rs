@@ -3,6 +3,10 @@
 //! See: https://wiki.openstreetmap.org/wiki/Elements
 
 use crate::geo::Coordinate;
+use crate::osm_io::error::{Error, ErrorKind};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 type RelationRole = String;
 type TimeStamp = i64;
@@ -20,7 +24,7 @@ pub struct Node {
 /// Group of nodes and meta data. See OSM docs for [`Way`].
 ///
 /// [`Way`]: https://wiki.openstreetmap.org/wiki/Way
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
 pub struct Way {
     pub id: i64,
     pub refs: Vec<i64>,
@@ -30,7 +34,7 @@ pub struct Way {
 /// Group of elements (node, way or relation). See OSM docs for [`Relation`].
 ///
 /// [`Relation`]: https://wiki.openstreetmap.org/wiki/Relation
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
 pub struct Relation {
     pub id: i64,
     pub members: Vec<RelationMember>,
@@ -47,11 +51,27 @@ pub struct Tag {
 }
 
 /// Common meta data used by multiple entities.
-#[derive(Debug, Eq, PartialEq, Clone)]
+///
+/// `visible` is `Some(false)` for deleted elements in history and change files. It is only
+/// supported by the xml format, the o5m format ignores it.
+///
+/// `action` marks elements edited in an editor, e.g. JOSM writes `action="modify"`. It is only
+/// supported by the xml format, the o5m and json formats drop it.
+///
+/// `timestamp` is the time of the last edit for elements that have no author information.
+/// When `author` is set its `created` time is used instead and this field is ignored.
+///
+/// The o5m format can't store a timestamp without author information, it is written as change
+/// set 0 with an anonymous user. The o5m reader maps such author information back to a
+/// `timestamp`, so an author with change set 0, uid 0 and no user does not survive o5m.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
 pub struct Meta {
     pub tags: Vec<Tag>,
     pub version: Option<u32>,
     pub author: Option<AuthorInformation>,
+    pub timestamp: Option<TimeStamp>,
+    pub visible: Option<bool>,
+    pub action: Option<Action>,
 }
 
 /// Author information is used to identify what nodes, ways and relation a specific user has
@@ -71,6 +91,139 @@ pub enum RelationMember {
     Relation(i64, RelationRole),
 }
 
+/// Borrowed node, way or relation. Yielded when iterating over all elements in an [`Osm`] map.
+///
+/// [`Osm`]: ../struct.Osm.html
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Element<'a> {
+    Node(&'a Node),
+    Way(&'a Way),
+    Relation(&'a Relation),
+}
+
+impl<'a> Element<'a> {
+    /// Meta data of the borrowed element.
+    pub fn meta(&self) -> &'a Meta {
+        match self {
+            Element::Node(node) => &node.meta,
+            Element::Way(way) => &way.meta,
+            Element::Relation(rel) => &rel.meta,
+        }
+    }
+
+    /// Reference to the borrowed element by type and id.
+    pub fn element_ref(&self) -> ElementRef {
+        match self {
+            Element::Node(node) => ElementRef::Node(node.id),
+            Element::Way(way) => ElementRef::Way(way.id),
+            Element::Relation(rel) => ElementRef::Relation(rel.id),
+        }
+    }
+}
+
+/// Reference to an element by type and id, used when creating relations with the
+/// [`OsmBuilder`]. Formatted and parsed as the type letter followed by the id, e.g. `w12345`.
+///
+/// [`OsmBuilder`]: ../struct.OsmBuilder.html
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum ElementRef {
+    Node(i64),
+    Way(i64),
+    Relation(i64),
+}
+
+/// The intended change of an element, as in the `action` attribute of xml files written by editors
+/// and the blocks of [`osmChange`] files.
+///
+/// [`osmChange`]: https://wiki.openstreetmap.org/wiki/OsmChange
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Action {
+    Create,
+    Modify,
+    Delete,
+}
+
+impl ElementRef {
+    /// Create a relation member referring to this element with the given role.
+    pub fn into_member<R: Into<RelationRole>>(self, role: R) -> RelationMember {
+        match self {
+            ElementRef::Node(id) => RelationMember::Node(id, role.into()),
+            ElementRef::Way(id) => RelationMember::Way(id, role.into()),
+            ElementRef::Relation(id) => RelationMember::Relation(id, role.into()),
+        }
+    }
+}
+
+impl Display for ElementRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementRef::Node(id) => write!(f, "n{}", id),
+            ElementRef::Way(id) => write!(f, "w{}", id),
+            ElementRef::Relation(id) => write!(f, "r{}", id),
+        }
+    }
+}
+
+impl FromStr for ElementRef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::ParseError,
+                Some(format!("Invalid element reference '{}'.", s)),
+            )
+        };
+
+        let mut chars = s.chars();
+        let kind = chars.next().ok_or_else(invalid)?;
+        let id = chars.as_str().parse().map_err(|_| invalid())?;
+        match kind {
+            'n' => Ok(ElementRef::Node(id)),
+            'w' => Ok(ElementRef::Way(id)),
+            'r' => Ok(ElementRef::Relation(id)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = Error;
+
+    /// Parse the value of an `action` attribute.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(Action::Create),
+            "modify" => Ok(Action::Modify),
+            "delete" => Ok(Action::Delete),
+            _ => Err(Error::new(
+                ErrorKind::ParseError,
+                Some(format!("Invalid action '{}'.", s)),
+            )),
+        }
+    }
+}
+
+impl Action {
+    /// The value of the `action` attribute.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Create => "create",
+            Action::Modify => "modify",
+            Action::Delete => "delete",
+        }
+    }
+}
+
+impl Tag {
+    pub fn new<K: Into<String>, V: Into<String>>(key: K, value: V) -> Self {
+        Tag {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
 impl From<(String, String)> for Tag {
     fn from((key, value): (String, String)) -> Self {
         Tag { key, value }
@@ -86,7 +239,52 @@ impl From<(&str, &str)> for Tag {
     }
 }
 
+impl Meta {
+    /// Remove tags with duplicate keys. The last tag with each key is kept, so the result is the
+    /// same as if the tags had been inserted in a map one by one.
+    pub fn dedup_tags(&mut self) {
+        let mut keys = HashSet::new();
+        let mut tags: Vec<Tag> = self
+            .tags
+            .drain(..)
+            .rev()
+            .filter(|tag| keys.insert(tag.key.clone()))
+            .collect();
+        tags.reverse();
+        self.tags = tags;
+    }
+
+    /// Get the key of the first tag which has the same key as an earlier tag.
+    pub fn duplicate_tag_key(&self) -> Option<&str> {
+        let mut keys = HashSet::new();
+        self.tags
+            .iter()
+            .find(|tag| !keys.insert(tag.key.as_str()))
+            .map(|tag| tag.key.as_str())
+    }
+
+    /// Get the value of the first tag with the given key.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.key == key)
+            .map(|tag| tag.value.as_str())
+    }
+}
+
 impl RelationMember {
+    pub fn node(id: i64, role: &str) -> Self {
+        RelationMember::Node(id, role.to_owned())
+    }
+
+    pub fn way(id: i64, role: &str) -> Self {
+        RelationMember::Way(id, role.to_owned())
+    }
+
+    pub fn relation(id: i64, role: &str) -> Self {
+        RelationMember::Relation(id, role.to_owned())
+    }
+
     pub fn ref_id(&self) -> i64 {
         match self {
             RelationMember::Node(id, _) => *id,
@@ -95,6 +293,15 @@ impl RelationMember {
         }
     }
 
+    /// The type and id of the member element.
+    pub fn element_ref(&self) -> ElementRef {
+        match self {
+            RelationMember::Node(id, _) => ElementRef::Node(*id),
+            RelationMember::Way(id, _) => ElementRef::Way(*id),
+            RelationMember::Relation(id, _) => ElementRef::Relation(*id),
+        }
+    }
+
     pub fn role(&self) -> &str {
         match self {
             RelationMember::Node(_, role) => role,
@@ -102,6 +309,14 @@ impl RelationMember {
             RelationMember::Relation(_, role) => role,
         }
     }
+
+    pub fn set_role(&mut self, role: &str) {
+        match self {
+            RelationMember::Node(_, r) => *r = role.to_owned(),
+            RelationMember::Way(_, r) => *r = role.to_owned(),
+            RelationMember::Relation(_, r) => *r = role.to_owned(),
+        }
+    }
 }
 
 impl Default for Node {
@@ -114,32 +329,114 @@ impl Default for Node {
     }
 }
 
-impl Default for Way {
-    fn default() -> Self {
-        Way {
-            id: 0,
-            refs: vec![],
-            meta: Default::default(),
-        }
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Element, ElementRef, Meta, Node, RelationMember, Tag, Way};
+    use std::collections::HashMap;
+
+    #[test]
+    fn element_meta_and_ref() {
+        let node = Node {
+            id: 1,
+            coordinate: (1.0, 1.0).into(),
+            meta: Meta {
+                tags: vec![("amenity", "bench").into()],
+                ..Meta::default()
+            },
+        };
+        let way = Way {
+            id: 2,
+            refs: vec![1],
+            meta: Meta::default(),
+        };
+
+        assert_eq!(Element::Node(&node).element_ref(), ElementRef::Node(1));
+        assert_eq!(Element::Node(&node).meta(), &node.meta);
+        assert_eq!(Element::Way(&way).element_ref(), ElementRef::Way(2));
+        assert_eq!(Element::Way(&way).meta(), &way.meta);
     }
-}
 
-impl Default for Relation {
-    fn default() -> Self {
-        Relation {
-            id: 0,
-            members: vec![],
-            meta: Default::default(),
+    #[test]
+    fn action_string_round_trip() {
+        for action in [Action::Create, Action::Modify, Action::Delete] {
+            assert_eq!(action.as_str().parse::<Action>().unwrap(), action);
         }
+
+        let error = "remove".parse::<Action>().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid action 'remove'.");
     }
-}
 
-impl Default for Meta {
-    fn default() -> Self {
-        Meta {
-            tags: vec![],
-            version: None,
-            author: None,
+    #[test]
+    fn element_ref_string_round_trip() {
+        let elements = [
+            ElementRef::Node(12345),
+            ElementRef::Way(12345),
+            ElementRef::Relation(-7),
+        ];
+        let strings: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+        assert_eq!(strings, vec!["n12345", "w12345", "r-7"]);
+
+        for (element, string) in elements.iter().zip(strings) {
+            assert_eq!(string.parse::<ElementRef>().unwrap(), *element);
         }
+
+        assert!("".parse::<ElementRef>().is_err());
+        assert!("x1".parse::<ElementRef>().is_err());
+        assert!("n".parse::<ElementRef>().is_err());
+        assert!("w1.5".parse::<ElementRef>().is_err());
+    }
+
+    #[test]
+    fn relation_member_role() {
+        let mut member = RelationMember::way(10, "inner");
+        assert_eq!(member, RelationMember::Way(10, "inner".to_owned()));
+
+        member.set_role("outer");
+        assert_eq!(member.role(), "outer");
+        assert_eq!(member.ref_id(), 10);
+        assert_eq!(member.element_ref(), ElementRef::Way(10));
+
+        assert_eq!(
+            RelationMember::node(1, "via"),
+            RelationMember::Node(1, "via".to_owned())
+        );
+        assert_eq!(
+            RelationMember::relation(2, ""),
+            RelationMember::Relation(2, "".to_owned())
+        );
+    }
+
+    #[test]
+    fn tag_from_owned_strings() {
+        let mut map = HashMap::new();
+        map.insert("highway".to_owned(), "primary".to_owned());
+
+        let tags: Vec<Tag> = map.into_iter().map(Tag::from).collect();
+        assert_eq!(tags, vec![Tag::new("highway", "primary")]);
+
+        let key = "name".to_owned();
+        let tag = Tag::new(key, String::from("Main street"));
+        assert_eq!(tag.key, "name");
+        assert_eq!(tag.value, "Main street");
+    }
+
+    #[test]
+    fn dedup_tags_keeps_last() {
+        let mut meta = Meta {
+            tags: vec![
+                ("name", "First").into(),
+                ("amenity", "cafe").into(),
+                ("name", "Second").into(),
+            ],
+            ..Meta::default()
+        };
+        assert_eq!(meta.duplicate_tag_key(), Some("name"));
+
+        meta.dedup_tags();
+        assert_eq!(
+            meta.tags,
+            vec![("amenity", "cafe").into(), ("name", "Second").into()]
+        );
+        assert_eq!(meta.duplicate_tag_key(), None);
     }
 }
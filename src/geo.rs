@@ -1,7 +1,46 @@
 //! General geographic data structures.
+mod geohash;
+mod wkt;
+
+pub use self::geohash::geohash_neighbors;
+pub use self::wkt::{from_wkt, polygon_to_wkt, to_wkt};
+use crate::Node;
+use std::fmt::{Display, Formatter};
 use std::ops::{Add, Sub};
 
-const COORD_PRECISION: f64 = 10_000_000.0;
+pub(crate) const COORD_PRECISION: f64 = 10_000_000.0;
+
+/// Earth radius in meters used by [`Coordinate::distance_to`].
+///
+/// [`Coordinate::distance_to`]: struct.Coordinate.html#method.distance_to
+pub const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Semi-major axis of the WGS 84 ellipsoid in meters.
+const WGS84_A: f64 = 6_378_137.0;
+
+/// Flattening of the WGS 84 ellipsoid.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Method used to calculate the distance between two coordinates.
+///
+/// The spherical models use the radius given to [`Coordinate::distance_to_with`], [`Vincenty`]
+/// uses the WGS 84 ellipsoid.
+///
+/// [`Coordinate::distance_to_with`]: struct.Coordinate.html#method.distance_to_with
+///
+/// [`Vincenty`]: #variant.Vincenty
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DistanceModel {
+    /// Great-circle distance using the haversine formula. Accurate for all distances.
+    #[default]
+    Haversine,
+    /// Great-circle distance using the spherical law of cosines. Loses precision for very short
+    /// distances.
+    SphericalCosine,
+    /// Ellipsoidal distance using Vincenty's inverse formula. Falls back to haversine for nearly
+    /// antipodal points where the formula does not converge.
+    Vincenty,
+}
 
 /// Represents a coordinate containing latitude and longitude.
 ///
@@ -23,8 +62,12 @@ const COORD_PRECISION: f64 = 10_000_000.0;
 /// assert_eq!(coordinate.lat, 709500000);
 /// assert_eq!(coordinate.lon, -86700000);
 ///
-/// // You can also use the `Into` trait to construct coordinates.
+/// // You can also use the `Into` trait to construct coordinates. Note that the tuple is
+/// // `(lat, lon)`, unlike WKT and GeoJSON which are `lon lat`.
 /// let coordinate: Coordinate = (70.95, -8.67).into();
+///
+/// // The explicit constructors make the order clear when it matters.
+/// assert_eq!(Coordinate::from_lon_lat(-8.67, 70.95), coordinate);
 /// ```
 ///
 /// [`O5m`]: https://wiki.openstreetmap.org/wiki/O5m#Numbers
@@ -82,6 +125,16 @@ impl Coordinate {
         }
     }
 
+    /// Create a coordinate from latitude and longitude, same as `new`.
+    pub fn from_lat_lon(lat: f64, lon: f64) -> Coordinate {
+        Coordinate::new(lat, lon)
+    }
+
+    /// Create a coordinate from longitude and latitude, i.e. x before y as in WKT and GeoJSON.
+    pub fn from_lon_lat(lon: f64, lat: f64) -> Coordinate {
+        Coordinate::new(lat, lon)
+    }
+
     pub fn lat(self) -> f64 {
         self.lat as f64 / COORD_PRECISION
     }
@@ -89,6 +142,527 @@ impl Coordinate {
     pub fn lon(self) -> f64 {
         self.lon as f64 / COORD_PRECISION
     }
+
+    /// Project to [`UTM`] using the zone of the coordinate's longitude. Returns easting and
+    /// northing in meters, and the zone. Northing is offset by 10 000 000 meters on the southern
+    /// hemisphere.
+    ///
+    /// [`UTM`]: https://en.wikipedia.org/wiki/Universal_Transverse_Mercator_coordinate_system
+    pub fn to_utm(self) -> (f64, f64, u8) {
+        let zone = utm_zone(self.lon());
+        let (easting, northing) = self.to_utm_zone(zone, self.lat() < 0.0);
+        (easting, northing, zone)
+    }
+
+    /// Project to a specific [`UTM`] zone and hemisphere, using the WGS 84 ellipsoid. Returns
+    /// easting and northing in meters.
+    ///
+    /// [`UTM`]: https://en.wikipedia.org/wiki/Universal_Transverse_Mercator_coordinate_system
+    pub fn to_utm_zone(self, zone: u8, south: bool) -> (f64, f64) {
+        const K0: f64 = 0.9996;
+
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let e4 = e2 * e2;
+        let e6 = e4 * e2;
+        let ep2 = e2 / (1.0 - e2);
+
+        let lat = self.lat().to_radians();
+        let lon0 = (zone as f64 * 6.0 - 183.0).to_radians();
+        let (sin, cos, tan) = (lat.sin(), lat.cos(), lat.tan());
+
+        let n = WGS84_A / (1.0 - e2 * sin * sin).sqrt();
+        let t = tan * tan;
+        let c = ep2 * cos * cos;
+        let a = cos * (self.lon().to_radians() - lon0);
+        let m = WGS84_A
+            * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+                - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+                + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+                - (35.0 * e6 / 3072.0) * (6.0 * lat).sin());
+
+        let easting = K0
+            * n
+            * (a + (1.0 - t + c) * a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+            + 500_000.0;
+        let mut northing = K0
+            * (m + n
+                * tan
+                * (a * a / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+        if south {
+            northing += 10_000_000.0;
+        }
+
+        (easting, northing)
+    }
+
+    /// Great-circle distance in meters to another coordinate, using the haversine formula on a
+    /// sphere with radius [`EARTH_RADIUS`]. See [`distance_to_with`] for other distance models
+    /// and radii.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::Coordinate;
+    /// let a = Coordinate::new(0.0, 0.0);
+    /// let b = Coordinate::new(0.0, 1.0);
+    /// assert!((a.distance_to(b) - 111_195.0).abs() < 1.0);
+    /// ```
+    ///
+    /// [`EARTH_RADIUS`]: constant.EARTH_RADIUS.html
+    /// [`distance_to_with`]: #method.distance_to_with
+    pub fn distance_to(self, other: Coordinate) -> f64 {
+        haversine_distance(self, other, EARTH_RADIUS)
+    }
+
+    /// Distance in meters to another coordinate, using the given distance model. The spherical
+    /// models use a sphere with the given `radius` in meters, which allows measuring on other
+    /// bodies than the earth. [`Vincenty`] always uses the WGS 84 ellipsoid and ignores `radius`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::{Coordinate, DistanceModel, EARTH_RADIUS};
+    /// let a = Coordinate::new(0.0, 0.0);
+    /// let b = Coordinate::new(0.0, 1.0);
+    /// let vincenty = a.distance_to_with(b, DistanceModel::Vincenty, EARTH_RADIUS);
+    /// assert!((vincenty - 111_319.5).abs() < 1.0);
+    ///
+    /// // Mars.
+    /// let haversine = a.distance_to_with(b, DistanceModel::Haversine, 3_389_500.0);
+    /// assert!((haversine - 59_158.0).abs() < 1.0);
+    /// ```
+    ///
+    /// [`Vincenty`]: enum.DistanceModel.html#variant.Vincenty
+    pub fn distance_to_with(self, other: Coordinate, model: DistanceModel, radius: f64) -> f64 {
+        match model {
+            DistanceModel::Haversine => haversine_distance(self, other, radius),
+            DistanceModel::SphericalCosine => {
+                let (lat1, lat2) = (self.lat().to_radians(), other.lat().to_radians());
+                let d_lon = (other.lon() - self.lon()).to_radians();
+                let cos = lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * d_lon.cos();
+                radius * cos.clamp(-1.0, 1.0).acos()
+            }
+            // The fallback approximates the ellipsoid, so it uses the earth radius.
+            DistanceModel::Vincenty => {
+                vincenty_distance(self, other).unwrap_or_else(|| self.distance_to(other))
+            }
+        }
+    }
+
+    /// Format as `lat, lon` with exactly `decimals` decimals. At most 7 decimals are used since
+    /// that is the precision of a coordinate.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::Coordinate;
+    /// let coordinate = Coordinate::new(60.675, -17.13625);
+    /// assert_eq!(coordinate.to_decimal_string(3), "60.675, -17.136");
+    /// ```
+    pub fn to_decimal_string(self, decimals: usize) -> String {
+        let decimals = decimals.min(7);
+        format!("{:.*}, {:.*}", decimals, self.lat(), decimals, self.lon())
+    }
+}
+
+/// Great-circle distance in meters between two coordinates on a sphere with the given radius.
+fn haversine_distance(a: Coordinate, b: Coordinate, radius: f64) -> f64 {
+    let (lat1, lat2) = (a.lat().to_radians(), b.lat().to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.lon() - a.lon()).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * radius * h.sqrt().asin()
+}
+
+/// Vincenty's inverse formula on the WGS 84 ellipsoid. Returns `None` if the iteration does not
+/// converge, which happens for nearly antipodal points.
+fn vincenty_distance(a: Coordinate, b: Coordinate) -> Option<f64> {
+    let minor = (1.0 - WGS84_F) * WGS84_A;
+    let l = (b.lon() - a.lon()).to_radians();
+    let u1 = ((1.0 - WGS84_F) * a.lat().to_radians().tan()).atan();
+    let u2 = ((1.0 - WGS84_F) * b.lat().to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            return Some(0.0);
+        }
+
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // On the equator cos2_alpha is 0 and the term is not used.
+        let cos_2sigma_m = if cos2_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+        };
+        let c = WGS84_F / 16.0 * cos2_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos2_alpha));
+
+        let previous = lambda;
+        lambda = l
+            + (1.0 - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))));
+        if lambda.abs() > std::f64::consts::PI {
+            return None;
+        }
+        if (lambda - previous).abs() > 1e-12 {
+            continue;
+        }
+
+        let u_sq = cos2_alpha * (WGS84_A.powi(2) - minor.powi(2)) / minor.powi(2);
+        let big_a =
+            1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+        let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+        let delta_sigma = big_b
+            * sin_sigma
+            * (cos_2sigma_m
+                + big_b / 4.0
+                    * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))
+                        - big_b / 6.0
+                            * cos_2sigma_m
+                            * (-3.0 + 4.0 * sin_sigma.powi(2))
+                            * (-3.0 + 4.0 * cos_2sigma_m.powi(2))));
+        return Some(minor * big_a * (sigma - delta_sigma));
+    }
+    None
+}
+
+/// Formats as `lat, lon` with up to 7 decimals, trailing zeros are trimmed.
+impl Display for Coordinate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {}", format_fixed(self.lat), format_fixed(self.lon))
+    }
+}
+
+/// UTM zone, 1 to 60, of a longitude. The exceptions around Norway and Svalbard are not
+/// considered.
+pub fn utm_zone(lon: f64) -> u8 {
+    let zone = ((lon + 180.0) / 6.0).floor() as i32 + 1;
+    zone.clamp(1, 60) as u8
+}
+
+/// Signed area of a ring using the shoelace formula, with longitude as x and latitude as y. The
+/// area is positive if the ring is counter-clockwise and negative if it is clockwise. The unit is
+/// square degrees, so it is mostly useful for its sign.
+///
+/// The ring may be closed or not, the result is the same.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::geo::{signed_area, Coordinate};
+/// let ring: Vec<Coordinate> = vec![(0.0, 0.0).into(), (0.0, 1.0).into(), (1.0, 1.0).into()];
+/// assert_eq!(signed_area(&ring), 0.5);
+/// ```
+pub fn signed_area(coordinates: &[Coordinate]) -> f64 {
+    let len = coordinates.len();
+    let mut sum = 0.0;
+    for i in 0..len {
+        let a = coordinates[i];
+        let b = coordinates[(i + 1) % len];
+        sum += a.lon() * b.lat() - b.lon() * a.lat();
+    }
+    sum / 2.0
+}
+
+/// True if no two segments of a ring intersect, except adjacent segments at their shared
+/// endpoint. Adjacent segments folding back on each other, and vertices visited twice, make the
+/// ring not simple. Repeated consecutive coordinates are ignored.
+///
+/// The ring may be closed or not, the result is the same. Every pair of segments is compared,
+/// so the time is quadratic in the number of coordinates.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::geo::{is_simple, Coordinate};
+/// let ring = |c: &[(f64, f64)]| -> Vec<Coordinate> { c.iter().map(|c| (*c).into()).collect() };
+///
+/// let square = ring(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]);
+/// let bowtie = ring(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+/// assert!(is_simple(&square));
+/// assert!(!is_simple(&bowtie));
+/// ```
+pub fn is_simple(ring: &[Coordinate]) -> bool {
+    let mut vertices = ring.to_vec();
+    vertices.dedup();
+    if vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+
+    // The two segments of a two point ring are the same line back and forth.
+    let len = vertices.len();
+    if len == 2 {
+        return false;
+    }
+
+    let segment = |i: usize| (vertices[i], vertices[(i + 1) % len]);
+    for i in 0..len {
+        for j in i + 1..len {
+            let (a, b) = segment(i);
+            let (c, d) = segment(j);
+            let intersects = if j == i + 1 {
+                folds_back(b, a, d)
+            } else if i == 0 && j == len - 1 {
+                folds_back(a, b, c)
+            } else {
+                segments_intersect((a, b), (c, d))
+            };
+            if intersects {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// True if the segments `shared`-`a` and `shared`-`b` overlap, i.e. point in the same direction.
+fn folds_back(shared: Coordinate, a: Coordinate, b: Coordinate) -> bool {
+    let dot = (a.lon as i128 - shared.lon as i128) * (b.lon as i128 - shared.lon as i128)
+        + (a.lat as i128 - shared.lat as i128) * (b.lat as i128 - shared.lat as i128);
+    orientation(shared, a, b) == 0 && dot > 0
+}
+
+/// True if two closed segments have at least one point in common.
+fn segments_intersect((a, b): (Coordinate, Coordinate), (c, d): (Coordinate, Coordinate)) -> bool {
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    if o1 != o2 && o3 != o4 {
+        return true;
+    }
+
+    (o1 == 0 && within_bounds(a, b, c))
+        || (o2 == 0 && within_bounds(a, b, d))
+        || (o3 == 0 && within_bounds(c, d, a))
+        || (o4 == 0 && within_bounds(c, d, b))
+}
+
+/// Sign of the cross product of `a`-`b` and `a`-`c`: 1 if counter-clockwise, -1 if clockwise
+/// and 0 if collinear. Computed on the fixed point values to be exact.
+pub(crate) fn orientation(a: Coordinate, b: Coordinate, c: Coordinate) -> i32 {
+    let cross = (b.lon as i128 - a.lon as i128) * (c.lat as i128 - a.lat as i128)
+        - (b.lat as i128 - a.lat as i128) * (c.lon as i128 - a.lon as i128);
+    cross.signum() as i32
+}
+
+/// True if `p` is within the bounding box of `a` and `b`.
+fn within_bounds(a: Coordinate, b: Coordinate, p: Coordinate) -> bool {
+    p.lat >= a.lat.min(b.lat)
+        && p.lat <= a.lat.max(b.lat)
+        && p.lon >= a.lon.min(b.lon)
+        && p.lon <= a.lon.max(b.lon)
+}
+
+/// Centroid of a ring or line. For a closed ring, i.e. first and last coordinate equal, the area
+/// weighted polygon centroid is used, with longitude as x and latitude as y. For open lines and
+/// rings with zero area the average of the vertices is used instead. An empty slice gives
+/// `(0, 0)`.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::geo::{centroid, Coordinate};
+/// let ring: Vec<Coordinate> = vec![
+///     (0.0, 0.0).into(),
+///     (0.0, 2.0).into(),
+///     (2.0, 2.0).into(),
+///     (2.0, 0.0).into(),
+///     (0.0, 0.0).into(),
+/// ];
+/// assert_eq!(centroid(&ring), (1.0, 1.0).into());
+/// ```
+pub fn centroid(coordinates: &[Coordinate]) -> Coordinate {
+    let closed = coordinates.len() > 1 && coordinates.first() == coordinates.last();
+    let vertices = if closed {
+        &coordinates[..coordinates.len() - 1]
+    } else {
+        coordinates
+    };
+
+    let len = vertices.len();
+    if len == 0 {
+        return Coordinate { lat: 0, lon: 0 };
+    }
+
+    // Sums are computed relative to the first vertex. With absolute fixed point values the cross
+    // products are huge compared to their differences and most of the precision cancels out.
+    let origin = vertices[0];
+    let relative = |c: Coordinate| {
+        (
+            (c.lat as i64 - origin.lat as i64) as f64,
+            (c.lon as i64 - origin.lon as i64) as f64,
+        )
+    };
+
+    let (mut lat, mut lon) = (0.0, 0.0);
+    if closed && len > 2 {
+        let mut area = 0.0;
+        for i in 0..len {
+            let (a_lat, a_lon) = relative(vertices[i]);
+            let (b_lat, b_lon) = relative(vertices[(i + 1) % len]);
+            let cross = a_lon * b_lat - b_lon * a_lat;
+            area += cross;
+            lon += (a_lon + b_lon) * cross;
+            lat += (a_lat + b_lat) * cross;
+        }
+
+        if area != 0.0 {
+            return Coordinate {
+                lat: (origin.lat as f64 + lat / (3.0 * area)).round() as i32,
+                lon: (origin.lon as f64 + lon / (3.0 * area)).round() as i32,
+            };
+        }
+    }
+
+    let (mut lat, mut lon) = (0.0, 0.0);
+    for vertex in vertices {
+        let (v_lat, v_lon) = relative(*vertex);
+        lat += v_lat;
+        lon += v_lon;
+    }
+    Coordinate {
+        lat: (origin.lat as f64 + lat / len as f64).round() as i32,
+        lon: (origin.lon as f64 + lon / len as f64).round() as i32,
+    }
+}
+
+/// Smallest circle enclosing all coordinates, using Welzl's algorithm. Returns the center and the
+/// radius in meters.
+///
+/// The circle is computed in a local equirectangular projection centered on the average
+/// latitude, i.e. longitudes are scaled by the cosine of that latitude. The error is small for
+/// areas up to a few hundred kilometers across, but grows with size and closeness to the poles.
+/// An empty slice gives a circle at `(0, 0)` with radius 0.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::geo::{bounding_circle, Coordinate};
+/// let coordinates: Vec<Coordinate> = vec![(0.0, -0.01).into(), (0.0, 0.01).into()];
+/// let (center, radius) = bounding_circle(&coordinates);
+/// assert_eq!(center, (0.0, 0.0).into());
+/// assert!((radius - 1112.0).abs() < 1.0);
+/// ```
+pub fn bounding_circle(coordinates: &[Coordinate]) -> (Coordinate, f64) {
+    if coordinates.is_empty() {
+        return (Coordinate { lat: 0, lon: 0 }, 0.0);
+    }
+
+    let lat0 = coordinates.iter().map(|c| c.lat()).sum::<f64>() / coordinates.len() as f64;
+    let scale = lat0.to_radians().cos();
+    let points: Vec<(f64, f64)> = coordinates
+        .iter()
+        .map(|c| (c.lon() * scale, c.lat()))
+        .collect();
+
+    let mut circle = Circle::from_point(points[0]);
+    for i in 1..points.len() {
+        if circle.contains(points[i]) {
+            continue;
+        }
+        circle = Circle::from_point(points[i]);
+        for j in 0..i {
+            if circle.contains(points[j]) {
+                continue;
+            }
+            circle = Circle::from_diameter(points[i], points[j]);
+            for k in 0..j {
+                if !circle.contains(points[k]) {
+                    circle = Circle::from_triangle(points[i], points[j], points[k]);
+                }
+            }
+        }
+    }
+
+    let (x, y) = circle.center;
+    let meters_per_degree = EARTH_RADIUS.to_radians();
+    let lon = if scale > 0.0 { x / scale } else { x };
+    (Coordinate::new(y, lon), circle.radius * meters_per_degree)
+}
+
+/// Circle in a plane, used by `bounding_circle`.
+#[derive(Copy, Clone)]
+struct Circle {
+    center: (f64, f64),
+    radius: f64,
+}
+
+impl Circle {
+    fn from_point(p: (f64, f64)) -> Self {
+        Circle {
+            center: p,
+            radius: 0.0,
+        }
+    }
+
+    fn from_diameter(a: (f64, f64), b: (f64, f64)) -> Self {
+        let center = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        Circle {
+            center,
+            radius: distance(center, a),
+        }
+    }
+
+    /// Circumscribed circle, falls back to the widest diameter if the points are collinear.
+    fn from_triangle(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Self {
+        let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+        if d == 0.0 {
+            let mut widest = Circle::from_diameter(a, b);
+            for circle in [Circle::from_diameter(a, c), Circle::from_diameter(b, c)].iter() {
+                if circle.radius > widest.radius {
+                    widest = *circle;
+                }
+            }
+            return widest;
+        }
+
+        let (a2, b2, c2) = (
+            a.0 * a.0 + a.1 * a.1,
+            b.0 * b.0 + b.1 * b.1,
+            c.0 * c.0 + c.1 * c.1,
+        );
+        let center = (
+            (a2 * (b.1 - c.1) + b2 * (c.1 - a.1) + c2 * (a.1 - b.1)) / d,
+            (a2 * (c.0 - b.0) + b2 * (a.0 - c.0) + c2 * (b.0 - a.0)) / d,
+        );
+        Circle {
+            center,
+            radius: distance(center, a),
+        }
+    }
+
+    /// Contains check with a small tolerance for rounding errors.
+    fn contains(&self, p: (f64, f64)) -> bool {
+        distance(self.center, p) <= self.radius * (1.0 + 1e-9) + 1e-12
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Format an internal fixed point number as a decimal number without rounding errors.
+fn format_fixed(value: i32) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let value = (value as i64).abs();
+    let precision = COORD_PRECISION as i64;
+    let fraction = format!("{:07}", value % precision);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        format!("{}{}", sign, value / precision)
+    } else {
+        format!("{}{}.{}", sign, value / precision, fraction)
+    }
 }
 
 impl Sub for Coordinate {
@@ -113,6 +687,8 @@ impl Add for Coordinate {
     }
 }
 
+/// Converts a `(lat, lon)` tuple. Note the order: latitude first, unlike WKT and GeoJSON. Use
+/// `Coordinate::from_lon_lat` for `(lon, lat)` data.
 impl From<(f64, f64)> for Coordinate {
     fn from((lat, lon): (f64, f64)) -> Self {
         Coordinate::new(lat, lon)
@@ -138,7 +714,60 @@ impl Boundary {
         }
     }
 
-    /// Expand boundary if necessary to include a coordinate.
+    /// Smallest boundary containing all coordinates. An empty iterator gives the same boundary
+    /// as `inverted()`, i.e. min is larger than max.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::{Boundary, Coordinate};
+    /// let coordinates = vec![Coordinate::new(1.0, 4.0), Coordinate::new(3.0, 2.0)];
+    /// let boundary = Boundary::from_coordinates(coordinates);
+    /// assert_eq!(boundary, Boundary::new((1.0, 2.0), (3.0, 4.0)));
+    /// ```
+    pub fn from_coordinates<I: IntoIterator<Item = Coordinate>>(coordinates: I) -> Self {
+        let mut boundary = Boundary::inverted();
+        for coordinate in coordinates {
+            boundary.expand(coordinate);
+        }
+        boundary
+    }
+
+    /// Smallest boundary containing all nodes. See `from_coordinates`.
+    pub fn from_nodes(nodes: &[Node]) -> Self {
+        Self::from_coordinates(nodes.iter().map(|n| n.coordinate))
+    }
+
+    /// The corners of the boundary as a closed ring in counter-clockwise order, starting and
+    /// ending at min.
+    pub fn to_ring(&self) -> Vec<Coordinate> {
+        vec![
+            self.min,
+            Coordinate {
+                lat: self.min.lat,
+                lon: self.max.lon,
+            },
+            self.max,
+            Coordinate {
+                lat: self.max.lat,
+                lon: self.min.lon,
+            },
+            self.min,
+        ]
+    }
+
+    /// Stop the boundary from expanding, see `expand`. The readers freeze the bounds of a file
+    /// while reading, so that nodes outside of them do not expand the boundary, and thaw it when
+    /// the whole file is read.
+    pub fn freeze(&mut self) {
+        self.freeze = true;
+    }
+
+    /// Let a frozen boundary expand again.
+    pub fn thaw(&mut self) {
+        self.freeze = false;
+    }
+
+    /// Expand boundary if necessary to include a coordinate. A frozen boundary is not changed.
     pub fn expand(&mut self, c: Coordinate) {
         if self.freeze {
             return;
@@ -168,3 +797,275 @@ impl Default for Boundary {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::{
+        bounding_circle, centroid, is_simple, signed_area, utm_zone, vincenty_distance, Boundary,
+        Coordinate, DistanceModel, EARTH_RADIUS,
+    };
+    use crate::Node;
+
+    #[test]
+    fn distance_models_agree() {
+        let stockholm = Coordinate::new(59.3293, 18.0686);
+        let gothenburg = Coordinate::new(57.7089, 11.9746);
+
+        let haversine = stockholm.distance_to(gothenburg);
+        let cosine =
+            stockholm.distance_to_with(gothenburg, DistanceModel::SphericalCosine, EARTH_RADIUS);
+        let vincenty =
+            stockholm.distance_to_with(gothenburg, DistanceModel::Vincenty, EARTH_RADIUS);
+
+        assert!(
+            (haversine - 396_900.0).abs() < 100.0,
+            "haversine {}",
+            haversine
+        );
+        assert!((haversine - cosine).abs() < 0.01);
+        // The ellipsoid differs from the sphere by at most about 0.5 %.
+        assert!((haversine - vincenty).abs() / haversine < 0.005);
+        assert_ne!(haversine, vincenty);
+
+        let doubled =
+            stockholm.distance_to_with(gothenburg, DistanceModel::Haversine, 2.0 * EARTH_RADIUS);
+        assert!((doubled - 2.0 * haversine).abs() < 1e-6);
+    }
+
+    #[test]
+    fn vincenty_edge_cases() {
+        let a = Coordinate::new(10.0, 20.0);
+        assert_eq!(
+            a.distance_to_with(a, DistanceModel::Vincenty, EARTH_RADIUS),
+            0.0
+        );
+
+        // Nearly antipodal, Vincenty does not converge and haversine is used.
+        let a = Coordinate::new(0.0, 0.0);
+        let b = Coordinate::new(0.5, 179.7);
+        assert_eq!(vincenty_distance(a, b), None);
+        let distance = a.distance_to_with(b, DistanceModel::Vincenty, EARTH_RADIUS);
+        assert_eq!(distance, a.distance_to(b));
+    }
+
+    #[test]
+    fn coordinate_display() {
+        assert_eq!(
+            Coordinate::new(60.675, 17.13625).to_string(),
+            "60.675, 17.13625"
+        );
+        assert_eq!(Coordinate::new(-90.0, 180.0).to_string(), "-90, 180");
+        assert_eq!(
+            Coordinate { lat: -1, lon: 123 }.to_string(),
+            "-0.0000001, 0.0000123"
+        );
+    }
+
+    #[test]
+    fn coordinate_to_decimal_string() {
+        let coordinate = Coordinate::new(60.675, 17.13625);
+        assert_eq!(coordinate.to_decimal_string(0), "61, 17");
+        assert_eq!(coordinate.to_decimal_string(10), "60.6750000, 17.1362500");
+    }
+
+    #[test]
+    fn utm_zones() {
+        assert_eq!(utm_zone(-180.0), 1);
+        assert_eq!(utm_zone(-79.4), 17);
+        assert_eq!(utm_zone(3.0), 31);
+        assert_eq!(utm_zone(17.1), 33);
+        assert_eq!(utm_zone(180.0), 60);
+    }
+
+    #[test]
+    fn coordinate_to_utm() {
+        // CN Tower, 17T 630084 4833438.
+        let (easting, northing, zone) = Coordinate::new(43.642567, -79.387139).to_utm();
+        assert_eq!(zone, 17);
+        assert!((easting - 630_084.0).abs() < 1.0, "easting {}", easting);
+        assert!(
+            (northing - 4_833_438.0).abs() < 1.0,
+            "northing {}",
+            northing
+        );
+
+        // Central meridian on the equator.
+        let (easting, northing, zone) = Coordinate::new(0.0, 3.0).to_utm();
+        assert_eq!(zone, 31);
+        assert!((easting - 500_000.0).abs() < 0.001);
+        assert!(northing.abs() < 0.001);
+
+        // Southern hemisphere has false northing.
+        let (_, northing, _) = Coordinate::new(-0.000_001, 3.0).to_utm();
+        assert!((northing - 10_000_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn boundary_from_coordinates() {
+        let coordinates: Vec<Coordinate> = vec![
+            (60.1, 17.5).into(),
+            (-10.0, 20.0).into(),
+            (5.5, -3.25).into(),
+            (0.0, 0.0).into(),
+        ];
+
+        let boundary = Boundary::from_coordinates(coordinates.clone());
+        assert_eq!(boundary, Boundary::new((-10.0, -3.25), (60.1, 20.0)));
+
+        let nodes: Vec<Node> = coordinates
+            .into_iter()
+            .map(|coordinate| Node {
+                coordinate,
+                ..Node::default()
+            })
+            .collect();
+        assert_eq!(Boundary::from_nodes(&nodes), boundary);
+
+        assert_eq!(Boundary::from_coordinates(vec![]), Boundary::inverted());
+    }
+
+    #[test]
+    fn boundary_to_ring() {
+        let boundary = Boundary::new((1.0, 2.0), (3.0, 4.0));
+        let ring = boundary.to_ring();
+
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring.first(), ring.last());
+        assert_eq!(
+            ring,
+            vec![
+                (1.0, 2.0).into(),
+                (1.0, 4.0).into(),
+                (3.0, 4.0).into(),
+                (3.0, 2.0).into(),
+                (1.0, 2.0).into(),
+            ]
+        );
+        assert!(signed_area(&ring) > 0.0);
+    }
+
+    #[test]
+    fn explicit_coordinate_order() {
+        let lat_lon = Coordinate::from_lat_lon(59.3293, 18.0686);
+        let lon_lat = Coordinate::from_lon_lat(59.3293, 18.0686);
+        assert_eq!(lat_lon.lat(), 59.3293);
+        assert_eq!(lat_lon.lon(), 18.0686);
+        assert_eq!(lon_lat.lat, lat_lon.lon);
+        assert_eq!(lon_lat.lon, lat_lon.lat);
+        assert_eq!(Coordinate::from((59.3293, 18.0686)), lat_lon);
+    }
+
+    #[test]
+    fn is_simple_rings() {
+        let ring = |coordinates: &[(f64, f64)]| -> Vec<Coordinate> {
+            coordinates.iter().map(|c| (*c).into()).collect()
+        };
+
+        let square = ring(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+        assert!(is_simple(&square));
+        assert!(is_simple(&square[..4]));
+
+        let bowtie = ring(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0), (0.0, 0.0)]);
+        assert!(!is_simple(&bowtie));
+
+        // Repeated points and collinear vertices are fine.
+        let repeated = ring(&[(0.0, 0.0), (0.0, 0.5), (0.0, 1.0), (0.0, 1.0), (1.0, 0.0)]);
+        assert!(is_simple(&repeated));
+
+        // A vertex touching a non-adjacent segment.
+        let touching = ring(&[(0.0, 0.0), (0.0, 2.0), (2.0, 2.0), (0.0, 1.0), (2.0, 0.0)]);
+        assert!(!is_simple(&touching));
+
+        // Adjacent segments overlapping.
+        let spike = ring(&[(0.0, 0.0), (0.0, 2.0), (0.0, 1.0), (1.0, 0.0)]);
+        assert!(!is_simple(&spike));
+    }
+
+    #[test]
+    fn centroid_of_square() {
+        let square: Vec<Coordinate> = vec![
+            (10.0, 20.0).into(),
+            (10.0, 21.0).into(),
+            (11.0, 21.0).into(),
+            (11.0, 20.0).into(),
+            (10.0, 20.0).into(),
+        ];
+        assert_eq!(centroid(&square), (10.5, 20.5).into());
+
+        // Open line uses the average of the vertices.
+        assert_eq!(
+            centroid(&square[..3]),
+            Coordinate {
+                lat: 103_333_333,
+                lon: 206_666_667
+            }
+        );
+    }
+
+    #[test]
+    fn centroid_of_small_building() {
+        // Irregular building of a few meters in Stockholm.
+        let building: Vec<Coordinate> = [
+            (593_293_000, 180_686_000),
+            (593_293_270, 180_686_100),
+            (593_293_310, 180_686_520),
+            (593_293_120, 180_686_610),
+            (593_293_040, 180_686_380),
+            (593_293_000, 180_686_000),
+        ]
+        .iter()
+        .map(|&(lat, lon)| Coordinate { lat, lon })
+        .collect();
+
+        assert_eq!(
+            centroid(&building),
+            Coordinate {
+                lat: 593_293_157,
+                lon: 180_686_301
+            }
+        );
+    }
+
+    #[test]
+    fn centroid_of_degenerate_ring() {
+        let collinear: Vec<Coordinate> = vec![
+            (0.0, 0.0).into(),
+            (1.0, 1.0).into(),
+            (2.0, 2.0).into(),
+            (0.0, 0.0).into(),
+        ];
+        assert_eq!(centroid(&collinear), (1.0, 1.0).into());
+        assert_eq!(centroid(&[]), Coordinate { lat: 0, lon: 0 });
+    }
+
+    #[test]
+    fn bounding_circle_of_triangle() {
+        // Acute triangle, so the circle is the circumscribed circle.
+        let triangle: Vec<Coordinate> =
+            vec![(0.0, 0.0).into(), (0.0, 0.02).into(), (0.015, 0.01).into()];
+        let (center, radius) = bounding_circle(&triangle);
+
+        // Circumradius of the triangle, from its sides a, b, c and area.
+        let a = triangle[0].distance_to(triangle[1]);
+        let b = triangle[1].distance_to(triangle[2]);
+        let c = triangle[2].distance_to(triangle[0]);
+        let s = (a + b + c) / 2.0;
+        let area = (s * (s - a) * (s - b) * (s - c)).sqrt();
+        let circumradius = a * b * c / (4.0 * area);
+
+        assert!((radius - circumradius).abs() < 1.0, "radius {}", radius);
+        for corner in &triangle {
+            assert!((center.distance_to(*corner) - radius).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn bounding_circle_of_obtuse_triangle() {
+        // The circle is given by the longest side, not the circumscribed circle.
+        let triangle: Vec<Coordinate> =
+            vec![(0.0, -0.01).into(), (0.0, 0.01).into(), (0.001, 0.0).into()];
+        let (center, radius) = bounding_circle(&triangle);
+        assert_eq!(center, (0.0, 0.0).into());
+        assert!((radius - triangle[0].distance_to(triangle[1]) / 2.0).abs() < 1.0);
+    }
+}
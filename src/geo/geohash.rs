@@ -0,0 +1,187 @@
+//! [`Geohash`] encoding and decoding of coordinates.
+//!
+//! [`Geohash`]: https://en.wikipedia.org/wiki/Geohash
+use crate::geo::Coordinate;
+
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+impl Coordinate {
+    /// Encode as a geohash with `precision` characters. A precision of 12 is enough to represent
+    /// a coordinate with about centimeter accuracy.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::Coordinate;
+    /// let coordinate = Coordinate::new(57.64911, 10.40744);
+    /// assert_eq!(coordinate.geohash(11), "u4pruydqqvj");
+    /// ```
+    pub fn geohash(self, precision: usize) -> String {
+        encode(self.lat(), self.lon(), precision)
+    }
+
+    /// Decode a geohash to the center of its cell. Returns `None` if the hash is empty or contains
+    /// invalid characters.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::Coordinate;
+    /// let coordinate = Coordinate::from_geohash("u4pruydqqvj").unwrap();
+    /// assert!((coordinate.lat() - 57.64911).abs() < 0.00001);
+    /// assert!((coordinate.lon() - 10.40744).abs() < 0.00001);
+    /// ```
+    pub fn from_geohash(hash: &str) -> Option<Coordinate> {
+        let (lat, lon, _, _) = decode(hash)?;
+        Some(Coordinate::new(lat, lon))
+    }
+}
+
+/// The 8 neighbors of a geohash cell, with the same precision. The order is north, north east,
+/// east, south east, south, south west, west and north west.
+///
+/// Neighbors wrap around the date line. Near the poles, where there is nothing further north or
+/// south, the cells at the pole are used instead. Returns `None` if the hash is invalid.
+pub fn geohash_neighbors(hash: &str) -> Option<[String; 8]> {
+    let (lat, lon, lat_err, lon_err) = decode(hash)?;
+    let precision = hash.len();
+    let (height, width) = (lat_err * 2.0, lon_err * 2.0);
+
+    let neighbor = |dlat: f64, dlon: f64| {
+        let lat = (lat + dlat * height)
+            .max(-90.0 + lat_err)
+            .min(90.0 - lat_err);
+        let mut lon = lon + dlon * width;
+        if lon > 180.0 {
+            lon -= 360.0;
+        } else if lon < -180.0 {
+            lon += 360.0;
+        }
+        encode(lat, lon, precision)
+    };
+
+    Some([
+        neighbor(1.0, 0.0),
+        neighbor(1.0, 1.0),
+        neighbor(0.0, 1.0),
+        neighbor(-1.0, 1.0),
+        neighbor(-1.0, 0.0),
+        neighbor(-1.0, -1.0),
+        neighbor(0.0, -1.0),
+        neighbor(1.0, -1.0),
+    ])
+}
+
+fn encode(lat: f64, lon: f64, precision: usize) -> String {
+    let mut lat_range = (-90.0, 90.0);
+    let mut lon_range = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+
+    for _ in 0..precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            let (value, range) = if even {
+                (lon, &mut lon_range)
+            } else {
+                (lat, &mut lat_range)
+            };
+
+            let mid = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= mid {
+                index |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+        hash.push(BASE32[index] as char);
+    }
+
+    hash
+}
+
+/// Decode to the center of the cell, and the cell's half height and half width.
+fn decode(hash: &str) -> Option<(f64, f64, f64, f64)> {
+    if hash.is_empty() {
+        return None;
+    }
+
+    let mut lat_range = (-90.0, 90.0);
+    let mut lon_range = (-180.0, 180.0);
+    let mut even = true;
+
+    for c in hash.bytes() {
+        let index = BASE32.iter().position(|b| *b == c.to_ascii_lowercase())?;
+        for bit in (0..5).rev() {
+            let range = if even { &mut lon_range } else { &mut lat_range };
+
+            let mid = (range.0 + range.1) / 2.0;
+            if index >> bit & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+
+    Some((
+        (lat_range.0 + lat_range.1) / 2.0,
+        (lon_range.0 + lon_range.1) / 2.0,
+        (lat_range.1 - lat_range.0) / 2.0,
+        (lon_range.1 - lon_range.0) / 2.0,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::{geohash_neighbors, Coordinate};
+
+    #[test]
+    fn encode_decode() {
+        let coordinate = Coordinate::new(60.6750500, 17.1362500);
+        let hash = coordinate.geohash(12);
+        assert_eq!(hash.len(), 12);
+
+        let decoded = Coordinate::from_geohash(&hash).unwrap();
+        assert!((decoded.lat - coordinate.lat).abs() <= 2, "{:?}", decoded);
+        assert!((decoded.lon - coordinate.lon).abs() <= 3, "{:?}", decoded);
+
+        assert_eq!(
+            Coordinate::new(57.64911, 10.40744).geohash(11),
+            "u4pruydqqvj"
+        );
+        assert_eq!(Coordinate::from_geohash(""), None);
+        assert_eq!(Coordinate::from_geohash("u4a"), None);
+    }
+
+    #[test]
+    fn neighbors() {
+        let neighbors = geohash_neighbors("u4pruydqqvj").unwrap();
+        assert_eq!(
+            neighbors,
+            [
+                "u4pruydqqvm",
+                "u4pruydqqvq",
+                "u4pruydqqvn",
+                "u4pruydqquy",
+                "u4pruydqquv",
+                "u4pruydqquu",
+                "u4pruydqqvh",
+                "u4pruydqqvk",
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbors_wrap_date_line() {
+        let hash = Coordinate::new(0.0, 179.99).geohash(3);
+        let neighbors = geohash_neighbors(&hash).unwrap();
+
+        let east = Coordinate::from_geohash(&neighbors[2]).unwrap();
+        assert!(east.lon() < -178.0);
+        let west = Coordinate::from_geohash(&neighbors[6]).unwrap();
+        assert!(west.lon() > 177.0);
+    }
+}
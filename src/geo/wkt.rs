@@ -0,0 +1,276 @@
+//! Conversion between coordinates and [`WKT`] (well-known text) geometries.
+//!
+//! WKT coordinates are ordered `lon lat`, i.e. x before y.
+//!
+//! [`WKT`]: https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry
+use crate::geo::Coordinate;
+use crate::osm_io::error::{Error, ErrorKind, Result};
+use crate::osm_io::validate_coordinate;
+use crate::{ElementRef, OsmBuilder, Tag};
+
+/// Convert the coordinates of a way to WKT. Closed ways, i.e. ways where the first and last
+/// coordinate are the same, are converted to a `POLYGON`, other ways to a `LINESTRING`.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::geo::{to_wkt, Coordinate};
+/// let line = vec![Coordinate::new(1.0, 2.0), Coordinate::new(3.0, 4.0)];
+/// assert_eq!(to_wkt(&line), "LINESTRING (2.0000000 1.0000000, 4.0000000 3.0000000)");
+/// ```
+pub fn to_wkt(coordinates: &[Coordinate]) -> String {
+    if is_closed(coordinates) {
+        polygon_to_wkt(&[coordinates.to_vec()])
+    } else {
+        format!("LINESTRING {}", ring_to_wkt(coordinates))
+    }
+}
+
+/// Convert a polygon to a WKT `POLYGON`. The first ring is the outer ring, the rest are holes.
+/// The closing coordinate is added to rings that are not closed.
+pub fn polygon_to_wkt(rings: &[Vec<Coordinate>]) -> String {
+    let rings: Vec<String> = rings
+        .iter()
+        .map(|ring| {
+            if is_closed(ring) {
+                ring_to_wkt(ring)
+            } else {
+                let mut ring = ring.clone();
+                ring.extend(ring.first().cloned());
+                ring_to_wkt(&ring)
+            }
+        })
+        .collect();
+
+    format!("POLYGON ({})", rings.join(", "))
+}
+
+/// Parse a WKT `POINT`, `LINESTRING` or `POLYGON` and add it to the builder with `add_point`,
+/// `add_polyline` or `add_polygon`. Returns a reference to the added element.
+///
+/// Polygon rings that are not closed are closed by adding the first coordinate to the end.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::geo::from_wkt;
+/// # use vadeen_osm::{ElementRef, OsmBuilder};
+/// let mut builder = OsmBuilder::default();
+/// let element = from_wkt(&mut builder, "POINT (17.13625 60.675)", vec![("amenity", "cafe")]);
+///
+/// assert_eq!(element.unwrap(), ElementRef::Node(1));
+/// ```
+pub fn from_wkt<T: Into<Tag>>(
+    builder: &mut OsmBuilder,
+    wkt: &str,
+    tags: Vec<T>,
+) -> Result<ElementRef> {
+    let wkt = wkt.trim();
+    let start = wkt
+        .find('(')
+        .ok_or_else(|| parse_error(format!("Missing '(' in WKT '{}'.", wkt)))?;
+    let geometry = wkt[..start].trim().to_ascii_uppercase();
+    let body = wkt[start..].trim();
+
+    match geometry.as_str() {
+        "POINT" => {
+            let mut coordinates = parse_ring(body)?;
+            if coordinates.len() != 1 {
+                return Err(parse_error(
+                    "POINT must have exactly one coordinate.".to_owned(),
+                ));
+            }
+            Ok(ElementRef::Node(
+                builder.add_point(coordinates.pop().unwrap(), tags),
+            ))
+        }
+        "LINESTRING" => Ok(ElementRef::Way(
+            builder.add_polyline(parse_ring(body)?, tags),
+        )),
+        "POLYGON" => {
+            let inner = strip_parentheses(body)?;
+            let mut rings = Vec::new();
+            for ring in split_rings(inner)? {
+                let mut ring = parse_ring(ring)?;
+                if !is_closed(&ring) {
+                    ring.extend(ring.first().cloned());
+                }
+                rings.push(ring);
+            }
+            if rings.is_empty() {
+                return Err(parse_error(
+                    "POLYGON must have at least one ring.".to_owned(),
+                ));
+            }
+            Ok(builder.add_polygon(rings, tags))
+        }
+        _ => Err(parse_error(format!(
+            "Unsupported WKT geometry '{}'.",
+            geometry
+        ))),
+    }
+}
+
+fn is_closed(coordinates: &[Coordinate]) -> bool {
+    coordinates.len() > 1 && coordinates.first() == coordinates.last()
+}
+
+fn ring_to_wkt(coordinates: &[Coordinate]) -> String {
+    let coordinates: Vec<String> = coordinates
+        .iter()
+        .map(|c| format!("{:.7} {:.7}", c.lon(), c.lat()))
+        .collect();
+    format!("({})", coordinates.join(", "))
+}
+
+/// Parse a parenthesized list of coordinates, e.g. `(1 2, 3 4)`.
+fn parse_ring(wkt: &str) -> Result<Vec<Coordinate>> {
+    strip_parentheses(wkt)?
+        .split(',')
+        .map(parse_coordinate)
+        .collect()
+}
+
+fn parse_coordinate(wkt: &str) -> Result<Coordinate> {
+    let numbers = wkt
+        .split_whitespace()
+        .map(|n| {
+            n.parse::<f64>()
+                .map_err(|_| parse_error(format!("Invalid number '{}' in WKT.", n)))
+        })
+        .collect::<Result<Vec<f64>>>()?;
+
+    if numbers.len() != 2 {
+        return Err(parse_error(format!(
+            "Invalid WKT coordinate '{}'.",
+            wkt.trim()
+        )));
+    }
+
+    let (lon, lat) = (numbers[0], numbers[1]);
+    validate_coordinate(lat, lon)?;
+    Ok(Coordinate::from_lon_lat(lon, lat))
+}
+
+fn strip_parentheses(wkt: &str) -> Result<&str> {
+    let wkt = wkt.trim();
+    if wkt.starts_with('(') && wkt.ends_with(')') {
+        Ok(&wkt[1..wkt.len() - 1])
+    } else {
+        Err(parse_error(format!(
+            "Expected parentheses around '{}'.",
+            wkt
+        )))
+    }
+}
+
+/// Split `(1 2, 3 4), (5 6, 7 8)` into the parenthesized rings.
+fn split_rings(wkt: &str) -> Result<Vec<&str>> {
+    let mut rings = Vec::new();
+    let mut start = None;
+    for (i, c) in wkt.char_indices() {
+        match (c, start) {
+            ('(', None) => start = Some(i),
+            (')', Some(s)) => {
+                rings.push(&wkt[s..=i]);
+                start = None;
+            }
+            ('(', Some(_)) | (')', None) => {
+                return Err(parse_error(format!("Unbalanced parentheses in '{}'.", wkt)));
+            }
+            _ => {}
+        }
+    }
+
+    if start.is_some() {
+        return Err(parse_error(format!("Unbalanced parentheses in '{}'.", wkt)));
+    }
+    Ok(rings)
+}
+
+fn parse_error(message: String) -> Error {
+    Error::new(ErrorKind::ParseError, Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::{from_wkt, polygon_to_wkt, to_wkt, Coordinate};
+    use crate::osm_io::error::ErrorKind;
+    use crate::{ElementRef, Osm, OsmBuilder, Way};
+
+    fn way_coordinates(osm: &Osm, way: &Way) -> Vec<Coordinate> {
+        way.refs
+            .iter()
+            .map(|id| osm.nodes.iter().find(|n| n.id == *id).unwrap().coordinate)
+            .collect()
+    }
+
+    #[test]
+    fn linestring_round_trip() {
+        let wkt = "LINESTRING (17.1362500 60.6750500, 17.1389800 60.6763100)";
+
+        let mut builder = OsmBuilder::default();
+        let element = from_wkt(&mut builder, wkt, vec![("highway", "path")]).unwrap();
+        let osm = builder.build();
+
+        assert_eq!(element, ElementRef::Way(osm.ways[0].id));
+        assert_eq!(
+            way_coordinates(&osm, &osm.ways[0]),
+            vec![
+                Coordinate::new(60.67505, 17.13625),
+                Coordinate::new(60.67631, 17.13898)
+            ]
+        );
+        assert_eq!(to_wkt(&way_coordinates(&osm, &osm.ways[0])), wkt);
+    }
+
+    #[test]
+    fn polygon_with_hole_round_trip() {
+        let wkt = "POLYGON ((0.0000000 0.0000000, 10.0000000 0.0000000, 10.0000000 10.0000000, \
+                   0.0000000 0.0000000), (1.0000000 1.0000000, 2.0000000 1.0000000, \
+                   2.0000000 2.0000000, 1.0000000 1.0000000))";
+
+        let mut builder = OsmBuilder::default();
+        let element = from_wkt(&mut builder, wkt, vec![("natural", "water")]).unwrap();
+        let osm = builder.build();
+
+        assert_eq!(element, ElementRef::Relation(osm.relations[0].id));
+        assert_eq!(osm.ways.len(), 2);
+
+        let rings: Vec<Vec<Coordinate>> =
+            osm.ways.iter().map(|w| way_coordinates(&osm, w)).collect();
+        assert_eq!(polygon_to_wkt(&rings), wkt);
+    }
+
+    #[test]
+    fn polygon_is_closed() {
+        let mut builder = OsmBuilder::default();
+        from_wkt(
+            &mut builder,
+            "POLYGON ((0 0, 1 0, 1 1))",
+            Vec::<(&str, &str)>::new(),
+        )
+        .unwrap();
+        let osm = builder.build();
+
+        let way = &osm.ways[0];
+        assert_eq!(way.refs.len(), 4);
+        assert_eq!(way.refs.first(), way.refs.last());
+        assert_eq!(
+            to_wkt(&way_coordinates(&osm, way)),
+            "POLYGON ((0.0000000 0.0000000, 1.0000000 0.0000000, 1.0000000 1.0000000, \
+             0.0000000 0.0000000))"
+        );
+    }
+
+    #[test]
+    fn invalid_wkt() {
+        let mut builder = OsmBuilder::default();
+        let error = from_wkt(&mut builder, "POINT (1)", Vec::<(&str, &str)>::new()).unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+
+        let error = from_wkt(&mut builder, "CIRCLE (1 2)", Vec::<(&str, &str)>::new()).unwrap_err();
+        assert_eq!(
+            error.message(),
+            Some(&"Unsupported WKT geometry 'CIRCLE'.".to_owned())
+        );
+    }
+}
@@ -0,0 +1,718 @@
+//! Geometric operations on the elements of a map.
+use crate::geo::{centroid, orientation, signed_area, utm_zone, Boundary, Coordinate};
+use crate::{Osm, Relation, RelationMember, Way};
+use std::collections::{HashMap, HashSet};
+
+/// Easting and northing in meters by node id.
+type UtmPositions = HashMap<i64, (f64, f64)>;
+
+/// Tag values that depend on the direction of a way, as key, value and value after the way is
+/// reversed. Used by [`Osm::reverse_way`].
+///
+/// [`Osm::reverse_way`]: struct.Osm.html#method.reverse_way
+pub const DIRECTIONAL_TAGS: [(&str, &str, &str); 6] = [
+    ("oneway", "yes", "-1"),
+    ("oneway", "-1", "yes"),
+    ("direction", "forward", "backward"),
+    ("direction", "backward", "forward"),
+    ("incline", "up", "down"),
+    ("incline", "down", "up"),
+];
+
+impl Osm {
+    /// Reverse the refs of multipolygon ways so that outer rings are counter-clockwise and inner
+    /// rings are clockwise, i.e. the right hand rule used by GeoJSON.
+    ///
+    /// Rings are identified by their `outer` and `inner` roles in relations tagged with
+    /// `type=multipolygon` or `type=boundary`. The ways of each role are joined into rings, and
+    /// the ways of a ring are reversed as needed to follow the direction of the ring. Rings that
+    /// can not be closed or that have missing nodes are left untouched, as are ways that would
+    /// need different directions in different relations.
+    pub fn normalize_winding(&mut self) {
+        let coordinates = self.node_coordinates();
+        let ways: HashMap<i64, &Way> = self.ways.iter().map(|w| (w.id, w)).collect();
+
+        // Whether each way must be reversed, `None` if relations disagree.
+        let mut reverse: HashMap<i64, Option<bool>> = HashMap::new();
+        for relation in &self.relations {
+            match relation.meta.tag("type") {
+                Some("multipolygon") | Some("boundary") => {}
+                _ => continue,
+            }
+
+            for (role, counter_clockwise) in [("outer", true), ("inner", false)].iter() {
+                let members: Option<Vec<&Way>> = relation
+                    .members
+                    .iter()
+                    .filter_map(|m| match m {
+                        RelationMember::Way(id, r) if r == role => Some(ways.get(id).copied()),
+                        _ => None,
+                    })
+                    .collect();
+                let members = match members {
+                    Some(members) => members,
+                    None => continue,
+                };
+
+                let refs = members.iter().map(|w| w.refs.as_slice()).collect();
+                for ring in assemble_rings(refs).unwrap_or_default() {
+                    let points: Option<Vec<Coordinate>> =
+                        ring.iter().map(|id| coordinates.get(id).cloned()).collect();
+                    let area = match points {
+                        Some(points) => signed_area(&points),
+                        None => continue,
+                    };
+                    let flip = if *counter_clockwise {
+                        area < 0.0
+                    } else {
+                        area > 0.0
+                    };
+
+                    for way in &members {
+                        if let Some(forward) = direction_in_ring(&ring, &way.refs) {
+                            let value = Some(forward == flip);
+                            let entry = reverse.entry(way.id).or_insert(value);
+                            if *entry != value {
+                                *entry = None;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for way in &mut self.ways {
+            if reverse.get(&way.id) == Some(&Some(true)) {
+                way.refs.reverse();
+            }
+        }
+    }
+
+    /// Reverse the direction of a way. Tag values listed in [`DIRECTIONAL_TAGS`] are swapped, as
+    /// are `forward` and `backward` roles of the way in relations. Does nothing if there is no
+    /// way with the id.
+    ///
+    /// [`DIRECTIONAL_TAGS`]: constant.DIRECTIONAL_TAGS.html
+    pub fn reverse_way(&mut self, way_id: i64) {
+        let way = match self.ways.iter_mut().find(|w| w.id == way_id) {
+            Some(way) => way,
+            None => return,
+        };
+
+        way.refs.reverse();
+        for tag in &mut way.meta.tags {
+            let reversed = DIRECTIONAL_TAGS
+                .iter()
+                .find(|(key, value, _)| tag.key == *key && tag.value == *value);
+            if let Some((_, _, reversed)) = reversed {
+                tag.value = (*reversed).to_owned();
+            }
+        }
+
+        for relation in &mut self.relations {
+            for member in &mut relation.members {
+                match member {
+                    RelationMember::Way(id, role) if *id == way_id && role == "forward" => {
+                        *role = "backward".to_owned();
+                    }
+                    RelationMember::Way(id, role) if *id == way_id && role == "backward" => {
+                        *role = "forward".to_owned();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Assemble the `outer` members of a multipolygon relation into one closed ring, ignoring
+    /// `inner` members. The ways may be in any order and direction. Returns `None` if any way or
+    /// node is missing, or if the outer ways do not form exactly one closed ring.
+    pub fn outer_ring(&self, rel: &Relation) -> Option<Vec<Coordinate>> {
+        let ways: HashMap<i64, &Way> = self.ways.iter().map(|w| (w.id, w)).collect();
+        let outer: Option<Vec<&[i64]>> = rel
+            .members
+            .iter()
+            .filter_map(|m| match m {
+                RelationMember::Way(id, role) if role == "outer" => Some(*id),
+                _ => None,
+            })
+            .map(|id| ways.get(&id).map(|w| w.refs.as_slice()))
+            .collect();
+
+        let ring = assemble_ring(outer?)?;
+        let coordinates = self.node_coordinates();
+        ring.iter().map(|id| coordinates.get(id).cloned()).collect()
+    }
+
+    /// Project all nodes to a single [`UTM`] zone, picked from the center of the boundary. The
+    /// hemisphere is also picked from the center. Returns easting and northing in meters by node
+    /// id, together with the zone.
+    ///
+    /// When the map has no boundary, or an inverted one, the [`data_extent`] is used instead.
+    /// Returns `None` if there is neither a boundary nor any nodes.
+    ///
+    /// [`UTM`]: https://en.wikipedia.org/wiki/Universal_Transverse_Mercator_coordinate_system
+    /// [`data_extent`]: #method.data_extent
+    pub fn to_utm(&self) -> Option<(UtmPositions, u8)> {
+        let boundary = match &self.boundary {
+            Some(b) if b.min.lat <= b.max.lat && b.min.lon <= b.max.lon => b.clone(),
+            _ => self.data_extent()?,
+        };
+        let lat = (boundary.min.lat() + boundary.max.lat()) / 2.0;
+        let lon = (boundary.min.lon() + boundary.max.lon()) / 2.0;
+
+        let zone = utm_zone(lon);
+        let projected = self
+            .nodes
+            .iter()
+            .map(|n| (n.id, n.coordinate.to_utm_zone(zone, lat < 0.0)))
+            .collect();
+        Some((projected, zone))
+    }
+
+    /// Length of a way in meters, the sum of the haversine distances between its nodes. Nodes
+    /// missing from the map are skipped.
+    ///
+    /// The nodes are looked up in the whole map on each call. Use [`ResolvedOsm::way_length`]
+    /// when measuring many ways.
+    ///
+    /// [`ResolvedOsm::way_length`]: struct.ResolvedOsm.html#method.way_length
+    pub fn way_length(&self, way: &Way) -> f64 {
+        line_length(&self.way_coordinates(way))
+    }
+
+    /// Perimeter of a way in meters. The refs of a closed way end at its first node, so this is
+    /// the distance around the full ring. For an open way it is the length of the way. Same as
+    /// [`way_length`].
+    ///
+    /// [`way_length`]: #method.way_length
+    pub fn way_perimeter(&self, way: &Way) -> f64 {
+        self.way_length(way)
+    }
+
+    /// Centroid of a way, see [`geo::centroid`]. Nodes missing from the map are skipped.
+    ///
+    /// [`geo::centroid`]: geo/fn.centroid.html
+    pub fn way_centroid(&self, way: &Way) -> Coordinate {
+        centroid(&self.way_coordinates(way))
+    }
+
+    /// Smallest boundary containing the nodes of a way, or `None` if none of its nodes are in the
+    /// map. Nodes missing from the map are skipped.
+    ///
+    /// Like `way_length` this looks up the nodes on each call. When culling many ways, use
+    /// [`ResolvedOsm::way_bounds`] instead.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// # use vadeen_osm::geo::Boundary;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_polyline(vec![(1.0, 4.0), (3.0, 2.0)], vec![("highway", "primary")]);
+    ///
+    /// let osm = builder.build();
+    /// let bounds = osm.way_bounds(&osm.ways[0]);
+    /// assert_eq!(bounds, Some(Boundary::new((1.0, 2.0), (3.0, 4.0))));
+    /// ```
+    ///
+    /// [`ResolvedOsm::way_bounds`]: struct.ResolvedOsm.html#method.way_bounds
+    pub fn way_bounds(&self, way: &Way) -> Option<Boundary> {
+        line_bounds(&self.way_coordinates(way))
+    }
+
+    /// Smallest boundary containing all nodes of the map, or `None` if the map has no nodes.
+    ///
+    /// Unlike the `boundary` field, which is the boundary declared in the file that was read,
+    /// this is always the extent of the data. An extract can have nodes outside its declared
+    /// boundary, since ways crossing the boundary are usually kept whole.
+    pub fn data_extent(&self) -> Option<Boundary> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        Some(Boundary::from_nodes(&self.nodes))
+    }
+
+    /// Iterate over all ways together with the coordinates of their nodes. Ways referring to
+    /// nodes missing from the map are skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    ///
+    /// let osm = builder.build();
+    /// for (way, coordinates) in osm.ways_with_geometry() {
+    ///     assert_eq!(way.refs.len(), coordinates.len());
+    /// }
+    /// ```
+    pub fn ways_with_geometry(&self) -> impl Iterator<Item = (&Way, Vec<Coordinate>)> + '_ {
+        let coordinates = self.node_coordinates();
+        self.ways.iter().filter_map(move |way| {
+            let geometry: Option<Vec<Coordinate>> = way
+                .refs
+                .iter()
+                .map(|id| coordinates.get(id).cloned())
+                .collect();
+            Some((way, geometry?))
+        })
+    }
+
+    /// Find ways accepted by `way_filter` that cross each other without a shared node at the
+    /// crossing, e.g. a road crossing a river without a bridge. Returns the ids of the two ways
+    /// and the crossing coordinate, for each crossing. Segments that only touch or overlap are
+    /// not considered crossing.
+    ///
+    /// Segments are intersected in the plane with longitude as x and latitude as y. Pairs of ways
+    /// whose boundaries do not overlap are skipped early.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// let a = builder.add_polyline(vec![(0.0, -1.0), (0.0, 1.0)], vec![("highway", "primary")]);
+    /// let b = builder.add_polyline(vec![(-1.0, 0.0), (1.0, 0.0)], vec![("waterway", "river")]);
+    ///
+    /// let osm = builder.build();
+    /// assert_eq!(osm.find_way_crossings(|_| true), vec![(a, b, (0.0, 0.0).into())]);
+    /// ```
+    pub fn find_way_crossings<F: Fn(&Way) -> bool>(
+        &self,
+        way_filter: F,
+    ) -> Vec<(i64, i64, Coordinate)> {
+        let ways: Vec<(&Way, Vec<Coordinate>, Boundary)> = self
+            .ways_with_geometry()
+            .filter(|(way, _)| way_filter(way))
+            .map(|(way, coordinates)| {
+                let boundary = Boundary::from_coordinates(coordinates.iter().cloned());
+                (way, coordinates, boundary)
+            })
+            .collect();
+
+        let mut crossings = Vec::new();
+        for (i, (a, a_coordinates, a_boundary)) in ways.iter().enumerate() {
+            for (b, b_coordinates, b_boundary) in &ways[i + 1..] {
+                if !overlaps(a_boundary, b_boundary) {
+                    continue;
+                }
+
+                for (s, p) in a_coordinates.windows(2).enumerate() {
+                    for (t, q) in b_coordinates.windows(2).enumerate() {
+                        let shared = a.refs[s..s + 2]
+                            .iter()
+                            .any(|id| b.refs[t..t + 2].contains(id));
+                        if shared {
+                            continue;
+                        }
+
+                        if let Some(c) = segment_intersection(p[0], p[1], q[0], q[1]) {
+                            crossings.push((a.id, b.id, c));
+                        }
+                    }
+                }
+            }
+        }
+        crossings
+    }
+
+    /// Coordinates of the nodes of a way, nodes missing from the map are skipped. Only the nodes
+    /// of the way are indexed, not the whole map.
+    fn way_coordinates(&self, way: &Way) -> Vec<Coordinate> {
+        let refs: HashSet<i64> = way.refs.iter().cloned().collect();
+        let coordinates: HashMap<i64, Coordinate> = self
+            .nodes
+            .iter()
+            .filter(|n| refs.contains(&n.id))
+            .map(|n| (n.id, n.coordinate))
+            .collect();
+        way.refs
+            .iter()
+            .filter_map(|id| coordinates.get(id).cloned())
+            .collect()
+    }
+
+    /// Coordinates of all nodes by id.
+    pub(crate) fn node_coordinates(&self) -> HashMap<i64, Coordinate> {
+        self.nodes.iter().map(|n| (n.id, n.coordinate)).collect()
+    }
+}
+
+/// Sum of the haversine distances between consecutive coordinates, in meters.
+pub(crate) fn line_length(coordinates: &[Coordinate]) -> f64 {
+    coordinates.windows(2).map(|w| w[0].distance_to(w[1])).sum()
+}
+
+/// Smallest boundary containing the coordinates, `None` if there are none.
+pub(crate) fn line_bounds(coordinates: &[Coordinate]) -> Option<Boundary> {
+    if coordinates.is_empty() {
+        return None;
+    }
+    Some(Boundary::from_coordinates(coordinates.iter().cloned()))
+}
+
+/// Join ways end to end into a single closed ring of node ids, reversing ways where needed.
+/// Returns `None` unless all ways are used and the result is closed.
+fn assemble_ring(mut ways: Vec<&[i64]>) -> Option<Vec<i64>> {
+    let ring = take_ring(&mut ways)?;
+    if ways.is_empty() {
+        Some(ring)
+    } else {
+        None
+    }
+}
+
+/// Join ways end to end into closed rings of node ids, reversing ways where needed. Returns
+/// `None` unless every way is part of a closed ring.
+pub(crate) fn assemble_rings(mut ways: Vec<&[i64]>) -> Option<Vec<Vec<i64>>> {
+    let mut rings = Vec::new();
+    while !ways.is_empty() {
+        rings.push(take_ring(&mut ways)?);
+    }
+    Some(rings)
+}
+
+/// Remove the first way and the ways joined to it until the ring is closed. Returns `None` if
+/// the ring can not be closed.
+fn take_ring(ways: &mut Vec<&[i64]>) -> Option<Vec<i64>> {
+    if ways.is_empty() {
+        return None;
+    }
+
+    let mut ring = ways.remove(0).to_vec();
+    while ring.len() < 2 || ring.first() != ring.last() {
+        let end = *ring.last()?;
+        let next = ways
+            .iter()
+            .position(|w| w.first() == Some(&end) || w.last() == Some(&end))?;
+        let way = ways.remove(next);
+        if way.first() == Some(&end) {
+            ring.extend_from_slice(&way[1..]);
+        } else {
+            ring.extend(way.iter().rev().skip(1));
+        }
+    }
+
+    if ring.len() >= 4 {
+        Some(ring)
+    } else {
+        None
+    }
+}
+
+/// True if the way follows the direction of the closed ring, false if it goes against it and
+/// `None` if the way is not part of the ring.
+fn direction_in_ring(ring: &[i64], refs: &[i64]) -> Option<bool> {
+    let (first, second) = (*refs.first()?, *refs.get(1)?);
+    ring.windows(2).find_map(|pair| match (pair[0], pair[1]) {
+        (a, b) if a == first && b == second => Some(true),
+        (a, b) if a == second && b == first => Some(false),
+        _ => None,
+    })
+}
+
+/// True if the boundaries overlap or touch.
+fn overlaps(a: &Boundary, b: &Boundary) -> bool {
+    a.min.lat <= b.max.lat
+        && b.min.lat <= a.max.lat
+        && a.min.lon <= b.max.lon
+        && b.min.lon <= a.max.lon
+}
+
+/// Intersection point of segment `a1`-`a2` and segment `b1`-`b2`, if they properly cross each
+/// other. Orientations are calculated exactly on the internal fixed point values.
+fn segment_intersection(
+    a1: Coordinate,
+    a2: Coordinate,
+    b1: Coordinate,
+    b2: Coordinate,
+) -> Option<Coordinate> {
+    let (d1, d2) = (orientation(b1, b2, a1), orientation(b1, b2, a2));
+    let (d3, d4) = (orientation(a1, a2, b1), orientation(a1, a2, b2));
+    if d1 * d2 >= 0 || d3 * d4 >= 0 {
+        return None;
+    }
+
+    let (ax, ay) = (a1.lon as f64, a1.lat as f64);
+    let (rx, ry) = (a2.lon as f64 - ax, a2.lat as f64 - ay);
+    let (sx, sy) = (b2.lon as f64 - b1.lon as f64, b2.lat as f64 - b1.lat as f64);
+    let t = ((b1.lon as f64 - ax) * sy - (b1.lat as f64 - ay) * sx) / (rx * sy - ry * sx);
+    Some(Coordinate {
+        lat: (ay + t * ry).round() as i32,
+        lon: (ax + t * rx).round() as i32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::assemble_ring;
+    use crate::geo::{signed_area, Coordinate};
+    use crate::{ElementRef, Meta, OsmBuilder, Way};
+
+    const NO_TAGS: Vec<(&str, &str)> = Vec::new();
+
+    #[test]
+    fn normalize_winding_of_ring_with_open_ways() {
+        let mut builder = OsmBuilder::default();
+
+        // A clockwise ring made of two open ways. The first way follows the ring and must be
+        // reversed, the second goes against it and must be kept.
+        let first = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], NO_TAGS);
+        let second = builder.add_polyline(vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0)], NO_TAGS);
+        builder.add_relation(
+            vec![
+                (ElementRef::Way(first), "outer"),
+                (ElementRef::Way(second), "outer"),
+            ],
+            vec![("type", "multipolygon")],
+        );
+
+        let mut osm = builder.build();
+        let first_refs = osm.ways[0].refs.clone();
+        let second_refs = osm.ways[1].refs.clone();
+
+        osm.normalize_winding();
+
+        assert_eq!(
+            osm.ways[0].refs,
+            first_refs.into_iter().rev().collect::<Vec<_>>()
+        );
+        assert_eq!(osm.ways[1].refs, second_refs);
+
+        let ring = assemble_ring(vec![&osm.ways[0].refs, &osm.ways[1].refs]).unwrap();
+        let coordinates = osm.node_coordinates();
+        let points: Vec<_> = ring.iter().map(|id| coordinates[id]).collect();
+        assert!(signed_area(&points) > 0.0);
+        assert_eq!(ring[..3], osm.ways[0].refs[..]);
+    }
+
+    #[test]
+    fn normalize_winding() {
+        let mut builder = OsmBuilder::default();
+
+        // Clockwise outer and counter-clockwise inner, both must be reversed.
+        let polygon = builder.add_polygon(
+            vec![
+                vec![
+                    (0.0, 0.0),
+                    (10.0, 0.0),
+                    (10.0, 10.0),
+                    (0.0, 10.0),
+                    (0.0, 0.0),
+                ],
+                vec![(1.0, 1.0), (1.0, 2.0), (2.0, 2.0), (1.0, 1.0)],
+            ],
+            vec![("natural", "water")],
+        );
+        assert!(matches!(polygon, ElementRef::Relation(_)));
+
+        // Not part of a multipolygon, must not be reversed.
+        builder.add_polygon(
+            vec![vec![(20.0, 20.0), (30.0, 20.0), (30.0, 30.0), (20.0, 20.0)]],
+            vec![("building", "yes")],
+        );
+
+        let mut osm = builder.build();
+        let outer = osm.ways[0].refs.clone();
+        let inner = osm.ways[1].refs.clone();
+        let building = osm.ways[2].refs.clone();
+
+        osm.normalize_winding();
+
+        let coordinates = osm.node_coordinates();
+        let area = |refs: &Vec<i64>| {
+            let ring: Vec<_> = refs.iter().map(|id| coordinates[id]).collect();
+            signed_area(&ring)
+        };
+
+        assert_eq!(
+            osm.ways[0].refs,
+            outer.into_iter().rev().collect::<Vec<_>>()
+        );
+        assert!(area(&osm.ways[0].refs) > 0.0);
+        assert_eq!(
+            osm.ways[1].refs,
+            inner.into_iter().rev().collect::<Vec<_>>()
+        );
+        assert!(area(&osm.ways[1].refs) < 0.0);
+        assert_eq!(osm.ways[2].refs, building);
+    }
+
+    #[test]
+    fn osm_to_utm() {
+        let mut builder = OsmBuilder::default();
+        let west = builder.add_point((60.0, 17.9), Vec::<(&str, &str)>::new());
+        let east = builder.add_point((60.0, 18.1), Vec::<(&str, &str)>::new());
+
+        let osm = builder.build();
+        let (projected, zone) = osm.to_utm().unwrap();
+        assert_eq!(zone, 34);
+
+        // Both nodes are projected in zone 34, even though the west node is in zone 33.
+        let west_coordinate = osm.nodes[0].coordinate;
+        assert_eq!(west_coordinate.to_utm().2, 33);
+        assert_eq!(projected[&west], west_coordinate.to_utm_zone(34, false));
+
+        let (west_easting, _) = projected[&west];
+        let (east_easting, _) = projected[&east];
+        assert!((east_easting - west_easting - 11_140.0).abs() < 50.0);
+    }
+
+    #[test]
+    fn osm_to_utm_without_boundary() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point((60.0, 18.1), Vec::<(&str, &str)>::new());
+
+        let mut osm = builder.build();
+        osm.boundary = None;
+        let (_, zone) = osm.to_utm().unwrap();
+        assert_eq!(zone, 34);
+
+        osm.nodes.clear();
+        assert!(osm.to_utm().is_none());
+    }
+
+    #[test]
+    fn way_perimeter() {
+        let a: Coordinate = (0.0, 0.0).into();
+        let b: Coordinate = (0.0, 1.0).into();
+        let c: Coordinate = (1.0, 0.0).into();
+
+        let mut builder = OsmBuilder::default();
+        let triangle = builder.add_polyline(vec![a, b, c, a], vec![("area", "yes")]);
+        let line = builder.add_polyline(vec![a, b, c], vec![("highway", "primary")]);
+
+        let osm = builder.build();
+        let sides = a.distance_to(b) + b.distance_to(c) + c.distance_to(a);
+        let perimeter = osm.way_perimeter(&osm.ways[0]);
+        assert_eq!(osm.ways[0].id, triangle);
+        assert!((perimeter - sides).abs() < 1e-6);
+        assert!((osm.way_length(&osm.ways[0]) - sides).abs() < 1e-6);
+
+        assert_eq!(osm.ways[1].id, line);
+        assert_eq!(
+            osm.way_perimeter(&osm.ways[1]),
+            osm.way_length(&osm.ways[1])
+        );
+        assert!(osm.way_length(&osm.ways[1]) < sides);
+    }
+
+    #[test]
+    fn way_centroid() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(
+            vec![(0.0, 0.0), (0.0, 4.0), (2.0, 4.0), (2.0, 0.0), (0.0, 0.0)],
+            vec![("building", "yes")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(osm.way_centroid(&osm.ways[0]), (1.0, 2.0).into());
+    }
+
+    #[test]
+    fn ways_with_geometry() {
+        let mut builder = OsmBuilder::default();
+        let line = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], vec![("highway", "primary")]);
+        let ring = builder.add_polyline(
+            vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.0, 0.0)],
+            vec![("building", "yes")],
+        );
+
+        let mut osm = builder.build();
+        osm.add_way(Way {
+            id: 100,
+            refs: vec![1, 99],
+            meta: Meta::default(),
+        });
+
+        let geometries: Vec<_> = osm
+            .ways_with_geometry()
+            .map(|(way, coordinates)| (way.id, coordinates.len()))
+            .collect();
+        assert_eq!(geometries, vec![(line, 2), (ring, 4)]);
+    }
+
+    #[test]
+    fn find_way_crossings() {
+        let mut builder = OsmBuilder::default();
+        let road = builder.add_polyline(
+            vec![(0.0, 0.0), (2.0, 2.0), (4.0, 0.0)],
+            vec![("highway", "primary")],
+        );
+        let river = builder.add_polyline(vec![(1.0, 0.0), (1.0, 4.0)], vec![("waterway", "river")]);
+
+        // Connected to the road through a shared node, not a crossing.
+        builder.add_polyline(vec![(2.0, 2.0), (3.0, 3.0)], vec![("highway", "service")]);
+
+        // Crosses the river, but is filtered out.
+        builder.add_polyline(vec![(0.0, 3.0), (2.0, 3.0)], vec![("bridge", "yes")]);
+
+        let osm = builder.build();
+        let crossings = osm.find_way_crossings(|w| w.meta.tag("bridge").is_none());
+        assert_eq!(crossings, vec![(road, river, (1.0, 1.0).into())]);
+    }
+
+    #[test]
+    fn reverse_way() {
+        let mut builder = OsmBuilder::default();
+        let way = builder.add_polyline(
+            vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)],
+            vec![("highway", "residential"), ("oneway", "yes")],
+        );
+        builder.add_relation(
+            vec![(ElementRef::Way(way), "forward")],
+            vec![("type", "route")],
+        );
+
+        let mut osm = builder.build();
+        osm.reverse_way(way);
+        assert_eq!(osm.ways[0].refs, vec![3, 2, 1]);
+        assert_eq!(osm.ways[0].meta.tag("oneway"), Some("-1"));
+        assert_eq!(osm.ways[0].meta.tag("highway"), Some("residential"));
+        assert_eq!(osm.relations[0].members[0].role(), "backward");
+
+        osm.reverse_way(way);
+        assert_eq!(osm.ways[0].refs, vec![1, 2, 3]);
+        assert_eq!(osm.ways[0].meta.tag("oneway"), Some("yes"));
+    }
+
+    #[test]
+    fn outer_ring_from_two_ways() {
+        let mut builder = OsmBuilder::default();
+        let north = builder.add_polyline(
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)],
+            Vec::<(&str, &str)>::new(),
+        );
+        // Drawn in the opposite direction, must be reversed to join.
+        let south = builder.add_polyline(
+            vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0)],
+            Vec::<(&str, &str)>::new(),
+        );
+        let hole = builder.add_polyline(
+            vec![(0.2, 0.2), (0.2, 0.3), (0.3, 0.3), (0.2, 0.2)],
+            Vec::<(&str, &str)>::new(),
+        );
+        builder.add_relation(
+            vec![
+                (ElementRef::Way(north), "outer"),
+                (ElementRef::Way(hole), "inner"),
+                (ElementRef::Way(south), "outer"),
+            ],
+            vec![("type", "multipolygon")],
+        );
+
+        let osm = builder.build();
+        let expected: Vec<Coordinate> = vec![
+            (0.0, 0.0).into(),
+            (1.0, 0.0).into(),
+            (1.0, 1.0).into(),
+            (0.0, 1.0).into(),
+            (0.0, 0.0).into(),
+        ];
+        assert_eq!(osm.outer_ring(&osm.relations[0]), Some(expected));
+
+        // A single open way is not a ring.
+        let mut open = osm.relations[0].clone();
+        open.members.pop();
+        assert_eq!(osm.outer_ring(&open), None);
+    }
+}
@@ -9,19 +9,34 @@
 //!
 //! The [`geo`] module contains some more general geographic abstractions used by this crate.
 //!
+//! The [`render`] module can render maps as SVG images for visual debugging.
+//!
 //! [`Open Street Maps`]: https://wiki.openstreetmap.org/wiki/Main_Page
 //! [`Osm`]: struct.Osm.html
 //! [`OsmBuilder`]: struct.OsmBuilder.html
 //! [`osm_io`]: osm_io/index.html
 //! [`geo`]: geo/index.html
+//! [`render`]: render/index.html
 mod element;
 pub mod geo;
+mod geometry;
+mod network;
 pub mod osm_io;
+pub mod render;
+mod resolved;
+mod tag_index;
+mod validation;
 
-use crate::geo::{Boundary, Coordinate};
+use crate::geo::{Boundary, Coordinate, COORD_PRECISION};
 pub use element::*;
+pub use geometry::DIRECTIONAL_TAGS;
+pub use network::*;
+pub use resolved::*;
 use std::cmp::max;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::slice::Iter;
+pub use tag_index::*;
+pub use validation::*;
 
 /// `OsmBuilder` makes it easy to build OSM maps from non OSM data. Polygons, multi polygons,
 /// poly lines and points are all represented as vectors of coordinates.
@@ -68,8 +83,13 @@ use std::collections::HashMap;
 ///
 /// [`Osm`]: struct.Osm.html
 /// [`osm_io`]: osm_io/index.html
+#[derive(Default)]
 pub struct OsmBuilder {
     osm: Osm,
+    dedup_tags: bool,
+    /// Ids of all nodes in the map, so refs can be checked without scanning the nodes. Only built
+    /// when `add_way_refs` is first called.
+    node_ids: Option<HashSet<i64>>,
 }
 
 /// Abstract representation of an OSM map.
@@ -89,37 +109,111 @@ pub struct OsmBuilder {
 /// [`Elements`]: https://wiki.openstreetmap.org/wiki/Elements
 /// [`osm_io`]: osm_io/index.html
 /// [`OsmBuilder`]: struct.OsmBuilder.html
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Osm {
     pub boundary: Option<Boundary>,
     pub nodes: Vec<Node>,
     pub ways: Vec<Way>,
     pub relations: Vec<Relation>,
+    /// Attributes of the `<osm>` element in xml files other than `version` and `generator`, e.g.
+    /// `upload="false"`. Kept so they can be written back, other formats ignore them.
+    pub extra_attributes: HashMap<String, String>,
+    /// Highest id of any element, but at least 0 so that generated ids never collide with the
+    /// negative ids used for new objects.
     max_id: i64,
     node_id_index: HashMap<Coordinate, i64>,
 }
 
 impl OsmBuilder {
+    /// Create a builder that extends an existing map. Ids of added elements continue from the
+    /// highest id in the map, and existing nodes are reused when adding elements at the same
+    /// coordinates.
+    pub fn from_osm(osm: Osm) -> Self {
+        OsmBuilder {
+            osm,
+            dedup_tags: false,
+            node_ids: None,
+        }
+    }
+
+    /// Toggle removal of tags with duplicate keys on added elements. When enabled the value of
+    /// the last tag with a key is kept, at the position where the key was first seen. Disabled by
+    /// default.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default().dedup_tags(true);
+    /// builder.add_point((1.0, 1.0), vec![("name", "Old"), ("amenity", "cafe"), ("name", "New")]);
+    ///
+    /// let osm = builder.build();
+    /// assert_eq!(osm.nodes[0].meta.tags, vec![("name", "New").into(), ("amenity", "cafe").into()]);
+    /// ```
+    pub fn dedup_tags(mut self, dedup: bool) -> Self {
+        self.dedup_tags = dedup;
+        self
+    }
+
+    /// Set a fixed boundary, e.g. the extent of a tile. The boundary is frozen so it is not
+    /// expanded by elements added after this, and stays frozen in the built map.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// # use vadeen_osm::geo::Boundary;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.with_boundary(Boundary::new((0.0, 0.0), (1.0, 1.0)));
+    /// builder.add_point((2.0, 2.0), vec![("amenity", "bench")]);
+    ///
+    /// let boundary = builder.build().boundary.unwrap();
+    /// assert_eq!(boundary.max, (1.0, 1.0).into());
+    /// ```
+    pub fn with_boundary(&mut self, boundary: Boundary) {
+        self.osm.set_boundary(boundary, true);
+    }
+
+    /// Reserve capacity for at least the given number of additional elements. See
+    /// [`Osm::reserve`].
+    ///
+    /// [`Osm::reserve`]: struct.Osm.html#method.reserve
+    pub fn reserve(&mut self, nodes: usize, ways: usize, relations: usize) {
+        self.osm.reserve(nodes, ways, relations);
+    }
+
     pub fn build(self) -> Osm {
         self.osm
     }
 
-    pub fn add_point<C: Into<Coordinate>, T: Into<Tag>>(&mut self, coordinate: C, tags: Vec<T>) {
-        let tags = tags.into_iter().map(T::into).collect();
-        self.add_node(coordinate.into(), tags);
+    /// Reset the builder to an empty map, so it can be reused for building another map.
+    pub fn clear(&mut self) {
+        self.osm = Osm::default();
+        self.node_ids = None;
+    }
+
+    /// Add a point as a node. Returns the id of the node.
+    pub fn add_point<C: Into<Coordinate>, T: Into<Tag>>(
+        &mut self,
+        coordinate: C,
+        tags: Vec<T>,
+    ) -> i64 {
+        let tags = self.create_tags(tags);
+        self.add_node(coordinate.into(), tags)
     }
 
     /// First part is the outer polygon, rest of the parts is inner polygons.
     /// `parts` must not be empty or a panic will occur.
-    pub fn add_polygon<C, T>(&mut self, mut parts: Vec<Vec<C>>, tags: Vec<T>)
+    ///
+    /// Returns a reference to the created element. A polygon without inner polygons is a way,
+    /// otherwise it is a multipolygon relation.
+    pub fn add_polygon<C, T>(&mut self, mut parts: Vec<Vec<C>>, tags: Vec<T>) -> ElementRef
     where
         C: Into<Coordinate>,
         T: Into<Tag>,
     {
         if parts.len() == 1 {
-            self.add_polyline(parts.pop().unwrap(), tags);
+            ElementRef::Way(self.add_polyline(parts.pop().unwrap(), tags))
         } else {
-            self.add_multipolygon(parts, tags);
+            ElementRef::Relation(self.add_multipolygon(parts, tags))
         }
     }
 
@@ -131,14 +225,198 @@ impl OsmBuilder {
         let refs = self.add_nodes(coordinates);
         let id = self.next_id();
         let meta = Meta {
-            tags: tags.into_iter().map(|t| t.into()).collect(),
+            tags: self.create_tags(tags),
             ..Default::default()
         };
         self.osm.add_way(Way { id, refs, meta });
         id
     }
 
-    fn add_multipolygon<C, T>(&mut self, parts: Vec<Vec<C>>, tags: Vec<T>)
+    /// Add a way referring to nodes already in the map, e.g. nodes of a map extended with
+    /// `from_osm`. Unlike `add_polyline`, no nodes are created. Returns the id of the way.
+    ///
+    /// If any of the nodes is not in the map, no way is added and
+    /// `ValidationError::MissingNode` is returned for the first missing node. Its `way_id` is the
+    /// id the way would have been given.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// let a = builder.add_point((1.0, 1.0), vec![("highway", "crossing")]);
+    /// let b = builder.add_point((2.0, 2.0), vec![("highway", "stop")]);
+    /// let way = builder.add_way_refs(vec![a, b], vec![("highway", "residential")]).unwrap();
+    /// assert!(builder.add_way_refs(vec![a, 100], vec![("highway", "service")]).is_err());
+    ///
+    /// let osm = builder.build();
+    /// assert_eq!(osm.ways[0].id, way);
+    /// assert_eq!(osm.ways[0].refs, vec![a, b]);
+    /// ```
+    pub fn add_way_refs<T: Into<Tag>>(
+        &mut self,
+        refs: Vec<i64>,
+        tags: Vec<T>,
+    ) -> Result<i64, ValidationError> {
+        let nodes = &self.osm.nodes;
+        let node_ids = self
+            .node_ids
+            .get_or_insert_with(|| nodes.iter().map(|n| n.id).collect());
+        if let Some(&node_id) = refs.iter().find(|id| !node_ids.contains(id)) {
+            return Err(ValidationError::MissingNode {
+                way_id: self.osm.max_id + 1,
+                node_id,
+            });
+        }
+
+        let id = self.next_id();
+        let meta = Meta {
+            tags: self.create_tags(tags),
+            ..Default::default()
+        };
+        self.osm.add_way(Way { id, refs, meta });
+        Ok(id)
+    }
+
+    /// Same as `add_polyline`, but the first coordinate is added to the end if the poly line is
+    /// not already closed. Returns the id of the way.
+    pub fn add_closed_polyline<C, T>(&mut self, coordinates: Vec<C>, tags: Vec<T>) -> i64
+    where
+        C: Into<Coordinate>,
+        T: Into<Tag>,
+    {
+        let mut coordinates: Vec<Coordinate> = coordinates.into_iter().map(C::into).collect();
+        if let (Some(first), Some(last)) = (coordinates.first(), coordinates.last()) {
+            if first != last {
+                coordinates.push(*first);
+            }
+        }
+        self.add_polyline(coordinates, tags)
+    }
+
+    /// Add a boundary as a closed way, see `Boundary::to_ring`. Returns the id of the way.
+    pub fn add_boundary_box<T: Into<Tag>>(&mut self, boundary: &Boundary, tags: Vec<T>) -> i64 {
+        self.add_polyline(boundary.to_ring(), tags)
+    }
+
+    /// Add a graticule, i.e. a grid of latitude and longitude lines, covering the boundary. Lines
+    /// are placed every `step_deg` degrees starting at the minimum corner of the boundary, and
+    /// each line spans the whole boundary. Lines share nodes where they cross. Returns the ids of
+    /// the ways, latitude lines first.
+    ///
+    /// Panics if `step_deg` is not positive.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::Boundary;
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// let bounds = Boundary::new((0.0, 0.0), (1.0, 2.0));
+    /// let ways = builder.add_graticule(bounds, 1.0, vec![("graticule", "yes")]);
+    ///
+    /// let osm = builder.build();
+    /// assert_eq!(ways.len(), 5);
+    /// assert_eq!(osm.nodes.len(), 6);
+    /// ```
+    pub fn add_graticule<T: Into<Tag>>(
+        &mut self,
+        bounds: Boundary,
+        step_deg: f64,
+        tags: Vec<T>,
+    ) -> Vec<i64> {
+        let step = (step_deg * COORD_PRECISION).round() as i64;
+        assert!(step > 0, "The graticule step must be positive.");
+
+        // Internal fixed point values are used so that crossing lines share coordinates exactly.
+        let grid = |min: i32, max: i32| -> Vec<i32> {
+            (min as i64..=max as i64)
+                .step_by(step as usize)
+                .map(|p| p as i32)
+                .collect()
+        };
+        let with_end = |mut positions: Vec<i32>, max: i32| {
+            if positions.last() != Some(&max) {
+                positions.push(max);
+            }
+            positions
+        };
+        let lats = grid(bounds.min.lat, bounds.max.lat);
+        let lons = grid(bounds.min.lon, bounds.max.lon);
+        let lat_vertices = with_end(lats.clone(), bounds.max.lat);
+        let lon_vertices = with_end(lons.clone(), bounds.max.lon);
+
+        let tags = self.create_tags(tags);
+        let mut lines: Vec<Vec<Coordinate>> = Vec::new();
+        for &lat in &lats {
+            lines.push(
+                lon_vertices
+                    .iter()
+                    .map(|&lon| Coordinate { lat, lon })
+                    .collect(),
+            );
+        }
+        for &lon in &lons {
+            lines.push(
+                lat_vertices
+                    .iter()
+                    .map(|&lat| Coordinate { lat, lon })
+                    .collect(),
+            );
+        }
+
+        let mut ids = Vec::new();
+        for line in lines {
+            let refs = self.add_nodes(line);
+            let id = self.next_id();
+            let meta = Meta {
+                tags: tags.clone(),
+                ..Default::default()
+            };
+            self.osm.add_way(Way { id, refs, meta });
+            ids.push(id);
+        }
+        ids
+    }
+
+    /// Add a relation with members referring to previously added elements, e.g. a route made of
+    /// ways returned by `add_polyline`. Returns the id of the relation.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{ElementRef, OsmBuilder};
+    /// let mut builder = OsmBuilder::default();
+    /// let way = builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    /// let route = builder.add_relation(
+    ///     vec![(ElementRef::Way(way), "forward")],
+    ///     vec![("type", "route"), ("route", "bus")],
+    /// );
+    ///
+    /// let osm = builder.build();
+    /// assert_eq!(osm.relations[0].id, route);
+    /// ```
+    pub fn add_relation<R, T>(&mut self, members: Vec<(ElementRef, R)>, tags: Vec<T>) -> i64
+    where
+        R: Into<String>,
+        T: Into<Tag>,
+    {
+        let members = members
+            .into_iter()
+            .map(|(element, role)| element.into_member(role))
+            .collect();
+        let id = self.next_id();
+        let meta = Meta {
+            tags: self.create_tags(tags),
+            ..Default::default()
+        };
+        self.osm.add_relation(Relation { id, members, meta });
+        id
+    }
+
+    /// Add a multipolygon relation. Each part is added as a way, the first part is the outer
+    /// polygon and the rest are inner polygons. `parts` must not be empty or a panic will occur.
+    /// Returns the id of the relation.
+    ///
+    /// Unlike `add_polygon`, a relation is created even if there is only one part.
+    pub fn add_multipolygon<C, T>(&mut self, parts: Vec<Vec<C>>, tags: Vec<T>) -> i64
     where
         C: Into<Coordinate>,
         T: Into<Tag>,
@@ -152,14 +430,14 @@ impl OsmBuilder {
             polygon_ids.push(id);
         }
 
-        let mut tags: Vec<Tag> = tags.into_iter().map(T::into).collect();
+        let mut tags = self.create_tags(tags);
         tags.push(("type", "multipolygon").into());
 
         let (outer, inner) = polygon_ids.split_first().unwrap();
-        self.add_polygon_relations(*outer, inner, tags);
+        self.add_polygon_relations(*outer, inner, tags)
     }
 
-    fn add_polygon_relations(&mut self, outer: i64, inner: &[i64], tags: Vec<Tag>) {
+    fn add_polygon_relations(&mut self, outer: i64, inner: &[i64], tags: Vec<Tag>) -> i64 {
         let mut members = Vec::new();
         for rel_ref in inner {
             members.push(RelationMember::Way(*rel_ref, "inner".to_owned()));
@@ -169,10 +447,32 @@ impl OsmBuilder {
         let id = self.next_id();
 
         let meta = Meta {
-            tags: tags.into_iter().map(|t| t).collect(),
+            tags: tags.into_iter().collect(),
             ..Default::default()
         };
         self.osm.add_relation(Relation { id, members, meta });
+        id
+    }
+
+    /// Convert tags, removing duplicate keys if enabled.
+    fn create_tags<T: Into<Tag>>(&self, tags: Vec<T>) -> Vec<Tag> {
+        let tags = tags.into_iter().map(T::into);
+        if !self.dedup_tags {
+            return tags.collect();
+        }
+
+        let mut indexes: HashMap<String, usize> = HashMap::new();
+        let mut result: Vec<Tag> = Vec::new();
+        for tag in tags {
+            match indexes.get(&tag.key) {
+                Some(&i) => result[i].value = tag.value,
+                None => {
+                    indexes.insert(tag.key.clone(), result.len());
+                    result.push(tag);
+                }
+            }
+        }
+        result
     }
 
     fn add_nodes<C: Into<Coordinate>>(&mut self, coordinates: Vec<C>) -> Vec<i64> {
@@ -197,6 +497,9 @@ impl OsmBuilder {
             coordinate,
             meta,
         });
+        if let Some(node_ids) = &mut self.node_ids {
+            node_ids.insert(id);
+        }
         id
     }
 
@@ -206,35 +509,554 @@ impl OsmBuilder {
     }
 }
 
-impl Default for OsmBuilder {
-    fn default() -> Self {
-        OsmBuilder {
-            osm: Osm::default(),
-        }
+impl Osm {
+    /// Set the boundary of the map. A frozen boundary is kept as is when nodes are added, like
+    /// the bounds of a file while it is read. Otherwise it is expanded to include added nodes.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::geo::Boundary;
+    /// # use vadeen_osm::{Node, Osm};
+    /// let mut osm = Osm::default();
+    /// osm.set_boundary(Boundary::new((0.0, 0.0), (1.0, 1.0)), false);
+    /// osm.add_node(Node {
+    ///     coordinate: (2.0, 2.0).into(),
+    ///     ..Node::default()
+    /// });
+    /// assert_eq!(osm.boundary.unwrap().max, (2.0, 2.0).into());
+    /// ```
+    pub fn set_boundary(&mut self, mut boundary: Boundary, freeze: bool) {
+        boundary.freeze = freeze;
+        self.boundary = Some(boundary);
     }
-}
 
-impl Osm {
-    /// Add a node to the map, the boundary is expanded to include the node.
+    /// Add a node to the map. The boundary is expanded to include the node, unless the boundary
+    /// is frozen, see `Boundary::freeze`.
     pub fn add_node(&mut self, node: Node) {
         if let Some(boundary) = &mut self.boundary {
             boundary.expand(node.coordinate);
         }
 
         self.max_id = max(self.max_id, node.id);
-        self.node_id_index.insert(node.coordinate.clone(), node.id);
+        self.node_id_index.insert(node.coordinate, node.id);
         self.nodes.push(node);
     }
 
     /// Add a way to the map.
     pub fn add_way(&mut self, way: Way) {
+        self.max_id = max(self.max_id, way.id);
         self.ways.push(way);
     }
 
+    /// Add a relation to the map.
     pub fn add_relation(&mut self, relation: Relation) {
+        self.max_id = max(self.max_id, relation.id);
         self.relations.push(relation);
     }
 
+    /// Iterate over all elements in the map. Nodes are yielded first, then ways and last
+    /// relations.
+    pub fn iter(&self) -> Elements<'_> {
+        Elements {
+            nodes: self.nodes.iter(),
+            ways: self.ways.iter(),
+            relations: self.relations.iter(),
+        }
+    }
+
+    /// Remove tags with duplicate keys on all elements, see `Meta::dedup_tags`.
+    pub fn dedup_all_tags(&mut self) {
+        for meta in self.metas_mut() {
+            meta.dedup_tags();
+        }
+    }
+
+    /// Sort nodes, ways and relations by id, and the tags of each element by key. Maps with the
+    /// same content are written identically after this, regardless of the order elements and
+    /// tags were added in. Tags with the same key keep their relative order.
+    pub fn canonicalize(&mut self) {
+        self.nodes.sort_by_key(|n| n.id);
+        self.ways.sort_by_key(|w| w.id);
+        self.relations.sort_by_key(|r| r.id);
+
+        for meta in self.metas_mut() {
+            meta.tags.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+
+        self.node_id_index = self.nodes.iter().map(|n| (n.coordinate, n.id)).collect();
+    }
+
+    /// A hash of the content of the map: ids, coordinates, tags, node refs and relation members.
+    /// The order of elements and of tags does not affect the fingerprint, the order of refs and
+    /// members does. Meta data other than tags and the boundary are not included.
+    ///
+    /// The hash is FNV-1a, so fingerprints are stable across platforms and can be stored.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+    /// let a = builder.build();
+    ///
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_point((1.0, 1.0), vec![("amenity", "cafe")]);
+    /// let b = builder.build();
+    ///
+    /// assert_ne!(a.fingerprint(), b.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut digests: Vec<u64> = self
+            .iter()
+            .map(|element| {
+                let mut hasher = Fnv64::new();
+                match element {
+                    Element::Node(node) => {
+                        hasher.write(&[0]);
+                        hasher.write_i64(node.id);
+                        hasher.write_i64(node.coordinate.lat as i64);
+                        hasher.write_i64(node.coordinate.lon as i64);
+                    }
+                    Element::Way(way) => {
+                        hasher.write(&[1]);
+                        hasher.write_i64(way.id);
+                        hasher.write_i64(way.refs.len() as i64);
+                        for id in &way.refs {
+                            hasher.write_i64(*id);
+                        }
+                    }
+                    Element::Relation(relation) => {
+                        hasher.write(&[2]);
+                        hasher.write_i64(relation.id);
+                        hasher.write_i64(relation.members.len() as i64);
+                        for member in &relation.members {
+                            let (member_type, id, role) = match member {
+                                RelationMember::Node(id, role) => (0, id, role),
+                                RelationMember::Way(id, role) => (1, id, role),
+                                RelationMember::Relation(id, role) => (2, id, role),
+                            };
+                            hasher.write(&[member_type]);
+                            hasher.write_i64(*id);
+                            hasher.write_str(role);
+                        }
+                    }
+                }
+
+                let mut tags: Vec<&Tag> = element.meta().tags.iter().collect();
+                tags.sort_by(|a, b| (&a.key, &a.value).cmp(&(&b.key, &b.value)));
+                for tag in tags {
+                    hasher.write_str(&tag.key);
+                    hasher.write_str(&tag.value);
+                }
+                hasher.finish()
+            })
+            .collect();
+        digests.sort_unstable();
+
+        let mut hasher = Fnv64::new();
+        for digest in digests {
+            hasher.write(&digest.to_le_bytes());
+        }
+        hasher.finish()
+    }
+
+    /// Rewrite the values of all tags with the given key according to `mapping`. Values not in
+    /// the mapping are left unchanged.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("oneway", "true")]);
+    ///
+    /// let mut mapping = HashMap::new();
+    /// mapping.insert("true".to_owned(), "yes".to_owned());
+    ///
+    /// let mut osm = builder.build();
+    /// osm.canonicalize_values("oneway", &mapping);
+    /// assert_eq!(osm.ways[0].meta.tag("oneway"), Some("yes"));
+    /// ```
+    pub fn canonicalize_values(&mut self, key: &str, mapping: &HashMap<String, String>) {
+        for meta in self.metas_mut() {
+            for tag in meta.tags.iter_mut().filter(|t| t.key == key) {
+                if let Some(value) = mapping.get(&tag.value) {
+                    tag.value = value.clone();
+                }
+            }
+        }
+    }
+
+    /// Add `delta` to the id of every node, way and relation. Way node references and relation
+    /// members are updated to the new ids. A negative delta can be used to move ids into the
+    /// negative range used for new objects.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    ///
+    /// let mut osm = builder.build();
+    /// osm.offset_ids(1000);
+    /// assert_eq!(osm.ways[0].id, 1003);
+    /// assert_eq!(osm.ways[0].refs, vec![1001, 1002]);
+    /// ```
+    pub fn offset_ids(&mut self, delta: i64) {
+        for node in &mut self.nodes {
+            node.id += delta;
+        }
+
+        for way in &mut self.ways {
+            way.id += delta;
+            for node in &mut way.refs {
+                *node += delta;
+            }
+        }
+
+        for relation in &mut self.relations {
+            relation.id += delta;
+            for member in &mut relation.members {
+                match member {
+                    RelationMember::Node(id, _)
+                    | RelationMember::Way(id, _)
+                    | RelationMember::Relation(id, _) => *id += delta,
+                }
+            }
+        }
+
+        self.reindex();
+    }
+
+    /// Keep only the nodes accepted by `f`. References to removed nodes are removed from ways and
+    /// relations.
+    pub fn retain_nodes<F: Fn(&Node) -> bool>(&mut self, f: F) {
+        let mut removed = HashSet::new();
+        self.nodes.retain(|n| {
+            let keep = f(n);
+            if !keep {
+                removed.insert(n.id);
+            }
+            keep
+        });
+        if removed.is_empty() {
+            return;
+        }
+
+        for way in &mut self.ways {
+            way.refs.retain(|id| !removed.contains(id));
+        }
+        self.remove_members(|m| matches!(m, RelationMember::Node(id, _) if removed.contains(id)));
+        self.node_id_index = self.nodes.iter().map(|n| (n.coordinate, n.id)).collect();
+    }
+
+    /// Keep only the ways accepted by `f`. Relation members referring to removed ways are
+    /// removed. Nodes used by removed ways are kept, see [`remove_orphan_nodes`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    /// builder.add_polyline(vec![(3.0, 3.0), (4.0, 4.0)], vec![("power", "line")]);
+    ///
+    /// let mut osm = builder.build();
+    /// osm.retain_ways(|w| w.meta.tag("highway").is_some());
+    /// osm.remove_orphan_nodes();
+    /// assert_eq!(osm.ways.len(), 1);
+    /// assert_eq!(osm.nodes.len(), 2);
+    /// ```
+    ///
+    /// [`remove_orphan_nodes`]: #method.remove_orphan_nodes
+    pub fn retain_ways<F: Fn(&Way) -> bool>(&mut self, f: F) {
+        let mut removed = HashSet::new();
+        self.ways.retain(|w| {
+            let keep = f(w);
+            if !keep {
+                removed.insert(w.id);
+            }
+            keep
+        });
+        self.remove_members(|m| matches!(m, RelationMember::Way(id, _) if removed.contains(id)));
+    }
+
+    /// Keep only the relations accepted by `f`. Members referring to removed relations are
+    /// removed from the remaining relations.
+    pub fn retain_relations<F: Fn(&Relation) -> bool>(&mut self, f: F) {
+        let mut removed = HashSet::new();
+        self.relations.retain(|r| {
+            let keep = f(r);
+            if !keep {
+                removed.insert(r.id);
+            }
+            keep
+        });
+        self.remove_members(
+            |m| matches!(m, RelationMember::Relation(id, _) if removed.contains(id)),
+        );
+    }
+
+    /// Remove nodes without tags that are not used by any way or relation.
+    pub fn remove_orphan_nodes(&mut self) {
+        let mut used: HashSet<i64> = self.ways.iter().flat_map(|w| w.refs.clone()).collect();
+        for relation in &self.relations {
+            for member in &relation.members {
+                if let RelationMember::Node(id, _) = member {
+                    used.insert(*id);
+                }
+            }
+        }
+
+        self.retain_nodes(|n| !n.meta.tags.is_empty() || used.contains(&n.id));
+    }
+
+    /// Relations with the element as a member, in map order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{ElementRef, OsmBuilder};
+    /// let mut builder = OsmBuilder::default();
+    /// let way = builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    /// builder.add_relation(vec![(ElementRef::Way(way), "")], vec![("type", "route")]);
+    ///
+    /// let osm = builder.build();
+    /// let element: ElementRef = format!("w{}", way).parse().unwrap();
+    /// assert_eq!(osm.relations_of(element).len(), 1);
+    /// assert!(osm.relations_of(ElementRef::Node(way)).is_empty());
+    /// ```
+    pub fn relations_of(&self, element: ElementRef) -> Vec<&Relation> {
+        self.relations_with_member(|m| m.element_ref() == element)
+    }
+
+    /// Relations with the node as a member, in map order.
+    pub fn relations_of_node(&self, node_id: i64) -> Vec<&Relation> {
+        self.relations_of(ElementRef::Node(node_id))
+    }
+
+    /// Relations with the way as a member, in map order. Useful to find what refers to a way
+    /// before it is removed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{ElementRef, OsmBuilder};
+    /// let mut builder = OsmBuilder::default();
+    /// let way = builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    /// let route = builder.add_relation(vec![(ElementRef::Way(way), "")], vec![("type", "route")]);
+    ///
+    /// let osm = builder.build();
+    /// let relations = osm.relations_of_way(way);
+    /// assert_eq!(relations.len(), 1);
+    /// assert_eq!(relations[0].id, route);
+    /// ```
+    pub fn relations_of_way(&self, way_id: i64) -> Vec<&Relation> {
+        self.relations_of(ElementRef::Way(way_id))
+    }
+
+    /// Relations with the relation as a member, in map order.
+    pub fn relations_of_relation(&self, relation_id: i64) -> Vec<&Relation> {
+        self.relations_of(ElementRef::Relation(relation_id))
+    }
+
+    /// Relations with any member matching `f`. Each relation is included once, even if it has
+    /// several matching members.
+    fn relations_with_member<F: Fn(&RelationMember) -> bool>(&self, f: F) -> Vec<&Relation> {
+        self.relations
+            .iter()
+            .filter(|r| r.members.iter().any(&f))
+            .collect()
+    }
+
+    /// Meta of all elements: nodes first, then ways and last relations.
+    fn metas(&self) -> impl Iterator<Item = &Meta> {
+        let nodes = self.nodes.iter().map(|n| &n.meta);
+        let ways = self.ways.iter().map(|w| &w.meta);
+        let relations = self.relations.iter().map(|r| &r.meta);
+        nodes.chain(ways).chain(relations)
+    }
+
+    /// Mutable meta of all elements, in the same order as `metas`.
+    fn metas_mut(&mut self) -> impl Iterator<Item = &mut Meta> {
+        let nodes = self.nodes.iter_mut().map(|n| &mut n.meta);
+        let ways = self.ways.iter_mut().map(|w| &mut w.meta);
+        let relations = self.relations.iter_mut().map(|r| &mut r.meta);
+        nodes.chain(ways).chain(relations)
+    }
+
+    /// Remove members matching `f` from all relations.
+    fn remove_members<F: Fn(&RelationMember) -> bool>(&mut self, f: F) {
+        for relation in &mut self.relations {
+            relation.members.retain(|m| !f(m));
+        }
+    }
+
+    /// Number of elements with each version. Elements without a version are counted under `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_point((1.0, 1.0), vec![("power", "tower")]);
+    ///
+    /// let histogram = builder.build().version_histogram();
+    /// assert_eq!(histogram[&None], 1);
+    /// ```
+    pub fn version_histogram(&self) -> BTreeMap<Option<u32>, usize> {
+        let mut histogram = BTreeMap::new();
+        for meta in self.metas() {
+            *histogram.entry(meta.version).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Iterate over the tags of all elements: nodes first, then ways and last relations.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+    /// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+    ///
+    /// let osm = builder.build();
+    /// let keys: Vec<&str> = osm.all_tags().map(|t| t.key.as_str()).collect();
+    /// assert_eq!(keys, vec!["amenity", "highway"]);
+    /// ```
+    pub fn all_tags(&self) -> impl Iterator<Item = &Tag> {
+        self.metas().flat_map(|meta| &meta.tags)
+    }
+
+    /// Number of elements with a tag of each key, sorted by descending count and then by key. An
+    /// element with several tags of the same key is counted once.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+    /// builder.add_point((2.0, 2.0), vec![("amenity", "cafe"), ("name", "Café")]);
+    ///
+    /// let cardinality = builder.build().tag_key_cardinality();
+    /// assert_eq!(cardinality, vec![("amenity".to_owned(), 2), ("name".to_owned(), 1)]);
+    /// ```
+    pub fn tag_key_cardinality(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for meta in self.metas() {
+            let keys: HashSet<&str> = meta.tags.iter().map(|t| t.key.as_str()).collect();
+            for key in keys {
+                *counts.entry(key).or_insert(0) += 1;
+            }
+        }
+
+        let mut cardinality: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(key, count)| (key.to_owned(), count))
+            .collect();
+        cardinality.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        cardinality
+    }
+
+    /// Number of relation members with each role, over all relations. Members without a role
+    /// are counted under the empty string.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_multipolygon(
+    ///     vec![
+    ///         vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (0.0, 0.0)],
+    ///         vec![(1.0, 1.0), (1.0, 2.0), (2.0, 2.0), (1.0, 1.0)],
+    ///     ],
+    ///     vec![("natural", "water")],
+    /// );
+    ///
+    /// let roles = builder.build().relation_roles();
+    /// assert_eq!(roles["outer"], 1);
+    /// assert_eq!(roles["inner"], 1);
+    /// ```
+    pub fn relation_roles(&self) -> HashMap<String, usize> {
+        let mut roles: HashMap<String, usize> = HashMap::new();
+        for member in self.relations.iter().flat_map(|r| &r.members) {
+            *roles.entry(member.role().to_owned()).or_insert(0) += 1;
+        }
+        roles
+    }
+
+    /// Highest node id, `None` if there are no nodes.
+    pub fn max_node_id(&self) -> Option<i64> {
+        self.nodes.iter().map(|n| n.id).max()
+    }
+
+    /// Lowest node id, `None` if there are no nodes.
+    pub fn min_node_id(&self) -> Option<i64> {
+        self.nodes.iter().map(|n| n.id).min()
+    }
+
+    /// Highest way id, `None` if there are no ways.
+    pub fn max_way_id(&self) -> Option<i64> {
+        self.ways.iter().map(|w| w.id).max()
+    }
+
+    /// Lowest way id, `None` if there are no ways.
+    pub fn min_way_id(&self) -> Option<i64> {
+        self.ways.iter().map(|w| w.id).min()
+    }
+
+    /// Highest relation id, `None` if there are no relations.
+    pub fn max_relation_id(&self) -> Option<i64> {
+        self.relations.iter().map(|r| r.id).max()
+    }
+
+    /// Lowest relation id, `None` if there are no relations.
+    pub fn min_relation_id(&self) -> Option<i64> {
+        self.relations.iter().map(|r| r.id).min()
+    }
+
+    /// Total number of nodes, ways and relations in the map.
+    pub fn len(&self) -> usize {
+        self.nodes.len() + self.ways.len() + self.relations.len()
+    }
+
+    /// Returns true if the map has no nodes, ways or relations.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.ways.is_empty() && self.relations.is_empty()
+    }
+
+    /// Rebuild the internal node index and highest id from the current elements. Call this after
+    /// changing `nodes`, `ways` or `relations` directly, so that `find_node_id` and ids generated
+    /// by an `OsmBuilder` created with `OsmBuilder::from_osm` are correct.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{Node, Osm};
+    /// let mut osm = Osm::default();
+    /// osm.nodes.push(Node {
+    ///     id: 5,
+    ///     coordinate: (1.0, 1.0).into(),
+    ///     ..Node::default()
+    /// });
+    /// assert_eq!(osm.find_node_id((1.0, 1.0).into()), None);
+    ///
+    /// osm.reindex();
+    /// assert_eq!(osm.find_node_id((1.0, 1.0).into()), Some(5));
+    /// ```
+    pub fn reindex(&mut self) {
+        let nodes = self.nodes.iter().map(|n| n.id);
+        let ways = self.ways.iter().map(|w| w.id);
+        let relations = self.relations.iter().map(|r| r.id);
+        self.max_id = nodes.chain(ways).chain(relations).fold(0, max);
+        self.node_id_index = self.nodes.iter().map(|n| (n.coordinate, n.id)).collect();
+    }
+
+    /// Reserve capacity for at least the given number of additional nodes, ways and relations,
+    /// to avoid reallocations when adding many elements.
+    pub fn reserve(&mut self, nodes: usize, ways: usize, relations: usize) {
+        self.nodes.reserve(nodes);
+        self.node_id_index.reserve(nodes);
+        self.ways.reserve(ways);
+        self.relations.reserve(relations);
+    }
+
     /// Find node id in an osm map by coordinate.
     pub fn find_node_id(&mut self, coordinate: Coordinate) -> Option<i64> {
         self.node_id_index.get(&coordinate).cloned()
@@ -248,16 +1070,154 @@ impl Default for Osm {
             nodes: Vec::new(),
             ways: Vec::new(),
             relations: Vec::new(),
+            extra_attributes: HashMap::new(),
             max_id: 0,
             node_id_index: HashMap::new(),
         }
     }
 }
 
+/// Maps are equal if they have the same boundary, extra attributes and elements, regardless of
+/// the order of the elements. The order of tags, refs and members matters.
+impl PartialEq for Osm {
+    fn eq(&self, other: &Self) -> bool {
+        self.boundary == other.boundary
+            && self.extra_attributes == other.extra_attributes
+            && same_elements(&self.nodes, &other.nodes, |n| n.id)
+            && same_elements(&self.ways, &other.ways, |w| w.id)
+            && same_elements(&self.relations, &other.relations, |r| r.id)
+    }
+}
+
+/// Compare elements sorted by id.
+fn same_elements<T: PartialEq, F: Fn(&T) -> i64>(a: &[T], b: &[T], id: F) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut a: Vec<&T> = a.iter().collect();
+    let mut b: Vec<&T> = b.iter().collect();
+    a.sort_by_key(|e| id(e));
+    b.sort_by_key(|e| id(e));
+    a == b
+}
+
+/// Changes to a map as created, modified and deleted elements, like the blocks of an
+/// [`osmChange`] file. Written with the [`OscWriter`].
+///
+/// [`osmChange`]: https://wiki.openstreetmap.org/wiki/OsmChange
+/// [`OscWriter`]: osm_io/struct.OscWriter.html
+#[derive(Debug, Default)]
+pub struct OsmChange {
+    pub create: Osm,
+    pub modify: Osm,
+    pub delete: Osm,
+}
+
+/// 64 bit [`FNV-1a`] hash. Unlike the hashers of the standard library, the result is the same on
+/// all platforms and releases.
+///
+/// [`FNV-1a`]: http://www.isthe.com/chongo/tech/comp/fnv/
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Strings are prefixed with their length, so that e.g. ("ab", "c") and ("a", "bc") differ.
+    fn write_str(&mut self, value: &str) {
+        self.write_i64(value.len() as i64);
+        self.write(value.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Iterator over all elements in an [`Osm`] map, see [`Osm::iter`].
+///
+/// [`Osm`]: struct.Osm.html
+/// [`Osm::iter`]: struct.Osm.html#method.iter
+pub struct Elements<'a> {
+    nodes: Iter<'a, Node>,
+    ways: Iter<'a, Way>,
+    relations: Iter<'a, Relation>,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Element<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(node) = self.nodes.next() {
+            return Some(Element::Node(node));
+        }
+        if let Some(way) = self.ways.next() {
+            return Some(Element::Way(way));
+        }
+        self.relations.next().map(Element::Relation)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.nodes.len() + self.ways.len() + self.relations.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a> IntoIterator for &'a Osm {
+    type Item = Element<'a>;
+    type IntoIter = Elements<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Extend<Node> for Osm {
+    fn extend<I: IntoIterator<Item = Node>>(&mut self, iter: I) {
+        for node in iter {
+            self.add_node(node);
+        }
+    }
+}
+
+impl Extend<Way> for Osm {
+    fn extend<I: IntoIterator<Item = Way>>(&mut self, iter: I) {
+        for way in iter {
+            self.add_way(way);
+        }
+    }
+}
+
+impl Extend<Relation> for Osm {
+    fn extend<I: IntoIterator<Item = Relation>>(&mut self, iter: I) {
+        for relation in iter {
+            self.add_relation(relation);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::geo::Boundary;
-    use crate::{Meta, Node, Osm};
+    use crate::geo::{Boundary, Coordinate};
+    use crate::osm_io::{create_writer, FileFormat};
+    use crate::{
+        Element, ElementRef, Meta, Node, Osm, OsmBuilder, Relation, RelationMember, Tag,
+        ValidationError, Way,
+    };
+    use std::collections::HashMap;
 
     #[test]
     fn osm_add_node() {
@@ -278,4 +1238,749 @@ mod tests {
         assert_eq!(osm.max_id, 10);
         assert_eq!(osm.boundary, Some(expected_boundary));
     }
+
+    #[test]
+    fn builder_add_relation() {
+        let mut builder = OsmBuilder::default();
+        let first =
+            builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        let second =
+            builder.add_polyline(vec![(2.0, 2.0), (3.0, 3.0)], vec![("highway", "primary")]);
+        let route = builder.add_relation(
+            vec![
+                (ElementRef::Way(first), "forward"),
+                (ElementRef::Way(second), "backward"),
+            ],
+            vec![("type", "route"), ("route", "bus")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(osm.relations.len(), 1);
+
+        let relation = &osm.relations[0];
+        assert_eq!(relation.id, route);
+        assert_eq!(
+            relation.members,
+            vec![
+                RelationMember::Way(first, "forward".to_owned()),
+                RelationMember::Way(second, "backward".to_owned()),
+            ]
+        );
+        assert_eq!(
+            relation.meta.tags,
+            vec![("type", "route").into(), ("route", "bus").into()]
+        );
+    }
+
+    #[test]
+    fn builder_returns_ids() {
+        let mut builder = OsmBuilder::default();
+        let point = builder.add_point((1.0, 1.0), vec![("amenity", "bus_stop")]);
+        let polygon = builder.add_polygon(
+            vec![vec![(2.0, 2.0), (3.0, 3.0), (2.0, 3.0), (2.0, 2.0)]],
+            vec![("building", "yes")],
+        );
+        let multipolygon = builder.add_polygon(
+            vec![
+                vec![(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 0.0)],
+                vec![(1.0, 0.5), (2.0, 0.5), (2.0, 1.0), (1.0, 0.5)],
+            ],
+            vec![("natural", "water")],
+        );
+        let route = builder.add_relation(
+            vec![(ElementRef::Node(point), "stop"), (polygon, "platform")],
+            vec![("type", "route")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(osm.nodes[0].id, point);
+        assert_eq!(polygon, ElementRef::Way(osm.ways[0].id));
+        assert_eq!(multipolygon, ElementRef::Relation(osm.relations[0].id));
+        assert_eq!(
+            osm.relations[1].members,
+            vec![
+                RelationMember::Node(point, "stop".to_owned()),
+                RelationMember::Way(osm.ways[0].id, "platform".to_owned()),
+            ]
+        );
+        assert_eq!(osm.relations[1].id, route);
+    }
+
+    #[test]
+    fn builder_clear() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        builder.clear();
+
+        assert_eq!(builder.osm.max_id, 0);
+        assert!(builder.osm.node_id_index.is_empty());
+
+        let point = builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        let osm = builder.build();
+
+        assert_eq!(point, 1);
+        assert_eq!(osm.nodes.len(), 1);
+        assert!(osm.ways.is_empty());
+        assert_eq!(osm.boundary, Some(Boundary::new((1.0, 1.0), (1.0, 1.0))));
+    }
+
+    #[test]
+    fn osm_len() {
+        let osm = Osm::default();
+        assert!(osm.is_empty());
+        assert_eq!(osm.len(), 0);
+
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        builder.add_polygon(
+            vec![
+                vec![(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 0.0)],
+                vec![(1.0, 0.5), (2.0, 0.5), (2.0, 1.0), (1.0, 0.5)],
+            ],
+            vec![("natural", "water")],
+        );
+
+        let osm = builder.build();
+        assert!(!osm.is_empty());
+        assert_eq!(osm.len(), 7 + 2 + 1);
+    }
+
+    #[test]
+    fn osm_extend_and_iterate() {
+        let mut osm = Osm::default();
+        osm.extend(vec![
+            Node {
+                id: 1,
+                coordinate: (1.0, 2.0).into(),
+                meta: Meta::default(),
+            },
+            Node {
+                id: 2,
+                coordinate: (3.0, 4.0).into(),
+                meta: Meta::default(),
+            },
+        ]);
+        osm.extend(vec![Way {
+            id: 3,
+            refs: vec![1, 2],
+            meta: Meta::default(),
+        }]);
+
+        assert_eq!(osm.boundary, Some(Boundary::new((1.0, 2.0), (3.0, 4.0))));
+        assert_eq!(osm.max_id, 3);
+
+        let refs: Vec<ElementRef> = osm.iter().map(|e| e.element_ref()).collect();
+        assert_eq!(
+            refs,
+            vec![ElementRef::Node(1), ElementRef::Node(2), ElementRef::Way(3)]
+        );
+        assert!(matches!((&osm).into_iter().last(), Some(Element::Way(_))));
+    }
+
+    #[test]
+    fn builder_add_multipolygon_single_part() {
+        let mut builder = OsmBuilder::default();
+        let id = builder.add_multipolygon(
+            vec![vec![(0.0, 0.0), (5.0, 0.0), (5.0, 5.0), (0.0, 0.0)]],
+            vec![("natural", "water")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(osm.ways.len(), 1);
+        assert_eq!(osm.relations.len(), 1);
+
+        let relation = &osm.relations[0];
+        assert_eq!(relation.id, id);
+        assert_eq!(
+            relation.members,
+            vec![RelationMember::Way(osm.ways[0].id, "outer".to_owned())]
+        );
+        assert_eq!(
+            relation.meta.tags,
+            vec![("natural", "water").into(), ("type", "multipolygon").into()]
+        );
+    }
+
+    #[test]
+    fn builder_add_closed_polyline() {
+        let mut builder = OsmBuilder::default();
+        let open = builder.add_closed_polyline(
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)],
+            vec![("building", "yes")],
+        );
+        let closed = builder.add_closed_polyline(
+            vec![(2.0, 2.0), (3.0, 2.0), (3.0, 3.0), (2.0, 2.0)],
+            vec![("building", "yes")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(osm.ways[0].id, open);
+        assert_eq!(osm.ways[0].refs, vec![1, 2, 3, 1]);
+        assert_eq!(osm.ways[1].id, closed);
+        assert_eq!(osm.ways[1].refs, vec![5, 6, 7, 5]);
+    }
+
+    #[test]
+    fn builder_with_boundary() {
+        let mut builder = OsmBuilder::default();
+        builder.with_boundary(Boundary::new((1.0, 1.0), (2.0, 2.0)));
+        builder.add_point((5.0, 5.0), vec![("amenity", "bench")]);
+        builder.add_polyline(vec![(0.0, 0.0), (1.5, 1.5)], vec![("highway", "primary")]);
+
+        let osm = builder.build();
+        let boundary = osm.boundary.unwrap();
+        assert_eq!(boundary.min, (1.0, 1.0).into());
+        assert_eq!(boundary.max, (2.0, 2.0).into());
+        assert!(boundary.freeze);
+    }
+
+    #[test]
+    fn builder_add_boundary_box() {
+        let mut builder = OsmBuilder::default();
+        let boundary = Boundary::new((1.0, 2.0), (3.0, 4.0));
+        let id = builder.add_boundary_box(&boundary, vec![("name", "extent")]);
+
+        let osm = builder.build();
+        assert_eq!(osm.ways[0].id, id);
+        assert_eq!(osm.ways[0].refs, vec![1, 2, 3, 4, 1]);
+        assert_eq!(osm.boundary, Some(boundary));
+    }
+
+    #[test]
+    fn osm_dedup_all_tags() {
+        let mut osm = Osm::default();
+        osm.add_node(Node {
+            id: 1,
+            coordinate: (1.0, 1.0).into(),
+            meta: Meta {
+                tags: vec![("name", "First").into(), ("name", "Second").into()],
+                ..Meta::default()
+            },
+        });
+        osm.add_way(Way {
+            id: 2,
+            refs: vec![1],
+            meta: Meta {
+                tags: vec![
+                    ("highway", "primary").into(),
+                    ("highway", "secondary").into(),
+                ],
+                ..Meta::default()
+            },
+        });
+
+        osm.dedup_all_tags();
+        assert_eq!(osm.nodes[0].meta.tags, vec![("name", "Second").into()]);
+        assert_eq!(osm.ways[0].meta.tags, vec![("highway", "secondary").into()]);
+    }
+
+    #[test]
+    fn osm_offset_ids() {
+        let mut builder = OsmBuilder::default();
+        let way = builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        builder.add_relation(
+            vec![(ElementRef::Way(way), "outer")],
+            vec![("type", "multipolygon")],
+        );
+
+        let mut osm = builder.build();
+        osm.offset_ids(-100);
+        assert_eq!(osm.nodes[0].id, -99);
+        assert_eq!(osm.ways[0].id, way - 100);
+        assert_eq!(osm.ways[0].refs, vec![-99, -98]);
+        assert_eq!(osm.relations[0].members[0].ref_id(), way - 100);
+        assert_eq!(osm.find_node_id((2.0, 2.0).into()), Some(-98));
+        assert_eq!(osm.max_id, 0);
+
+        osm.offset_ids(200);
+        assert_eq!(osm.ways[0].refs, vec![101, 102]);
+        assert_eq!(osm.max_id, 104);
+    }
+
+    #[test]
+    fn osm_retain_ways() {
+        let mut builder = OsmBuilder::default();
+        let highway =
+            builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], vec![("highway", "primary")]);
+        let river = builder.add_polyline(vec![(1.0, 0.0), (2.0, 0.0)], vec![("waterway", "river")]);
+        builder.add_point((5.0, 5.0), vec![("amenity", "bench")]);
+        builder.add_relation(
+            vec![
+                (ElementRef::Way(highway), "outer"),
+                (ElementRef::Way(river), "outer"),
+            ],
+            vec![("type", "multipolygon")],
+        );
+
+        let mut osm = builder.build();
+        osm.retain_ways(|w| w.meta.tag("highway").is_some());
+        assert_eq!(osm.ways.len(), 1);
+        assert_eq!(osm.ways[0].id, highway);
+        assert_eq!(
+            osm.relations[0].members,
+            vec![RelationMember::Way(highway, "outer".to_owned())]
+        );
+        assert_eq!(osm.nodes.len(), 4);
+
+        // The bench is tagged and the shared node is used by the highway.
+        osm.remove_orphan_nodes();
+        assert_eq!(osm.nodes.len(), 3);
+        assert!(osm.nodes.iter().all(|n| n.coordinate != (2.0, 0.0).into()));
+    }
+
+    #[test]
+    fn osm_retain_nodes() {
+        let mut builder = OsmBuilder::default();
+        let way = builder.add_polyline(
+            vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)],
+            vec![("highway", "primary")],
+        );
+
+        let mut osm = builder.build();
+        osm.retain_nodes(|n| n.coordinate != (1.0, 0.0).into());
+        assert_eq!(osm.nodes.len(), 2);
+        assert_eq!(osm.ways[0].id, way);
+        assert_eq!(osm.ways[0].refs, vec![1, 3]);
+        assert_eq!(osm.find_node_id((1.0, 0.0).into()), None);
+    }
+
+    #[test]
+    fn osm_canonicalize_values() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], vec![("oneway", "Yes")]);
+        builder.add_polyline(vec![(1.0, 0.0), (2.0, 0.0)], vec![("oneway", "true")]);
+        builder.add_polyline(vec![(2.0, 0.0), (3.0, 0.0)], vec![("oneway", "-1")]);
+        builder.add_polyline(vec![(3.0, 0.0), (4.0, 0.0)], vec![("access", "Yes")]);
+
+        let mut mapping = HashMap::new();
+        mapping.insert("Yes".to_owned(), "yes".to_owned());
+        mapping.insert("true".to_owned(), "yes".to_owned());
+
+        let mut osm = builder.build();
+        osm.canonicalize_values("oneway", &mapping);
+        let values: Vec<_> = osm
+            .ways
+            .iter()
+            .map(|w| w.meta.tags[0].value.as_str())
+            .collect();
+        assert_eq!(values, vec!["yes", "yes", "-1", "Yes"]);
+    }
+
+    #[test]
+    fn osm_version_histogram() {
+        let mut osm = Osm::default();
+        for &(id, version) in &[(1, Some(1)), (2, Some(3)), (3, None), (4, Some(1))] {
+            osm.add_node(Node {
+                id,
+                meta: Meta {
+                    version,
+                    ..Meta::default()
+                },
+                ..Node::default()
+            });
+        }
+        osm.add_way(Way {
+            id: 5,
+            refs: vec![1, 2],
+            meta: Meta::default(),
+        });
+
+        let histogram: Vec<_> = osm.version_histogram().into_iter().collect();
+        assert_eq!(histogram, vec![(None, 2), (Some(1), 2), (Some(3), 1)]);
+    }
+
+    #[test]
+    fn osm_fingerprint() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench"), ("name", "Park")]);
+        builder.add_polyline(vec![(0.0, 0.0), (1.0, 1.0)], vec![("highway", "primary")]);
+        builder.add_point((2.0, 2.0), vec![("amenity", "cafe")]);
+        let osm = builder.build();
+        let fingerprint = osm.fingerprint();
+
+        let mut reordered = Osm::default();
+        for node in osm.nodes.iter().rev() {
+            let mut node = node.clone();
+            node.meta.tags.reverse();
+            reordered.add_node(node);
+        }
+        reordered.extend(osm.ways.iter().cloned());
+        assert_eq!(reordered.fingerprint(), fingerprint);
+
+        reordered.nodes[0].meta.tags[0].value = "restaurant".to_owned();
+        assert_ne!(reordered.fingerprint(), fingerprint);
+
+        let mut moved = Osm::default();
+        moved.extend(osm.nodes.iter().cloned());
+        moved.extend(osm.ways.iter().cloned());
+        moved.nodes[1].coordinate = (1.5, 1.5).into();
+        assert_ne!(moved.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn osm_reindex() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        let mut osm = builder.build();
+
+        osm.nodes[0].coordinate = (2.0, 2.0).into();
+        osm.nodes.push(Node {
+            id: 40,
+            coordinate: (3.0, 3.0).into(),
+            meta: Meta::default(),
+        });
+        osm.reindex();
+
+        assert_eq!(osm.max_id, 40);
+        assert_eq!(osm.find_node_id((1.0, 1.0).into()), None);
+        assert_eq!(osm.find_node_id((2.0, 2.0).into()), Some(1));
+        assert_eq!(osm.find_node_id((3.0, 3.0).into()), Some(40));
+
+        let mut builder = OsmBuilder::from_osm(osm);
+        assert_eq!(builder.add_point((4.0, 4.0), vec![("amenity", "cafe")]), 41);
+    }
+
+    #[test]
+    fn osm_relation_roles() {
+        let mut builder = OsmBuilder::default();
+        let square = |d: f64| vec![(d, d), (d, 10.0 - d), (10.0 - d, 10.0 - d), (d, d)];
+        builder.add_multipolygon(
+            vec![square(0.0), square(1.0), square(2.0)],
+            vec![("a", "b")],
+        );
+        let way = builder.add_polyline(vec![(20.0, 20.0), (21.0, 21.0)], vec![("c", "d")]);
+        builder.add_relation(vec![(ElementRef::Way(way), "")], vec![("type", "route")]);
+
+        let roles = builder.build().relation_roles();
+        assert_eq!(roles.len(), 3);
+        assert_eq!(roles["outer"], 1);
+        assert_eq!(roles["inner"], 2);
+        assert_eq!(roles[""], 1);
+    }
+
+    #[test]
+    fn builder_add_way_refs() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        let osm = builder.build();
+        let refs: Vec<i64> = osm.nodes.iter().map(|n| n.id).rev().collect();
+
+        let mut builder = OsmBuilder::from_osm(osm);
+        let id = builder
+            .add_way_refs(refs.clone(), vec![("highway", "service")])
+            .unwrap();
+
+        let osm = builder.build();
+        assert_eq!(osm.nodes.len(), 2);
+        assert_eq!(osm.ways.len(), 2);
+        assert_eq!(osm.ways[1].id, id);
+        assert_eq!(osm.ways[1].refs, refs);
+        assert_eq!(osm.ways[1].meta.tag("highway"), Some("service"));
+    }
+
+    #[test]
+    fn builder_add_way_refs_missing_node() {
+        let mut builder = OsmBuilder::default();
+        let node = builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        let error = builder
+            .add_way_refs(vec![node, 5], vec![("highway", "service")])
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ValidationError::MissingNode {
+                way_id: 2,
+                node_id: 5
+            }
+        );
+
+        // Nodes added after the first call are known.
+        let other = builder.add_point((2.0, 2.0), vec![("amenity", "bench")]);
+        let way = builder
+            .add_way_refs(vec![node, other], vec![("highway", "service")])
+            .unwrap();
+        assert_eq!(builder.build().ways[0].id, way);
+    }
+
+    #[test]
+    fn builder_add_way_refs_after_clear() {
+        let mut builder = OsmBuilder::default();
+        let node = builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        let tags = vec![("highway", "service")];
+        builder.add_way_refs(vec![node], tags.clone()).unwrap();
+        builder.clear();
+
+        let error = builder.add_way_refs(vec![node], tags).unwrap_err();
+        assert_eq!(
+            error,
+            ValidationError::MissingNode {
+                way_id: 1,
+                node_id: node
+            }
+        );
+    }
+
+    #[test]
+    fn relations_of_element() {
+        let mut builder = OsmBuilder::default();
+        let no_tags: Vec<(&str, &str)> = Vec::new();
+        let way = builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        let node = builder.add_point((3.0, 3.0), vec![("amenity", "bench")]);
+        let a = builder.add_relation(
+            vec![
+                (ElementRef::Way(way), "forward"),
+                (ElementRef::Way(way), "backward"),
+            ],
+            no_tags.clone(),
+        );
+        let b = builder.add_relation(
+            vec![(ElementRef::Node(node), ""), (ElementRef::Way(way), "")],
+            no_tags.clone(),
+        );
+        let c = builder.add_relation(vec![(ElementRef::Relation(a), "")], no_tags);
+
+        let osm = builder.build();
+        let ids = |relations: Vec<&Relation>| relations.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(osm.relations_of_way(way)), vec![a, b]);
+        assert_eq!(ids(osm.relations_of_node(node)), vec![b]);
+        assert_eq!(ids(osm.relations_of_relation(a)), vec![c]);
+        assert!(osm.relations_of_relation(c).is_empty());
+        // A node with the same id as the way is not a member.
+        assert!(osm.relations_of_node(way).is_empty());
+    }
+
+    #[test]
+    fn osm_clone_eq() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "path")]);
+        let osm = builder.build();
+
+        let mut copy = osm.clone();
+        assert_eq!(copy, osm);
+        assert_eq!(copy.find_node_id((2.0, 2.0).into()), Some(2));
+
+        // Element order does not matter.
+        copy.nodes.reverse();
+        assert_eq!(copy, osm);
+
+        copy.nodes[0].meta.tags.push(("name", "A").into());
+        assert_ne!(copy, osm);
+    }
+
+    #[test]
+    fn frozen_boundary_does_not_expand() {
+        let node = |id, coordinate: (f64, f64)| Node {
+            id,
+            coordinate: coordinate.into(),
+            meta: Meta::default(),
+        };
+
+        let mut osm = Osm::default();
+        osm.set_boundary(Boundary::new((0.0, 0.0), (1.0, 1.0)), true);
+        osm.add_node(node(1, (2.0, 2.0)));
+        let boundary = osm.boundary.as_ref().unwrap();
+        assert_eq!(boundary.max, (1.0, 1.0).into());
+        assert!(boundary.freeze);
+
+        osm.boundary.as_mut().unwrap().thaw();
+        osm.add_node(node(2, (3.0, -1.0)));
+        let boundary = osm.boundary.as_ref().unwrap();
+        assert_eq!(boundary.min, (0.0, -1.0).into());
+        assert_eq!(boundary.max, (3.0, 1.0).into());
+
+        osm.boundary.as_mut().unwrap().freeze();
+        osm.add_node(node(3, (4.0, 4.0)));
+        assert_eq!(osm.boundary.unwrap().max, (3.0, 1.0).into());
+    }
+
+    #[test]
+    fn builder_reserve() {
+        let mut builder = OsmBuilder::default();
+        builder.reserve(1000, 100, 10);
+
+        let osm = builder.build();
+        assert!(osm.nodes.capacity() >= 1000);
+        assert!(osm.node_id_index.capacity() >= 1000);
+        assert!(osm.ways.capacity() >= 100);
+        assert!(osm.relations.capacity() >= 10);
+    }
+
+    #[test]
+    fn builder_add_graticule() {
+        let mut builder = OsmBuilder::default();
+        let bounds = Boundary::new((10.0, 20.0), (10.3, 20.25));
+        let ways = builder.add_graticule(bounds, 0.1, vec![("graticule", "yes")]);
+        let osm = builder.build();
+
+        // Latitude lines at 10.0 to 10.3 and longitude lines at 20.0 to 20.2.
+        assert_eq!(ways.len(), 4 + 3);
+        assert_eq!(osm.ways.len(), 7);
+        assert_eq!(osm.nodes.len(), 4 * 4);
+
+        let first = &osm.ways[0];
+        assert_eq!(first.refs.len(), 4);
+        assert_eq!(first.meta.tags, vec![("graticule", "yes").into()]);
+        let coordinates: Vec<Coordinate> = first
+            .refs
+            .iter()
+            .map(|id| osm.nodes.iter().find(|n| n.id == *id).unwrap().coordinate)
+            .collect();
+        assert_eq!(coordinates.first(), Some(&(10.0, 20.0).into()));
+        assert_eq!(coordinates.last(), Some(&(10.0, 20.25).into()));
+
+        // The first longitude line shares its nodes with the latitude lines.
+        let meridian = &osm.ways[4].refs;
+        assert!(osm.ways[..4]
+            .iter()
+            .all(|w| w.refs[0] == meridian[0] || meridian.contains(&w.refs[0])));
+    }
+
+    #[test]
+    fn osm_all_tags() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench"), ("name", "Corner")]);
+        let way = builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        builder.add_relation(vec![(ElementRef::Way(way), "")], vec![("type", "route")]);
+        let osm = builder.build();
+
+        let per_element: usize = osm.iter().map(|e| e.meta().tags.len()).sum();
+        assert_eq!(osm.all_tags().count(), per_element);
+        assert_eq!(osm.all_tags().count(), 4);
+        assert_eq!(osm.all_tags().last(), Some(&("type", "route").into()));
+    }
+
+    #[test]
+    fn osm_tag_key_cardinality() {
+        let mut osm = Osm::default();
+        osm.add_node(Node {
+            id: 1,
+            meta: Meta {
+                tags: vec![("name", "a").into(), ("name", "b").into()],
+                ..Meta::default()
+            },
+            ..Node::default()
+        });
+        osm.add_way(Way {
+            id: 2,
+            refs: vec![1],
+            meta: Meta {
+                tags: vec![("highway", "primary").into(), ("name", "c").into()],
+                ..Meta::default()
+            },
+        });
+
+        let cardinality = osm.tag_key_cardinality();
+        assert_eq!(
+            cardinality,
+            vec![("name".to_owned(), 2), ("highway".to_owned(), 1)]
+        );
+    }
+
+    #[test]
+    fn osm_id_ranges() {
+        let mut osm = Osm::default();
+        assert_eq!(osm.max_node_id(), None);
+
+        for id in &[5, 2, 9] {
+            osm.add_node(Node {
+                id: *id,
+                ..Node::default()
+            });
+        }
+        osm.add_way(Way {
+            id: 3,
+            refs: vec![2, 5],
+            meta: Meta::default(),
+        });
+        osm.add_relation(Relation {
+            id: 1,
+            members: vec![RelationMember::Way(3, "outer".to_owned())],
+            meta: Meta::default(),
+        });
+
+        assert_eq!(osm.min_node_id(), Some(2));
+        assert_eq!(osm.max_node_id(), Some(9));
+        assert_eq!(osm.min_way_id(), Some(3));
+        assert_eq!(osm.max_way_id(), Some(3));
+        assert_eq!(osm.min_relation_id(), Some(1));
+        assert_eq!(osm.max_relation_id(), Some(1));
+    }
+
+    #[test]
+    fn builder_with_negative_ids() {
+        let mut osm = Osm::default();
+        osm.add_node(Node {
+            id: -5,
+            coordinate: (1.0, 1.0).into(),
+            ..Node::default()
+        });
+        assert_eq!(osm.max_id, 0);
+
+        let mut builder = OsmBuilder::from_osm(osm);
+        let id = builder.add_point((2.0, 2.0), vec![("power", "tower")]);
+        assert_eq!(id, 1);
+
+        let osm = builder.build();
+        assert_eq!(osm.min_node_id(), Some(-5));
+        assert_eq!(osm.max_node_id(), Some(1));
+    }
+
+    #[test]
+    fn osm_canonicalize() {
+        let node = |id, tags: Vec<Tag>| Node {
+            id,
+            coordinate: (id as f64, 1.0).into(),
+            meta: Meta {
+                tags,
+                version: Some(1),
+                ..Meta::default()
+            },
+        };
+        let way = |id| Way {
+            id,
+            refs: vec![1, 2],
+            meta: Meta::default(),
+        };
+
+        let mut a = Osm::default();
+        a.add_node(node(1, vec![("a", "1").into(), ("b", "2").into()]));
+        a.add_node(node(2, vec![]));
+        a.add_way(way(3));
+        a.add_way(way(4));
+
+        let mut b = Osm::default();
+        b.add_way(way(4));
+        b.add_node(node(2, vec![]));
+        b.add_way(way(3));
+        b.add_node(node(1, vec![("b", "2").into(), ("a", "1").into()]));
+
+        let write = |osm: &Osm| {
+            let mut writer = create_writer(Vec::new(), FileFormat::Xml);
+            writer.write(osm).unwrap();
+            writer.into_inner()
+        };
+        assert_ne!(write(&a), write(&b));
+
+        a.canonicalize();
+        b.canonicalize();
+        assert_eq!(write(&a), write(&b));
+    }
+
+    #[test]
+    fn builder_dedup_tags() {
+        let tags = vec![("name", "First"), ("amenity", "bench"), ("name", "Second")];
+
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), tags.clone());
+        assert_eq!(builder.build().nodes[0].meta.tags.len(), 3);
+
+        let mut builder = OsmBuilder::default().dedup_tags(true);
+        builder.add_point((1.0, 1.0), tags.clone());
+        builder.add_polyline(vec![(2.0, 2.0), (3.0, 3.0)], tags);
+
+        let osm = builder.build();
+        let expected: Vec<Tag> = vec![("name", "Second").into(), ("amenity", "bench").into()];
+        assert_eq!(osm.nodes[0].meta.tags, expected);
+        assert_eq!(osm.ways[0].meta.tags, expected);
+    }
 }
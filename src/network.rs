@@ -0,0 +1,551 @@
+//! Analysis of the road network, e.g. for routing.
+use crate::geo::{Coordinate, EARTH_RADIUS};
+use crate::{ElementRef, Osm, Relation, RelationMember, Way};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A resolved [`turn restriction`] relation.
+///
+/// [`turn restriction`]: https://wiki.openstreetmap.org/wiki/Relation:restriction
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TurnRestriction {
+    /// Id of the relation.
+    pub id: i64,
+    /// Value of the `restriction` tag, e.g. `no_left_turn`.
+    pub restriction: String,
+    /// Way the restriction starts at.
+    pub from: i64,
+    /// Node or ways between `from` and `to`, in relation order.
+    pub via: Vec<ElementRef>,
+    /// Way the restriction ends at.
+    pub to: i64,
+}
+
+/// A coordinate projected onto the nearest way, see [`Osm::snap_to_way`].
+///
+/// [`Osm::snap_to_way`]: struct.Osm.html#method.snap_to_way
+#[derive(Debug, PartialEq, Clone)]
+pub struct SnapResult {
+    /// Id of the way.
+    pub way: i64,
+    /// Index of the segment, i.e. the segment between `refs[segment]` and `refs[segment + 1]`.
+    pub segment: usize,
+    /// The nearest point on the way.
+    pub coordinate: Coordinate,
+    /// Distance in meters from the snapped coordinate to the way.
+    pub distance: f64,
+}
+
+impl Osm {
+    /// Resolve all relations tagged with `type=restriction`.
+    ///
+    /// Relations missing the `restriction` tag or any of the `from`, `via` or `to` members are
+    /// skipped. They are reported by [`validate`] as `InvalidRestriction`.
+    ///
+    /// [`validate`]: #method.validate
+    pub fn turn_restrictions(&self) -> Vec<TurnRestriction> {
+        self.relations
+            .iter()
+            .filter(|r| is_restriction(r))
+            .filter_map(resolve_restriction)
+            .collect()
+    }
+
+    /// Group the ways accepted by `way_filter` into components of ways connected to each other
+    /// through shared nodes. Components are ordered by their first way, and way ids within a
+    /// component are in map order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// let a = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], vec![("highway", "primary")]);
+    /// let b = builder.add_polyline(vec![(1.0, 0.0), (2.0, 0.0)], vec![("highway", "primary")]);
+    /// let c = builder.add_polyline(vec![(5.0, 5.0), (6.0, 6.0)], vec![("highway", "primary")]);
+    ///
+    /// let osm = builder.build();
+    /// let components = osm.connected_components(|w| w.meta.tag("highway").is_some());
+    /// assert_eq!(components, vec![vec![a, b], vec![c]]);
+    /// ```
+    pub fn connected_components<F: Fn(&Way) -> bool>(&self, way_filter: F) -> Vec<Vec<i64>> {
+        let ways: Vec<&Way> = self.ways.iter().filter(|w| way_filter(w)).collect();
+        let mut components = UnionFind::new(ways.len());
+
+        let mut node_ways: HashMap<i64, usize> = HashMap::new();
+        for (i, way) in ways.iter().enumerate() {
+            for node in &way.refs {
+                match node_ways.get(node) {
+                    Some(&j) => components.union(i, j),
+                    None => {
+                        node_ways.insert(*node, i);
+                    }
+                }
+            }
+        }
+
+        let mut roots: HashMap<usize, usize> = HashMap::new();
+        let mut result: Vec<Vec<i64>> = Vec::new();
+        for (i, way) in ways.iter().enumerate() {
+            let root = components.find(i);
+            let index = *roots.entry(root).or_insert_with(|| {
+                result.push(Vec::new());
+                result.len() - 1
+            });
+            result[index].push(way.id);
+        }
+        result
+    }
+
+    /// Degree of each node used by the ways accepted by `way_filter`, i.e. the number of way
+    /// segments connected to the node.
+    ///
+    /// Each use of a node as the first or last node of a way counts as one, and each use as an
+    /// internal node counts as two since the way both enters and leaves the node. This means the
+    /// first node of a closed way gets degree two from that way, like any other node in the ring.
+    pub fn node_degrees<F: Fn(&Way) -> bool>(&self, way_filter: F) -> HashMap<i64, usize> {
+        let mut degrees = HashMap::new();
+        for way in self.ways.iter().filter(|w| way_filter(w)) {
+            let last = way.refs.len().saturating_sub(1);
+            for (i, node) in way.refs.iter().enumerate() {
+                let degree = if i == 0 || i == last { 1 } else { 2 };
+                *degrees.entry(*node).or_insert(0) += degree;
+            }
+        }
+        degrees
+    }
+
+    /// Ids of nodes with degree one, i.e. where a way ends without connecting to any other way.
+    /// See [`node_degrees`] for how the degree is counted. Ids are sorted.
+    ///
+    /// [`node_degrees`]: #method.node_degrees
+    pub fn dead_ends<F: Fn(&Way) -> bool>(&self, way_filter: F) -> Vec<i64> {
+        self.nodes_with_degree(way_filter, |d| d == 1)
+    }
+
+    /// Ids of nodes with degree three or more, i.e. where ways intersect.
+    /// See [`node_degrees`] for how the degree is counted. Ids are sorted.
+    ///
+    /// [`node_degrees`]: #method.node_degrees
+    pub fn junctions<F: Fn(&Way) -> bool>(&self, way_filter: F) -> Vec<i64> {
+        self.nodes_with_degree(way_filter, |d| d >= 3)
+    }
+
+    /// Find the nearest point on any of the ways accepted by `way_filter`. Returns `None` if no
+    /// way with resolvable nodes is accepted.
+    ///
+    /// Distances are calculated in a local planar approximation around `c`, which is accurate
+    /// for nearby ways. If the nearest point is a node shared by two segments, the first segment
+    /// is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// let id = builder.add_polyline(vec![(0.0, 0.0), (0.0, 0.01)], vec![("highway", "primary")]);
+    ///
+    /// let osm = builder.build();
+    /// let snap = osm.snap_to_way((0.0001, 0.005).into(), |_| true).unwrap();
+    /// assert_eq!(snap.way, id);
+    /// assert_eq!(snap.coordinate, (0.0, 0.005).into());
+    /// ```
+    pub fn snap_to_way<F: Fn(&Way) -> bool>(
+        &self,
+        c: Coordinate,
+        way_filter: F,
+    ) -> Option<SnapResult> {
+        let meters_per_degree = EARTH_RADIUS.to_radians();
+        let scale = c.lat().to_radians().cos();
+        let project = |p: Coordinate| {
+            (
+                (p.lon() - c.lon()) * scale * meters_per_degree,
+                (p.lat() - c.lat()) * meters_per_degree,
+            )
+        };
+
+        let mut nearest: Option<SnapResult> = None;
+        for (way, coordinates) in self.ways_with_geometry().filter(|(w, _)| way_filter(w)) {
+            for (segment, pair) in coordinates.windows(2).enumerate() {
+                let (ax, ay) = project(pair[0]);
+                let (bx, by) = project(pair[1]);
+                let (dx, dy) = (bx - ax, by - ay);
+                let length = dx * dx + dy * dy;
+                let t = if length > 0.0 {
+                    (-(ax * dx + ay * dy) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+
+                let (x, y) = (ax + t * dx, ay + t * dy);
+                let distance = (x * x + y * y).sqrt();
+                let is_nearest = match &nearest {
+                    Some(n) => distance < n.distance,
+                    None => true,
+                };
+                if is_nearest {
+                    // The projection is linear, so the point is interpolated in coordinates.
+                    let (a, b) = (pair[0], pair[1]);
+                    let interpolate = |a: i32, b: i32| a + (t * (b - a) as f64).round() as i32;
+                    nearest = Some(SnapResult {
+                        way: way.id,
+                        segment,
+                        coordinate: Coordinate {
+                            lat: interpolate(a.lat, b.lat),
+                            lon: interpolate(a.lon, b.lon),
+                        },
+                        distance,
+                    });
+                }
+            }
+        }
+        nearest
+    }
+
+    /// Extract the ways accepted by `way_filter` that can be reached from `seed_node` by
+    /// travelling along the ways, together with their nodes. The seed node is always included if
+    /// it exists, even if no way is reachable. Elements are in map order and relations are not
+    /// included.
+    ///
+    /// If `respect_oneway` is set, ways tagged `oneway=yes` (or `true`, `1`) can only be travelled
+    /// forwards and ways tagged `oneway=-1` only backwards. A way is included if any of its
+    /// segments can be travelled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// let mut builder = OsmBuilder::default();
+    /// let a = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], vec![("highway", "primary")]);
+    /// let b = builder.add_polyline(vec![(1.0, 0.0), (2.0, 0.0)], vec![("highway", "primary")]);
+    /// builder.add_polyline(vec![(5.0, 5.0), (6.0, 6.0)], vec![("highway", "primary")]);
+    ///
+    /// let osm = builder.build();
+    /// let seed = osm.ways[0].refs[0];
+    /// let reachable = osm.reachable_ways(seed, false, |_| true);
+    /// assert_eq!(reachable.ways.iter().map(|w| w.id).collect::<Vec<_>>(), vec![a, b]);
+    /// assert_eq!(reachable.nodes.len(), 3);
+    /// ```
+    pub fn reachable_ways<F: Fn(&Way) -> bool>(
+        &self,
+        seed_node: i64,
+        respect_oneway: bool,
+        way_filter: F,
+    ) -> Osm {
+        let ways: Vec<&Way> = self.ways.iter().filter(|w| way_filter(w)).collect();
+        let mut node_ways: HashMap<i64, Vec<(usize, usize)>> = HashMap::new();
+        for (i, way) in ways.iter().enumerate() {
+            for (position, node) in way.refs.iter().enumerate() {
+                node_ways.entry(*node).or_default().push((i, position));
+            }
+        }
+
+        let mut visited: HashSet<i64> = HashSet::new();
+        let mut reached_ways: HashSet<usize> = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(seed_node);
+        queue.push_back(seed_node);
+        while let Some(node) = queue.pop_front() {
+            for &(i, position) in node_ways.get(&node).into_iter().flatten() {
+                let refs = &ways[i].refs;
+                let (forward, backward) = if respect_oneway {
+                    match ways[i].meta.tag("oneway") {
+                        Some("yes") | Some("true") | Some("1") => (true, false),
+                        Some("-1") => (false, true),
+                        _ => (true, true),
+                    }
+                } else {
+                    (true, true)
+                };
+
+                let next = refs.get(position + 1).filter(|_| forward);
+                let previous = position
+                    .checked_sub(1)
+                    .map(|p| &refs[p])
+                    .filter(|_| backward);
+                for neighbour in next.into_iter().chain(previous) {
+                    reached_ways.insert(i);
+                    if visited.insert(*neighbour) {
+                        queue.push_back(*neighbour);
+                    }
+                }
+            }
+        }
+
+        let mut result = Osm::default();
+        let mut node_ids: HashSet<i64> = HashSet::new();
+        node_ids.insert(seed_node);
+        for (i, way) in ways.iter().enumerate() {
+            if reached_ways.contains(&i) {
+                node_ids.extend(&way.refs);
+                result.add_way((*way).clone());
+            }
+        }
+        for node in self.nodes.iter().filter(|n| node_ids.contains(&n.id)) {
+            result.add_node(node.clone());
+        }
+        result
+    }
+
+    fn nodes_with_degree<F, D>(&self, way_filter: F, degree_filter: D) -> Vec<i64>
+    where
+        F: Fn(&Way) -> bool,
+        D: Fn(usize) -> bool,
+    {
+        let mut ids: Vec<i64> = self
+            .node_degrees(way_filter)
+            .into_iter()
+            .filter(|(_, degree)| degree_filter(*degree))
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+/// Disjoint set of indexes, with path compression.
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        UnionFind {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let parent = self.parents[i];
+        if parent == i {
+            return i;
+        }
+
+        let root = self.find(parent);
+        self.parents[i] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+/// True if the relation is tagged with `type=restriction`.
+pub(crate) fn is_restriction(relation: &Relation) -> bool {
+    relation.meta.tag("type") == Some("restriction")
+}
+
+/// Resolve a restriction relation, `None` if the relation is malformed.
+pub(crate) fn resolve_restriction(relation: &Relation) -> Option<TurnRestriction> {
+    let restriction = relation.meta.tag("restriction")?.to_owned();
+
+    let mut from = None;
+    let mut via = Vec::new();
+    let mut to = None;
+    for member in &relation.members {
+        match (member, member.role()) {
+            (RelationMember::Way(id, _), "from") => from = Some(*id),
+            (RelationMember::Way(id, _), "to") => to = Some(*id),
+            (RelationMember::Node(id, _), "via") => via.push(ElementRef::Node(*id)),
+            (RelationMember::Way(id, _), "via") => via.push(ElementRef::Way(*id)),
+            _ => {}
+        }
+    }
+
+    if via.is_empty() {
+        return None;
+    }
+
+    Some(TurnRestriction {
+        id: relation.id,
+        restriction,
+        from: from?,
+        via,
+        to: to?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::{SnapResult, TurnRestriction};
+    use crate::{ElementRef, Osm, OsmBuilder};
+
+    #[test]
+    fn no_left_turn() {
+        let mut builder = OsmBuilder::default();
+        let from = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], vec![("highway", "primary")]);
+        let to = builder.add_polyline(vec![(1.0, 0.0), (1.0, -1.0)], vec![("highway", "primary")]);
+        let via = builder.add_point((1.0, 0.0), Vec::<(&str, &str)>::new());
+        let restriction = builder.add_relation(
+            vec![
+                (ElementRef::Way(from), "from"),
+                (ElementRef::Node(via), "via"),
+                (ElementRef::Way(to), "to"),
+            ],
+            vec![("type", "restriction"), ("restriction", "no_left_turn")],
+        );
+
+        // Missing the to member.
+        builder.add_relation(
+            vec![
+                (ElementRef::Way(from), "from"),
+                (ElementRef::Node(via), "via"),
+            ],
+            vec![("type", "restriction"), ("restriction", "no_u_turn")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(
+            osm.turn_restrictions(),
+            vec![TurnRestriction {
+                id: restriction,
+                restriction: "no_left_turn".to_owned(),
+                from,
+                via: vec![ElementRef::Node(via)],
+                to,
+            }]
+        );
+    }
+
+    #[test]
+    fn connected_components() {
+        let mut builder = OsmBuilder::default();
+        let highway = vec![("highway", "residential")];
+        let a = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], highway.clone());
+        let c = builder.add_polyline(vec![(5.0, 5.0), (6.0, 5.0)], highway.clone());
+        let b = builder.add_polyline(vec![(1.0, 1.0), (1.0, 0.0)], highway.clone());
+        let d = builder.add_polyline(vec![(6.0, 5.0), (6.0, 6.0)], highway);
+
+        // Connects the two components, but is not a highway.
+        builder.add_polyline(vec![(0.0, 0.0), (5.0, 5.0)], vec![("power", "line")]);
+
+        let osm = builder.build();
+        let components = osm.connected_components(|w| w.meta.tag("highway").is_some());
+        assert_eq!(components, vec![vec![a, b], vec![c, d]]);
+    }
+
+    #[test]
+    fn t_junction() {
+        let mut builder = OsmBuilder::default();
+        let highway = vec![("highway", "residential")];
+        builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], highway.clone());
+        builder.add_polyline(vec![(1.0, 0.0), (2.0, 0.0)], highway.clone());
+        builder.add_polyline(vec![(1.0, 0.0), (1.0, 1.0)], highway);
+
+        let osm = builder.build();
+        let id = |lat, lon| {
+            osm.nodes
+                .iter()
+                .find(|n| n.coordinate == (lat, lon).into())
+                .unwrap()
+                .id
+        };
+        let junction = id(1.0, 0.0);
+
+        let degrees = osm.node_degrees(|_| true);
+        assert_eq!(degrees[&junction], 3);
+        assert_eq!(osm.junctions(|_| true), vec![junction]);
+
+        let mut dead_ends = vec![id(0.0, 0.0), id(2.0, 0.0), id(1.0, 1.0)];
+        dead_ends.sort_unstable();
+        assert_eq!(osm.dead_ends(|_| true), dead_ends);
+    }
+
+    #[test]
+    fn closed_way_degrees() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)],
+            vec![("highway", "residential")],
+        );
+        builder.add_polyline(
+            vec![(1.0, 0.0), (2.0, 0.0)],
+            vec![("highway", "residential")],
+        );
+
+        let osm = builder.build();
+        let degrees = osm.node_degrees(|_| true);
+        assert_eq!(degrees.values().filter(|d| **d == 2).count(), 2);
+        assert_eq!(osm.junctions(|_| true).len(), 1);
+        assert_eq!(osm.dead_ends(|_| true).len(), 1);
+    }
+
+    #[test]
+    fn reachable_ways() {
+        let mut builder = OsmBuilder::default();
+        let highway = vec![("highway", "residential")];
+        let a = builder.add_polyline(vec![(0.0, 0.0), (1.0, 0.0)], highway.clone());
+        let b = builder.add_polyline(vec![(1.0, 0.0), (1.0, 1.0), (2.0, 1.0)], highway.clone());
+        let oneway = builder.add_polyline(
+            vec![(3.0, 1.0), (2.0, 1.0)],
+            vec![("highway", "residential"), ("oneway", "yes")],
+        );
+        let isolated = builder.add_polyline(vec![(5.0, 5.0), (6.0, 6.0)], highway);
+        builder.add_point((9.0, 9.0), vec![("amenity", "bench")]);
+
+        let osm = builder.build();
+        let node = |lat, lon| {
+            osm.nodes
+                .iter()
+                .find(|n| n.coordinate == (lat, lon).into())
+                .unwrap()
+                .id
+        };
+        let way_ids = |osm: &Osm| osm.ways.iter().map(|w| w.id).collect::<Vec<_>>();
+
+        let reachable = osm.reachable_ways(node(0.0, 0.0), false, |_| true);
+        assert_eq!(way_ids(&reachable), vec![a, b, oneway]);
+        assert_eq!(reachable.nodes.len(), 5);
+        assert!(!way_ids(&reachable).contains(&isolated));
+
+        // The oneway can't be entered from its end.
+        let reachable = osm.reachable_ways(node(0.0, 0.0), true, |_| true);
+        assert_eq!(way_ids(&reachable), vec![a, b]);
+        assert_eq!(reachable.nodes.len(), 4);
+
+        // But it leads into the network from its start.
+        let reachable = osm.reachable_ways(node(3.0, 1.0), true, |_| true);
+        assert_eq!(way_ids(&reachable), vec![a, b, oneway]);
+
+        // A seed without ways only gives the seed node.
+        let seed = node(9.0, 9.0);
+        let reachable = osm.reachable_ways(seed, true, |_| true);
+        assert!(reachable.ways.is_empty());
+        assert_eq!(reachable.nodes.len(), 1);
+        assert_eq!(reachable.nodes[0].id, seed);
+    }
+
+    #[test]
+    fn snap_to_way() {
+        let mut builder = OsmBuilder::default();
+        let way = builder.add_polyline(vec![(0.0, 0.0), (0.0, 0.01)], vec![("highway", "primary")]);
+        builder.add_polyline(vec![(1.0, 0.0), (1.0, 0.01)], vec![("highway", "primary")]);
+
+        let osm = builder.build();
+        let snap = osm.snap_to_way((0.0001, 0.004).into(), |_| true).unwrap();
+        assert_eq!(snap.way, way);
+        assert_eq!(snap.segment, 0);
+        assert_eq!(snap.coordinate, (0.0, 0.004).into());
+        assert!(
+            (snap.distance - 11.12).abs() < 0.01,
+            "distance {}",
+            snap.distance
+        );
+
+        // Beyond the end of the way, the end node is the nearest point.
+        let c = (0.0001, 0.0101).into();
+        let snap = osm.snap_to_way(c, |_| true).unwrap();
+        assert_eq!(
+            snap,
+            SnapResult {
+                way,
+                segment: 0,
+                coordinate: (0.0, 0.01).into(),
+                distance: snap.distance,
+            }
+        );
+        let expected = c.distance_to((0.0, 0.01).into());
+        assert!((snap.distance - expected).abs() < 0.01);
+
+        assert_eq!(osm.snap_to_way(c, |_| false), None);
+    }
+}
@@ -59,20 +59,29 @@
 //! [`error`]: error/index.html
 extern crate chrono;
 
+mod csv;
 pub mod error;
+mod json;
 mod o5m;
 mod xml;
 
+use self::chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
+pub use self::csv::nodes_to_csv;
 use self::error::*;
-use self::o5m::O5mWriter;
-use self::xml::XmlWriter;
-use crate::osm_io::o5m::O5mReader;
-use crate::osm_io::xml::XmlReader;
-use crate::Osm;
+pub use self::json::{JsonReader, JsonWriter};
+pub use self::o5m::varint;
+use self::o5m::O5mStreamWriter;
+pub use self::o5m::{O5mReader, O5mWriter};
+use self::xml::XmlStreamWriter;
+pub use self::xml::{OscWriter, XmlReader, XmlWriter};
+use crate::geo::Boundary;
+use crate::{Meta, Node, Osm, Relation, Warning, Way};
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
-use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 
 /// Represent a osm file format.
 ///
@@ -86,12 +95,101 @@ use std::path::Path;
 /// assert_eq!("osm".try_into(), Ok(FileFormat::Xml));
 /// assert_eq!(Path::new("./path/file.o5m").try_into(), Ok(FileFormat::O5m));
 /// assert_eq!(FileFormat::from("o5m"), Some(FileFormat::O5m));
+/// assert_eq!(FileFormat::from("json"), Some(FileFormat::OsmJson));
 /// ```
 /// [`file formats`]: https://wiki.openstreetmap.org/wiki/OSM_file_formats
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum FileFormat {
     Xml,
     O5m,
+    /// The json format of the Overpass API.
+    OsmJson,
+}
+
+/// Compression of the input to a reader, see [`create_reader_compressed`].
+///
+/// Each compression requires the feature named after the crate implementing it: `flate2` for
+/// gzip, `zstd` for zstd and `bzip2` for bzip2.
+///
+/// [`create_reader_compressed`]: fn.create_reader_compressed.html
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+/// Options for reading, used to make the readers stricter than the default.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::osm_io::{create_reader_with_options, FileFormat, ReadOptions};
+/// let options = ReadOptions {
+///     strict_tags: true,
+///     ..ReadOptions::default()
+/// };
+///
+/// let xml = r#"<node id="1" lat="1" lon="1"><tag k="a" v="1"/><tag k="a" v="2"/></node>"#;
+/// let mut reader = create_reader_with_options(xml.as_bytes(), FileFormat::Xml, options);
+/// assert!(reader.read().is_err());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ReadOptions {
+    /// Return a parse error if an element has multiple tags with the same key.
+    pub strict_tags: bool,
+    /// Return a parse error if a way has fewer nodes than this. Defaults to 0, i.e. no check.
+    pub min_way_nodes: usize,
+    /// Return a `TooManyElements` error if the map has more nodes, ways and relations in total
+    /// than this. Defaults to `None`, i.e. no limit.
+    pub max_elements: Option<usize>,
+    /// Only keep ways and relations matching the filter, see `create_filtered_reader`. Defaults to
+    /// `None`, i.e. all elements are kept.
+    pub tag_filter: Option<TagFilter>,
+}
+
+/// Tag predicates used to keep only matching ways and relations when reading, see
+/// [`create_filtered_reader`]. An element matches if it has any of the tags, or any tag with one
+/// of the keys.
+///
+/// All nodes are kept by default, since the matching ways need them. Enable `prune_nodes` to
+/// remove nodes that are neither used nor tagged after reading, see `Osm::remove_orphan_nodes`.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::osm_io::TagFilter;
+/// let filter = TagFilter::default()
+///     .tag("amenity", "parking")
+///     .key("building")
+///     .prune_nodes(true);
+/// ```
+///
+/// [`create_filtered_reader`]: fn.create_filtered_reader.html
+#[derive(Debug, Default, Clone)]
+pub struct TagFilter {
+    /// Keys with the required value, or `None` if any value matches.
+    tags: Vec<(String, Option<String>)>,
+    prune_nodes: bool,
+}
+
+/// Options for writing, used to avoid writing elements other tools consider invalid.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::osm_io::{create_writer_with_options, FileFormat, WriteOptions};
+/// # use vadeen_osm::OsmBuilder;
+/// let options = WriteOptions::default().skip_empty(true);
+/// let mut writer = create_writer_with_options(Vec::new(), FileFormat::Xml, options);
+/// writer.write(&OsmBuilder::default().build()).unwrap();
+/// ```
+///
+/// The struct is non exhaustive so that options can be added later, create it with `default()`
+/// and the builder methods.
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct WriteOptions {
+    /// Skip ways with less than two nodes and relations without members.
+    pub skip_empty: bool,
 }
 
 /// Writer for the osm formats.
@@ -106,9 +204,70 @@ pub trait OsmRead {
     fn read(&mut self) -> std::result::Result<Osm, Error>;
 }
 
+/// Receiver of the data read by the readers. Implemented by `Osm` when reading a whole map, and
+/// by the stream writers used by [`convert`].
+///
+/// [`convert`]: fn.convert.html
+pub(crate) trait ElementSink {
+    /// Attributes of the `<osm>` element, only read from the xml format.
+    fn set_attributes(&mut self, _attributes: HashMap<String, String>) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_boundary(&mut self, boundary: Boundary) -> Result<()>;
+
+    fn add_node(&mut self, node: Node) -> Result<()>;
+
+    fn add_way(&mut self, way: Way) -> Result<()>;
+
+    fn add_relation(&mut self, relation: Relation) -> Result<()>;
+
+    /// Number of elements received, used to check `ReadOptions::max_elements`.
+    fn element_count(&self) -> usize;
+
+    /// Called when all data is read.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ElementSink for Osm {
+    fn set_attributes(&mut self, attributes: HashMap<String, String>) -> Result<()> {
+        self.extra_attributes = attributes;
+        Ok(())
+    }
+
+    fn set_boundary(&mut self, boundary: Boundary) -> Result<()> {
+        self.boundary = Some(boundary);
+        Ok(())
+    }
+
+    fn add_node(&mut self, node: Node) -> Result<()> {
+        Osm::add_node(self, node);
+        Ok(())
+    }
+
+    fn add_way(&mut self, way: Way) -> Result<()> {
+        Osm::add_way(self, way);
+        Ok(())
+    }
+
+    fn add_relation(&mut self, relation: Relation) -> Result<()> {
+        Osm::add_relation(self, relation);
+        Ok(())
+    }
+
+    fn element_count(&self) -> usize {
+        self.len()
+    }
+}
+
 /// Convenience function for easily reading osm files.
 /// Format is determined from file ending.
 ///
+/// Files ending with `.bz2`, e.g. `map.osm.bz2`, are decompressed when the `bzip2` feature is
+/// enabled. The format is then determined from the ending before `.bz2`.
+///
 /// # Example
 /// ```rust,no_run
 /// # use vadeen_osm::osm_io::error::Result;
@@ -123,12 +282,49 @@ pub trait OsmRead {
 /// # }
 /// ```
 pub fn read<P: AsRef<Path>>(path: P) -> Result<Osm> {
-    let format = path.as_ref().try_into()?;
+    let path = path.as_ref();
+    if path.extension() == Some("bz2".as_ref()) {
+        return read_bzip2(path);
+    }
+
+    let format = path.try_into()?;
     let file = File::open(path)?;
     let mut reader = create_reader(BufReader::new(file), format);
     reader.read()
 }
 
+/// Same as [`read`], but also returns warnings about suspicious geometry in the map, e.g. ways
+/// with duplicate consecutive nodes. See `Osm::warnings`.
+///
+/// [`read`]: fn.read.html
+pub fn read_with_warnings<P: AsRef<Path>>(path: P) -> Result<(Osm, Vec<Warning>)> {
+    let osm = read(path)?;
+    let warnings = osm.warnings();
+    Ok((osm, warnings))
+}
+
+/// Read a bzip2 compressed file, e.g. `map.osm.bz2`. The format is determined from the extension
+/// before `.bz2`.
+#[cfg(feature = "bzip2")]
+fn read_bzip2(path: &Path) -> Result<Osm> {
+    let format = Path::new(path.file_stem().unwrap_or_default()).try_into()?;
+    let file = File::open(path)?;
+    let decoder = bzip2::bufread::BzDecoder::new(BufReader::new(file));
+    let mut reader = create_reader(BufReader::new(decoder), format);
+    reader.read()
+}
+
+#[cfg(not(feature = "bzip2"))]
+fn read_bzip2(path: &Path) -> Result<Osm> {
+    Err(Error::new(
+        ErrorKind::InvalidFileFormat,
+        Some(format!(
+            "Reading '{}' requires the bzip2 feature.",
+            path.display()
+        )),
+    ))
+}
+
 /// Convenience function for easily writing osm files.
 /// Format is determined from file ending.
 ///
@@ -152,7 +348,56 @@ pub fn write<P: AsRef<Path>>(path: P, osm: &Osm) -> Result<()> {
     let format = path.as_ref().try_into()?;
     let file = File::create(path)?;
     let mut writer = create_writer(file, format);
-    writer.write(&osm)
+    writer.write(osm)
+}
+
+/// Same as `write`, but the map is first written to a temporary file in the same directory,
+/// which then replaces the file at `path`. Readers never see a partially written file, and an
+/// existing file is left untouched if writing fails.
+///
+/// # Example
+/// ```rust,no_run
+/// # use vadeen_osm::OsmBuilder;
+/// # use vadeen_osm::osm_io::error::Result;
+/// # use vadeen_osm::osm_io::write_atomic;
+/// # fn main() -> Result<()> {
+/// let osm = OsmBuilder::default().build();
+/// write_atomic("map.o5m", &osm)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_atomic<P: AsRef<Path>>(path: P, osm: &Osm) -> Result<()> {
+    let path = path.as_ref();
+    let format = path.try_into()?;
+    replace_file(path, |file| {
+        let mut writer = create_writer(file, format);
+        writer.write(osm)?;
+        Ok(writer.into_inner())
+    })
+}
+
+/// Create a temporary file next to `path`, fill it with `write` and rename it to `path`. The
+/// temporary file is removed if any step fails.
+fn replace_file<F: FnOnce(File) -> Result<File>>(path: &Path, write: F) -> Result<()> {
+    let temp_path = temporary_path(path);
+    let result = File::create(&temp_path)
+        .map_err(Error::from)
+        .and_then(write)
+        .and_then(|file| Ok(file.sync_all()?))
+        .and_then(|_| Ok(fs::rename(&temp_path, path)?));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Hidden file in the same directory as `path`, e.g. `.map.o5m.1234.tmp` for `map.o5m`.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
 }
 
 /// Creates an `OsmReader` appropriate to the provided `FileFormat`.
@@ -179,16 +424,131 @@ pub fn write<P: AsRef<Path>>(path: P, osm: &Osm) -> Result<()> {
 /// # Ok(())
 /// # }
 /// ```
-pub fn create_reader<'a, R: BufRead + 'a>(
+pub fn create_reader<'a, R: BufRead + 'a>(reader: R, format: FileFormat) -> Box<dyn OsmRead + 'a> {
+    match format {
+        FileFormat::Xml => Box::new(XmlReader::new(reader)),
+        FileFormat::O5m => Box::new(O5mReader::new(reader)),
+        FileFormat::OsmJson => Box::new(JsonReader::new(reader)),
+    }
+}
+
+/// Same as `create_reader`, but with options for reading.
+pub fn create_reader_with_options<'a, R: BufRead + 'a>(
     reader: R,
     format: FileFormat,
+    options: ReadOptions,
 ) -> Box<dyn OsmRead + 'a> {
     match format {
-        FileFormat::Xml => Box::new(XmlReader::new(reader)),
-        FileFormat::O5m => Box::new(O5mReader::new(reader)),
+        FileFormat::Xml => Box::new(XmlReader::new(reader).with_options(options)),
+        FileFormat::O5m => Box::new(O5mReader::new(reader).with_options(options)),
+        FileFormat::OsmJson => Box::new(JsonReader::new(reader).with_options(options)),
     }
 }
 
+/// Same as `create_reader`, but only ways and relations matching `filter` are added to the map.
+/// Elements are filtered as they are parsed, so the rest are never stored.
+///
+/// # Example
+/// ```
+/// # use vadeen_osm::osm_io::{create_filtered_reader, FileFormat, TagFilter};
+/// let xml = r#"<osm>
+///     <way id="1"><tag k="amenity" v="parking"/></way>
+///     <way id="2"><tag k="highway" v="primary"/></way>
+/// </osm>"#;
+///
+/// let filter = TagFilter::default().tag("amenity", "parking");
+/// let osm = create_filtered_reader(xml.as_bytes(), FileFormat::Xml, filter)
+///     .read()
+///     .unwrap();
+/// assert_eq!(osm.ways.len(), 1);
+/// ```
+pub fn create_filtered_reader<'a, R: BufRead + 'a>(
+    reader: R,
+    format: FileFormat,
+    filter: TagFilter,
+) -> Box<dyn OsmRead + 'a> {
+    let options = ReadOptions {
+        tag_filter: Some(filter),
+        ..ReadOptions::default()
+    };
+    create_reader_with_options(reader, format, options)
+}
+
+/// Same as `create_reader`, but the input is decompressed before it is read. Useful for
+/// compressed data without a file name, e.g. from the network. Returns an error if the feature
+/// required by the compression is not enabled, see [`Compression`].
+///
+/// # Example
+/// ```rust,no_run
+/// # use vadeen_osm::osm_io::{create_reader_compressed, Compression, FileFormat};
+/// # use vadeen_osm::osm_io::error::Result;
+/// # use std::io::BufReader;
+/// # use std::net::TcpStream;
+/// # fn main() -> Result<()> {
+/// let stream = TcpStream::connect("127.0.0.1:8080")?;
+/// let mut reader =
+///     create_reader_compressed(BufReader::new(stream), FileFormat::O5m, Compression::Gzip)?;
+/// let osm = reader.read()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Compression`]: enum.Compression.html
+pub fn create_reader_compressed<'a, R: BufRead + 'a>(
+    reader: R,
+    format: FileFormat,
+    compression: Compression,
+) -> Result<Box<dyn OsmRead + 'a>> {
+    let decoder = match compression {
+        Compression::None => return Ok(create_reader(reader, format)),
+        Compression::Gzip => gzip_decoder(reader)?,
+        Compression::Zstd => zstd_decoder(reader)?,
+        Compression::Bzip2 => bzip2_decoder(reader)?,
+    };
+    Ok(create_reader(BufReader::new(decoder), format))
+}
+
+#[cfg(feature = "flate2")]
+fn gzip_decoder<'a, R: BufRead + 'a>(reader: R) -> Result<Box<dyn Read + 'a>> {
+    Ok(Box::new(flate2::bufread::MultiGzDecoder::new(reader)))
+}
+
+#[cfg(not(feature = "flate2"))]
+fn gzip_decoder<'a, R: BufRead + 'a>(_reader: R) -> Result<Box<dyn Read + 'a>> {
+    Err(missing_compression_feature("Gzip", "flate2"))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decoder<'a, R: BufRead + 'a>(reader: R) -> Result<Box<dyn Read + 'a>> {
+    Ok(Box::new(zstd::Decoder::with_buffer(reader)?))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decoder<'a, R: BufRead + 'a>(_reader: R) -> Result<Box<dyn Read + 'a>> {
+    Err(missing_compression_feature("Zstd", "zstd"))
+}
+
+#[cfg(feature = "bzip2")]
+fn bzip2_decoder<'a, R: BufRead + 'a>(reader: R) -> Result<Box<dyn Read + 'a>> {
+    Ok(Box::new(bzip2::bufread::BzDecoder::new(reader)))
+}
+
+#[cfg(not(feature = "bzip2"))]
+fn bzip2_decoder<'a, R: BufRead + 'a>(_reader: R) -> Result<Box<dyn Read + 'a>> {
+    Err(missing_compression_feature("Bzip2", "bzip2"))
+}
+
+#[cfg(not(all(feature = "flate2", feature = "zstd", feature = "bzip2")))]
+fn missing_compression_feature(compression: &str, feature: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidFileFormat,
+        Some(format!(
+            "{} compression requires the {} feature.",
+            compression, feature
+        )),
+    )
+}
+
 /// Creates an `OsmWriter` appropriate to the provided `FileFormat`.
 ///
 /// # Example
@@ -220,6 +580,243 @@ pub fn create_writer<'a, W: Write + 'a>(
     match format {
         FileFormat::O5m => Box::new(O5mWriter::new(writer)),
         FileFormat::Xml => Box::new(XmlWriter::new(writer)),
+        FileFormat::OsmJson => Box::new(JsonWriter::new(writer)),
+    }
+}
+
+/// Convert between formats element by element, without reading the whole map into memory.
+///
+/// Only the xml and o5m formats are streamed, json input or output is converted by reading the
+/// whole map. Since the boundary is not calculated, the output only has a boundary if the input
+/// has one.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::osm_io::{convert, create_reader, FileFormat};
+/// let xml = r#"<osm><node id="1" lat="1" lon="2"/></osm>"#;
+/// let mut o5m = Vec::new();
+/// convert(xml.as_bytes(), FileFormat::Xml, &mut o5m, FileFormat::O5m).unwrap();
+///
+/// let osm = create_reader(&o5m[..], FileFormat::O5m).read().unwrap();
+/// assert_eq!(osm.nodes.len(), 1);
+/// ```
+pub fn convert<R: BufRead, W: Write>(
+    reader: R,
+    in_format: FileFormat,
+    writer: W,
+    out_format: FileFormat,
+) -> Result<()> {
+    match out_format {
+        FileFormat::Xml => {
+            let sink = XmlStreamWriter::new(XmlWriter::new(writer));
+            convert_into(reader, in_format, sink)
+        }
+        FileFormat::O5m => {
+            let sink = O5mStreamWriter::new(O5mWriter::new(writer))?;
+            convert_into(reader, in_format, sink)
+        }
+        FileFormat::OsmJson => {
+            let osm = create_reader(reader, in_format).read()?;
+            create_writer(writer, out_format).write(&osm)
+        }
+    }
+}
+
+/// Read everything from `reader` into `sink`, one element at a time for the streamed formats.
+fn convert_into<R: BufRead, S: ElementSink>(
+    reader: R,
+    in_format: FileFormat,
+    mut sink: S,
+) -> Result<()> {
+    match in_format {
+        FileFormat::Xml => XmlReader::new(reader).read_into(&mut sink)?,
+        FileFormat::O5m => O5mReader::new(reader).read_into(&mut sink)?,
+        FileFormat::OsmJson => {
+            let osm = JsonReader::new(reader).read()?;
+            if let Some(boundary) = osm.boundary {
+                sink.set_boundary(boundary)?;
+            }
+            for node in osm.nodes {
+                sink.add_node(node)?;
+            }
+            for way in osm.ways {
+                sink.add_way(way)?;
+            }
+            for relation in osm.relations {
+                sink.add_relation(relation)?;
+            }
+        }
+    }
+    sink.finish()
+}
+
+/// Same as `create_writer`, but with options for writing.
+pub fn create_writer_with_options<'a, W: Write + 'a>(
+    writer: W,
+    format: FileFormat,
+    options: WriteOptions,
+) -> Box<dyn OsmWrite<W> + 'a> {
+    match format {
+        FileFormat::O5m => Box::new(O5mWriter::new(writer).with_options(options)),
+        FileFormat::Xml => Box::new(XmlWriter::new(writer).with_options(options)),
+        FileFormat::OsmJson => Box::new(JsonWriter::new(writer).with_options(options)),
+    }
+}
+
+impl ReadOptions {
+    /// Check that the meta data of an element fulfills the options.
+    pub(crate) fn check_meta(&self, element: &str, id: i64, meta: &Meta) -> Result<()> {
+        if self.strict_tags {
+            if let Some(key) = meta.duplicate_tag_key() {
+                return Err(Error::new(
+                    ErrorKind::ParseError,
+                    Some(format!(
+                        "Duplicate tag key '{}' on {} {}.",
+                        key, element, id
+                    )),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the number of elements read is within `max_elements`.
+    pub(crate) fn check_element_count(&self, count: usize) -> Result<()> {
+        match self.max_elements {
+            Some(limit) if count > limit => Err(Error::new(
+                ErrorKind::TooManyElements { limit },
+                Some(format!("More than {} elements.", limit)),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that a way has at least `min_way_nodes` nodes.
+    pub(crate) fn check_way(&self, way: &Way) -> Result<()> {
+        if way.refs.len() < self.min_way_nodes {
+            return Err(Error::new(
+                ErrorKind::ParseError,
+                Some(format!(
+                    "Way {} has {} nodes, at least {} required.",
+                    way.id,
+                    way.refs.len(),
+                    self.min_way_nodes
+                )),
+            ));
+        }
+        Ok(())
+    }
+
+    /// True if a way or relation with this meta data passes the tag filter.
+    pub(crate) fn include(&self, meta: &Meta) -> bool {
+        match &self.tag_filter {
+            Some(filter) => filter.matches(meta),
+            None => true,
+        }
+    }
+
+    /// Post processing of the map when all elements are read. The boundary read from the file is
+    /// frozen while reading, so nodes outside of it do not expand it. It is thawed here, so that
+    /// nodes added to the map later do.
+    pub(crate) fn finish(&self, osm: &mut Osm) {
+        if let Some(boundary) = osm.boundary.as_mut() {
+            boundary.thaw();
+        }
+
+        if let Some(filter) = &self.tag_filter {
+            if filter.prune_nodes {
+                osm.remove_orphan_nodes();
+            }
+        }
+    }
+}
+
+impl TagFilter {
+    /// Match elements with a tag with this key, regardless of value.
+    pub fn key(mut self, key: &str) -> Self {
+        self.tags.push((key.to_owned(), None));
+        self
+    }
+
+    /// Match elements with this tag.
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.push((key.to_owned(), Some(value.to_owned())));
+        self
+    }
+
+    /// Toggle removal of untagged nodes not used by any kept way or relation. Disabled by default.
+    pub fn prune_nodes(mut self, prune: bool) -> Self {
+        self.prune_nodes = prune;
+        self
+    }
+
+    /// True if the meta data has any of the tags of the filter.
+    pub fn matches(&self, meta: &Meta) -> bool {
+        meta.tags.iter().any(|tag| {
+            self.tags.iter().any(|(key, value)| match value {
+                Some(value) => *key == tag.key && *value == tag.value,
+                None => *key == tag.key,
+            })
+        })
+    }
+}
+
+impl WriteOptions {
+    /// Set `skip_empty`, see the field.
+    pub fn skip_empty(mut self, skip_empty: bool) -> Self {
+        self.skip_empty = skip_empty;
+        self
+    }
+
+    /// True if the way should be written.
+    pub(crate) fn include_way(&self, way: &Way) -> bool {
+        !self.skip_empty || way.refs.len() >= 2
+    }
+
+    /// True if the relation should be written.
+    pub(crate) fn include_relation(&self, relation: &Relation) -> bool {
+        !self.skip_empty || !relation.members.is_empty()
+    }
+}
+
+/// Validate that a coordinate is within ±90 latitude and ±180 longitude.
+pub(crate) fn validate_coordinate(lat: f64, lon: f64) -> Result<()> {
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidCoordinate { lat, lon },
+            Some(format!(
+                "Coordinate (lat {}, lon {}) is out of range.",
+                lat, lon
+            )),
+        ))
+    }
+}
+
+/// Parse a timestamp to seconds since the epoch. Accepts RFC 3339 timestamps with any offset,
+/// which is converted to UTC, and optional fractional seconds, which are truncated. Timestamps
+/// without an offset are read as UTC.
+pub(crate) fn parse_timestamp(time_str: &str) -> Result<i64> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time_str) {
+        return Ok(time.timestamp());
+    }
+
+    match NaiveDateTime::parse_from_str(time_str, "%Y-%m-%dT%H:%M:%S%.f") {
+        Ok(time) => Ok(time.and_utc().timestamp()),
+        Err(_) => Err(Error::new(
+            ErrorKind::ParseError,
+            Some(format!("Invalid timestamp '{}'", time_str)),
+        )),
+    }
+}
+
+/// Format seconds since the epoch as a UTC timestamp, e.g. `2020-01-02T03:04:05Z`. The reverse
+/// of `parse_timestamp`. Fails for timestamps outside of the range chrono can represent.
+pub(crate) fn format_timestamp(timestamp: i64) -> Result<String> {
+    match Utc.timestamp_opt(timestamp, 0) {
+        LocalResult::Single(time) => Ok(time.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()),
+        _ => Err(Error::new(ErrorKind::InvalidTimestamp { timestamp }, None)),
     }
 }
 
@@ -228,16 +825,38 @@ impl FileFormat {
         match s {
             "osm" => Some(FileFormat::Xml),
             "o5m" => Some(FileFormat::O5m),
+            "json" => Some(FileFormat::OsmJson),
             _ => None,
         }
     }
+
+    /// The file extension of the format, without the dot. The reverse of `FileFormat::from`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::osm_io::FileFormat;
+    /// assert_eq!(FileFormat::Xml.extension(), "osm");
+    /// assert_eq!(FileFormat::from(FileFormat::O5m.extension()), Some(FileFormat::O5m));
+    /// ```
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileFormat::Xml => "osm",
+            FileFormat::O5m => "o5m",
+            FileFormat::OsmJson => "json",
+        }
+    }
+
+    /// All supported formats.
+    pub fn all() -> &'static [FileFormat] {
+        &[FileFormat::Xml, FileFormat::O5m, FileFormat::OsmJson]
+    }
 }
 
 impl TryFrom<&str> for FileFormat {
     type Error = Error;
 
     fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        if let Some(format) = FileFormat::from(&value) {
+        if let Some(format) = FileFormat::from(value) {
             Ok(format)
         } else {
             Err(Error::new(
@@ -277,10 +896,86 @@ impl TryFrom<&Path> for FileFormat {
 
 #[cfg(test)]
 mod tests {
-    use crate::osm_io::{read, FileFormat};
+    use crate::osm_io::error::ErrorKind;
+    use crate::osm_io::{
+        create_reader_compressed, format_timestamp, parse_timestamp, read, read_with_warnings,
+        replace_file, temporary_path, write_atomic, Compression, FileFormat,
+    };
+    use crate::{OsmBuilder, Warning};
     use std::convert::TryInto;
+    use std::fs;
+    use std::io::{self, Write};
     use std::path::Path;
 
+    #[test]
+    fn format_timestamps() {
+        assert_eq!(format_timestamp(0).unwrap(), "1970-01-01T00:00:00Z");
+        let formatted = format_timestamp(1_577_934_245).unwrap();
+        assert_eq!(formatted, "2020-01-02T03:04:05Z");
+        assert_eq!(parse_timestamp(&formatted).unwrap(), 1_577_934_245);
+
+        let error = format_timestamp(i64::MAX).unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::InvalidTimestamp {
+            timestamp: i64::MAX
+        }));
+    }
+
+    #[test]
+    fn write_atomic_replaces_file() {
+        let path = std::env::temp_dir().join("vadeen_osm_write_atomic.osm");
+        fs::write(&path, "old content").unwrap();
+
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        write_atomic(&path, &builder.build()).unwrap();
+
+        let osm = read(&path).unwrap();
+        assert_eq!(osm.nodes.len(), 1);
+        assert!(!temporary_path(&path).exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_atomic_write_keeps_file() {
+        let path = std::env::temp_dir().join("vadeen_osm_failed_write_atomic.osm");
+        fs::write(&path, "old content").unwrap();
+
+        let result = replace_file(&path, |mut file| {
+            file.write_all(b"<osm>")?;
+            Err(io::Error::other("Disk full.").into())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old content");
+        assert!(!temporary_path(&path).exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_duplicate_consecutive_ref_warning() {
+        let path = std::env::temp_dir().join("vadeen_osm_read_with_warnings.osm");
+        fs::write(
+            &path,
+            r#"<osm version="0.6">
+                <node id="1" lat="1" lon="1"/>
+                <node id="2" lat="2" lon="2"/>
+                <way id="3"><nd ref="1"/><nd ref="2"/><nd ref="2"/></way>
+            </osm>"#,
+        )
+        .unwrap();
+
+        let (osm, warnings) = read_with_warnings(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(osm.ways.len(), 1);
+        assert_eq!(
+            warnings,
+            vec![Warning::DuplicateConsecutiveRef {
+                way_id: 3,
+                node_id: 2
+            }]
+        );
+    }
+
     #[test]
     fn file_format_from_path() {
         let path = Path::new("test.o5m");
@@ -310,6 +1005,35 @@ mod tests {
         assert_eq!(format, Ok(FileFormat::Xml));
     }
 
+    #[test]
+    fn create_uncompressed_reader() {
+        let xml = r#"<osm><node id="1" lat="1" lon="1"/></osm>"#;
+        let mut reader =
+            create_reader_compressed(xml.as_bytes(), FileFormat::Xml, Compression::None).unwrap();
+        assert_eq!(reader.read().unwrap().nodes.len(), 1);
+    }
+
+    #[cfg(not(feature = "flate2"))]
+    #[test]
+    fn create_gzip_reader_without_feature() {
+        let result = create_reader_compressed(&b""[..], FileFormat::Xml, Compression::Gzip);
+        let error = result.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Gzip compression requires the flate2 feature."
+        );
+    }
+
+    #[test]
+    fn file_format_extension() {
+        assert_eq!(FileFormat::Xml.extension(), "osm");
+        assert!(FileFormat::all().contains(&FileFormat::Xml));
+        assert!(FileFormat::all().contains(&FileFormat::O5m));
+        for format in FileFormat::all() {
+            assert_eq!(FileFormat::from(format.extension()), Some(*format));
+        }
+    }
+
     #[test]
     fn read_invalid_format() {
         let err = read("osm.invalid").unwrap_err();
@@ -0,0 +1,89 @@
+//! Export of nodes to CSV, see [`nodes_to_csv`].
+//!
+//! [`nodes_to_csv`]: fn.nodes_to_csv.html
+use crate::Osm;
+
+/// Export all nodes as CSV. The header is `id,lat,lon` followed by `tag_keys`, and there is one
+/// row per node. Tag columns are empty for nodes missing the tag.
+///
+/// Values are escaped according to [`RFC 4180`].
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::OsmBuilder;
+/// # use vadeen_osm::osm_io::nodes_to_csv;
+/// let mut builder = OsmBuilder::default();
+/// builder.add_point((60.675, 17.13625), vec![("amenity", "cafe")]);
+///
+/// let csv = nodes_to_csv(&builder.build(), &["amenity"]);
+/// assert_eq!(csv, "id,lat,lon,amenity\r\n1,60.675,17.13625,cafe\r\n");
+/// ```
+///
+/// [`RFC 4180`]: https://tools.ietf.org/html/rfc4180
+pub fn nodes_to_csv(osm: &Osm, tag_keys: &[&str]) -> String {
+    let mut csv = String::new();
+
+    let header: Vec<&str> = ["id", "lat", "lon"]
+        .iter()
+        .chain(tag_keys)
+        .copied()
+        .collect();
+    write_row(&mut csv, header.into_iter());
+
+    for node in &osm.nodes {
+        let id = node.id.to_string();
+        let lat = node.coordinate.lat().to_string();
+        let lon = node.coordinate.lon().to_string();
+        let tags = tag_keys.iter().map(|key| node.meta.tag(key).unwrap_or(""));
+
+        let fields = [id.as_str(), lat.as_str(), lon.as_str()];
+        write_row(&mut csv, fields.iter().copied().chain(tags));
+    }
+
+    csv
+}
+
+fn write_row<'a, I: Iterator<Item = &'a str>>(csv: &mut String, fields: I) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        write_field(csv, field);
+    }
+    csv.push_str("\r\n");
+}
+
+/// Fields containing separators, quotes or line breaks are quoted, and quotes are doubled.
+fn write_field(csv: &mut String, field: &str) {
+    if field.contains(&[',', '"', '\r', '\n'][..]) {
+        csv.push('"');
+        csv.push_str(&field.replace('"', "\"\""));
+        csv.push('"');
+    } else {
+        csv.push_str(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::osm_io::nodes_to_csv;
+    use crate::OsmBuilder;
+
+    #[test]
+    fn nodes_with_tags() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point(
+            (60.675, 17.13625),
+            vec![("name", "Café, \"Gamla\""), ("amenity", "cafe")],
+        );
+        builder.add_point((-1.5, 2.0), vec![("amenity", "bench")]);
+
+        let csv = nodes_to_csv(&builder.build(), &["name", "amenity"]);
+        assert_eq!(
+            csv,
+            "id,lat,lon,name,amenity\r\n\
+             1,60.675,17.13625,\"Café, \"\"Gamla\"\"\",cafe\r\n\
+             2,-1.5,2,,bench\r\n"
+        );
+    }
+}
@@ -10,6 +10,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub struct Error {
     repr: Repr,
     message: Option<String>,
+    position: Option<(u32, u32)>,
 }
 
 /// It will make it possible to change internals without breaking change.
@@ -26,6 +27,15 @@ pub enum ErrorKind {
     /// Parse error. E.g. invalid data in file.
     ParseError,
 
+    /// Coordinate outside of the valid range, i.e. ±90 latitude and ±180 longitude.
+    InvalidCoordinate { lat: f64, lon: f64 },
+
+    /// More elements than allowed by `ReadOptions::max_elements`.
+    TooManyElements { limit: usize },
+
+    /// Timestamp in seconds since the epoch that can not be represented as a date.
+    InvalidTimestamp { timestamp: i64 },
+
     /// IO error. E.g. file not found, permission denied.
     IO(io::Error),
 }
@@ -35,6 +45,7 @@ impl Error {
         Error {
             repr: Simple(kind),
             message,
+            position: None,
         }
     }
 
@@ -46,15 +57,82 @@ impl Error {
         self.message = Some(message);
     }
 
+    /// Set line and column where the error occurred. Only used by text based formats.
+    pub fn set_position(&mut self, line: u32, column: u32) {
+        self.position = Some((line, column));
+    }
+
+    /// Line where the error occurred, if known.
+    pub fn line(&self) -> Option<u32> {
+        self.position.map(|(line, _)| line)
+    }
+
+    /// Column where the error occurred, if known. The column is counted in bytes from the start of
+    /// the line, starting at 1.
+    pub fn column(&self) -> Option<u32> {
+        self.position.map(|(_, column)| column)
+    }
+
     /// Returns reference to error kind.
     pub fn kind(&self) -> &ErrorKind {
         match &self.repr {
-            Simple(e) => &e,
+            Simple(e) => e,
         }
     }
+
+    /// Check if the error is of a specific kind. IO errors are equal if their
+    /// `std::io::ErrorKind` are equal.
+    pub fn kind_eq(&self, other: &ErrorKind) -> bool {
+        self.kind().shallow_eq(other)
+    }
 }
 
 impl ErrorKind {
+    /// Clones the error kind. Since `io::Error` can not be cloned, IO errors are recreated from
+    /// their `std::io::ErrorKind`, i.e. the inner error and description are lost.
+    pub fn clone_shallow(&self) -> ErrorKind {
+        match self {
+            ErrorKind::InvalidFileFormat => ErrorKind::InvalidFileFormat,
+            ErrorKind::ParseError => ErrorKind::ParseError,
+            ErrorKind::InvalidCoordinate { lat, lon } => ErrorKind::InvalidCoordinate {
+                lat: *lat,
+                lon: *lon,
+            },
+            ErrorKind::TooManyElements { limit } => ErrorKind::TooManyElements { limit: *limit },
+            ErrorKind::InvalidTimestamp { timestamp } => ErrorKind::InvalidTimestamp {
+                timestamp: *timestamp,
+            },
+            IO(e) => IO(io::Error::from(e.kind())),
+        }
+    }
+
+    /// Compare kinds, IO errors are compared by their `std::io::ErrorKind`.
+    fn shallow_eq(&self, other: &ErrorKind) -> bool {
+        match (self, other) {
+            (ErrorKind::InvalidFileFormat, ErrorKind::InvalidFileFormat) => true,
+            (ErrorKind::ParseError, ErrorKind::ParseError) => true,
+            (
+                ErrorKind::InvalidCoordinate { lat, lon },
+                ErrorKind::InvalidCoordinate {
+                    lat: other_lat,
+                    lon: other_lon,
+                },
+            ) => lat == other_lat && lon == other_lon,
+            (
+                ErrorKind::TooManyElements { limit },
+                ErrorKind::TooManyElements { limit: other_limit },
+            ) => limit == other_limit,
+            (
+                ErrorKind::InvalidTimestamp { timestamp },
+                ErrorKind::InvalidTimestamp {
+                    timestamp: other_timestamp,
+                },
+            ) => timestamp == other_timestamp,
+            (IO(e), IO(other)) => e.kind() == other.kind(),
+            _ => false,
+        }
+    }
+
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             IO(e) => Some(e),
@@ -68,6 +146,13 @@ impl Display for ErrorKind {
         match self {
             ErrorKind::InvalidFileFormat => write!(f, "File format not recognized.")?,
             ErrorKind::ParseError => write!(f, "Unknown parse error occurred.")?,
+            ErrorKind::InvalidCoordinate { lat, lon } => {
+                write!(f, "Coordinate (lat {}, lon {}) is out of range.", lat, lon)?
+            }
+            ErrorKind::TooManyElements { limit } => write!(f, "More than {} elements.", limit)?,
+            ErrorKind::InvalidTimestamp { timestamp } => {
+                write!(f, "Timestamp {} is out of range.", timestamp)?
+            }
             IO(io_error) => match io_error.kind() {
                 io::ErrorKind::UnexpectedEof => write!(f, "Unexpected end of file.")?,
                 _ => write!(f, "IO error: {}", io_error)?,
@@ -100,6 +185,10 @@ impl std::error::Error for Error {
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        if let Some((line, column)) = self.position {
+            write!(f, "Line {}, column {}: ", line, column)?;
+        }
+
         match &self.message {
             Some(message) => write!(f, "{}", message),
             None => self.repr.fmt(f),
@@ -119,6 +208,36 @@ impl From<io::Error> for Error {
         Error {
             repr: Simple(IO(e)),
             message: None,
+            position: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::osm_io::error::{Error, ErrorKind};
+    use std::io;
+
+    #[test]
+    fn kind_eq() {
+        let parse_error = Error::new(ErrorKind::ParseError, None);
+        let format_error = Error::new(ErrorKind::InvalidFileFormat, None);
+
+        assert!(parse_error.kind_eq(&ErrorKind::ParseError));
+        assert!(!parse_error.kind_eq(&ErrorKind::InvalidFileFormat));
+        assert!(format_error.kind_eq(&ErrorKind::InvalidFileFormat));
+        assert!(!format_error.kind_eq(parse_error.kind()));
+    }
+
+    #[test]
+    fn clone_shallow() {
+        let error: Error = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        let kind = error.kind().clone_shallow();
+
+        assert!(error.kind_eq(&kind));
+        assert!(!error.kind_eq(&ErrorKind::IO(io::ErrorKind::UnexpectedEof.into())));
+        assert!(
+            Error::new(ErrorKind::ParseError, None).kind_eq(&ErrorKind::ParseError.clone_shallow())
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! Base module for reading and writing osm json data, as produced by the Overpass API.
+//! See: https://wiki.openstreetmap.org/wiki/OSM_JSON
+
+extern crate serde_json;
+
+mod reader;
+mod writer;
+
+pub use self::reader::*;
+pub use self::writer::*;
+use crate::osm_io::error::Error;
+use crate::osm_io::error::ErrorKind::ParseError;
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            return std::io::Error::from(e).into();
+        }
+        Error::new(ParseError, Some(e.to_string()))
+    }
+}
@@ -0,0 +1,413 @@
+use super::serde_json::{Map, Value};
+use crate::geo::{Boundary, Coordinate};
+use crate::osm_io::error::ErrorKind::ParseError;
+use crate::osm_io::error::{Error, Result};
+use crate::osm_io::{parse_timestamp, validate_coordinate, OsmRead, ReadOptions};
+use crate::{AuthorInformation, Meta, Node, Osm, Relation, RelationMember, Tag, Way};
+use std::convert::TryFrom;
+use std::io::BufRead;
+
+/// A reader for the osm json format.
+pub struct JsonReader<R: BufRead> {
+    inner: R,
+    options: ReadOptions,
+}
+
+/// A json object, i.e. an element, a member or the bounds.
+type Object = Map<String, Value>;
+
+impl<R: BufRead> JsonReader<R> {
+    pub fn new(inner: R) -> JsonReader<R> {
+        JsonReader {
+            inner,
+            options: ReadOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse one object of the `elements` array. Elements of unknown types are ignored.
+    fn parse_element(&self, osm: &mut Osm, element: &Object) -> Result<()> {
+        match get_str(element, "type")? {
+            "node" => {
+                let node = parse_node(element)?;
+                self.options.check_meta("node", node.id, &node.meta)?;
+                osm.add_node(node);
+            }
+            "way" => {
+                let way = parse_way(element)?;
+                self.options.check_meta("way", way.id, &way.meta)?;
+                self.options.check_way(&way)?;
+                if self.options.include(&way.meta) {
+                    osm.add_way(way);
+                }
+            }
+            "relation" => {
+                let relation = parse_relation(element)?;
+                self.options
+                    .check_meta("relation", relation.id, &relation.meta)?;
+                if self.options.include(&relation.meta) {
+                    osm.add_relation(relation);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> OsmRead for JsonReader<R> {
+    fn read(&mut self) -> std::result::Result<Osm, Error> {
+        let document: Value = serde_json::from_reader(&mut self.inner)?;
+        let document = as_object(&document, "document")?;
+
+        let mut osm = Osm::default();
+        if let Some(bounds) = document.get("bounds") {
+            osm.boundary = Some(parse_boundary(as_object(bounds, "bounds")?)?);
+        }
+
+        let elements = match document.get("elements") {
+            Some(Value::Array(elements)) => elements,
+            Some(_) => return Err(invalid_data("elements")),
+            None => return Err(missing_field("elements")),
+        };
+
+        for element in elements {
+            self.parse_element(&mut osm, as_object(element, "elements")?)?;
+            self.options.check_element_count(osm.len())?;
+        }
+
+        self.options.finish(&mut osm);
+        Ok(osm)
+    }
+}
+
+fn parse_boundary(bounds: &Object) -> Result<Boundary> {
+    Ok(Boundary {
+        min: Coordinate::new(get_f64(bounds, "minlat")?, get_f64(bounds, "minlon")?),
+        max: Coordinate::new(get_f64(bounds, "maxlat")?, get_f64(bounds, "maxlon")?),
+        freeze: true,
+    })
+}
+
+fn parse_node(element: &Object) -> Result<Node> {
+    let lat = get_f64(element, "lat")?;
+    let lon = get_f64(element, "lon")?;
+    validate_coordinate(lat, lon)?;
+
+    Ok(Node {
+        id: get_i64(element, "id")?,
+        coordinate: Coordinate::new(lat, lon),
+        meta: parse_meta(element)?,
+    })
+}
+
+/// Ways list their node ids in `nodes`. The `geometry` some tools add is ignored.
+fn parse_way(element: &Object) -> Result<Way> {
+    let refs = match element.get("nodes") {
+        Some(Value::Array(nodes)) => nodes
+            .iter()
+            .map(|id| id.as_i64().ok_or_else(|| invalid_data("nodes")))
+            .collect::<Result<Vec<_>>>()?,
+        Some(_) => return Err(invalid_data("nodes")),
+        None => Vec::new(),
+    };
+
+    Ok(Way {
+        id: get_i64(element, "id")?,
+        refs,
+        meta: parse_meta(element)?,
+    })
+}
+
+fn parse_relation(element: &Object) -> Result<Relation> {
+    let members = match element.get("members") {
+        Some(Value::Array(members)) => members
+            .iter()
+            .map(|member| parse_relation_member(as_object(member, "members")?))
+            .collect::<Result<Vec<_>>>()?,
+        Some(_) => return Err(invalid_data("members")),
+        None => Vec::new(),
+    };
+
+    Ok(Relation {
+        id: get_i64(element, "id")?,
+        members,
+        meta: parse_meta(element)?,
+    })
+}
+
+fn parse_relation_member(member: &Object) -> Result<RelationMember> {
+    let mem_ref = get_i64(member, "ref")?;
+    let mem_role = match member.get("role") {
+        Some(_) => get_str(member, "role")?.to_owned(),
+        None => String::new(),
+    };
+
+    match get_str(member, "type")? {
+        "node" => Ok(RelationMember::Node(mem_ref, mem_role)),
+        "way" => Ok(RelationMember::Way(mem_ref, mem_role)),
+        "relation" => Ok(RelationMember::Relation(mem_ref, mem_role)),
+        t => Err(Error::new(
+            ParseError,
+            Some(format!("The 'type' field contains invalid data '{}'.", t)),
+        )),
+    }
+}
+
+fn parse_meta(element: &Object) -> Result<Meta> {
+    let author = if ["timestamp", "uid", "user", "changeset"]
+        .iter()
+        .all(|key| element.contains_key(*key))
+    {
+        Some(AuthorInformation {
+            created: get_timestamp(element)?,
+            uid: get_u64(element, "uid")?,
+            user: get_str(element, "user")?.to_owned(),
+            change_set: get_u64(element, "changeset")?,
+        })
+    } else {
+        None
+    };
+
+    let timestamp = if author.is_none() && element.contains_key("timestamp") {
+        Some(get_timestamp(element)?)
+    } else {
+        None
+    };
+
+    let version = match element.get("version") {
+        Some(version) => Some(
+            version
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| invalid_data("version"))?,
+        ),
+        None => None,
+    };
+
+    let visible = match element.get("visible") {
+        Some(Value::Bool(visible)) => Some(*visible),
+        Some(_) => return Err(invalid_data("visible")),
+        None => None,
+    };
+
+    Ok(Meta {
+        tags: parse_tags(element)?,
+        version,
+        author,
+        timestamp,
+        visible,
+        action: None,
+    })
+}
+
+/// Tags are an object of string values, e.g. `"tags": {"highway": "primary"}`.
+fn parse_tags(element: &Object) -> Result<Vec<Tag>> {
+    let tags = match element.get("tags") {
+        Some(Value::Object(tags)) => tags,
+        Some(_) => return Err(invalid_data("tags")),
+        None => return Ok(Vec::new()),
+    };
+
+    tags.iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => Ok(Tag {
+                key: key.to_owned(),
+                value: value.to_owned(),
+            }),
+            _ => Err(invalid_data("tags")),
+        })
+        .collect()
+}
+
+fn get_timestamp(element: &Object) -> Result<i64> {
+    parse_timestamp(get_str(element, "timestamp")?)
+}
+
+fn get_required<'a>(object: &'a Object, field: &str) -> Result<&'a Value> {
+    object.get(field).ok_or_else(|| missing_field(field))
+}
+
+fn get_str<'a>(object: &'a Object, field: &str) -> Result<&'a str> {
+    get_required(object, field)?
+        .as_str()
+        .ok_or_else(|| invalid_data(field))
+}
+
+fn get_i64(object: &Object, field: &str) -> Result<i64> {
+    get_required(object, field)?
+        .as_i64()
+        .ok_or_else(|| invalid_data(field))
+}
+
+fn get_u64(object: &Object, field: &str) -> Result<u64> {
+    get_required(object, field)?
+        .as_u64()
+        .ok_or_else(|| invalid_data(field))
+}
+
+fn get_f64(object: &Object, field: &str) -> Result<f64> {
+    get_required(object, field)?
+        .as_f64()
+        .ok_or_else(|| invalid_data(field))
+}
+
+fn as_object<'a>(value: &'a Value, field: &str) -> Result<&'a Object> {
+    value.as_object().ok_or_else(|| invalid_data(field))
+}
+
+fn missing_field(field: &str) -> Error {
+    Error::new(
+        ParseError,
+        Some(format!("Required field '{}' missing.", field)),
+    )
+}
+
+fn invalid_data(field: &str) -> Error {
+    Error::new(
+        ParseError,
+        Some(format!("The '{}' field contains invalid data.", field)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::Coordinate;
+    use crate::osm_io::error::ErrorKind;
+    use crate::osm_io::{create_reader, FileFormat};
+    use crate::{AuthorInformation, RelationMember, Tag};
+
+    const OVERPASS: &str = r#"{
+        "version": 0.6,
+        "generator": "Overpass API",
+        "osm3s": {"timestamp_osm_base": "2020-01-01T00:00:00Z"},
+        "bounds": {"minlat": 59.3, "minlon": 18.0, "maxlat": 59.4, "maxlon": 18.1},
+        "elements": [
+            {
+                "type": "node",
+                "id": 1,
+                "lat": 59.3293,
+                "lon": 18.0686,
+                "timestamp": "2020-01-01T00:00:00Z",
+                "version": 3,
+                "changeset": 42,
+                "user": "mapper",
+                "uid": 7,
+                "tags": {"amenity": "bench"}
+            },
+            {
+                "type": "way",
+                "id": 2,
+                "nodes": [1, 3, 4],
+                "tags": {"highway": "residential", "name": "Storgatan"}
+            },
+            {
+                "type": "relation",
+                "id": 5,
+                "members": [
+                    {"type": "way", "ref": 2, "role": "outer"},
+                    {"type": "node", "ref": 1, "role": ""}
+                ],
+                "tags": {"type": "multipolygon"}
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn read_overpass_response() {
+        let osm = create_reader(OVERPASS.as_bytes(), FileFormat::OsmJson)
+            .read()
+            .unwrap();
+
+        let boundary = osm.boundary.as_ref().unwrap();
+        assert_eq!(boundary.min, Coordinate::new(59.3, 18.0));
+        assert_eq!(boundary.max, Coordinate::new(59.4, 18.1));
+        assert!(!boundary.freeze);
+
+        assert_eq!(osm.nodes.len(), 1);
+        let node = &osm.nodes[0];
+        assert_eq!(node.id, 1);
+        assert_eq!(node.coordinate, Coordinate::new(59.3293, 18.0686));
+        assert_eq!(node.meta.version, Some(3));
+        assert_eq!(
+            node.meta.author,
+            Some(AuthorInformation {
+                created: 1577836800,
+                change_set: 42,
+                uid: 7,
+                user: "mapper".to_owned(),
+            })
+        );
+        assert_eq!(node.meta.tags, vec![Tag::from(("amenity", "bench"))]);
+
+        assert_eq!(osm.ways.len(), 1);
+        let way = &osm.ways[0];
+        assert_eq!(way.id, 2);
+        assert_eq!(way.refs, vec![1, 3, 4]);
+        assert_eq!(way.meta.author, None);
+        assert_eq!(
+            way.meta.tags,
+            vec![
+                Tag::from(("highway", "residential")),
+                Tag::from(("name", "Storgatan"))
+            ]
+        );
+
+        assert_eq!(osm.relations.len(), 1);
+        let relation = &osm.relations[0];
+        assert_eq!(relation.id, 5);
+        assert_eq!(
+            relation.members,
+            vec![
+                RelationMember::Way(2, "outer".to_owned()),
+                RelationMember::Node(1, "".to_owned())
+            ]
+        );
+        assert_eq!(
+            relation.meta.tags,
+            vec![Tag::from(("type", "multipolygon"))]
+        );
+    }
+
+    #[test]
+    fn read_timestamps_with_offset_and_fraction() {
+        let json = r#"{"elements": [
+            {"type": "node", "id": 1, "lat": 1, "lon": 1, "timestamp": "2007-01-28T13:40:26+02:00"},
+            {"type": "node", "id": 2, "lat": 1, "lon": 1, "timestamp": "2007-01-28T11:40:26.75Z"},
+            {"type": "node", "id": 3, "lat": 1, "lon": 1, "timestamp": "2007-01-28T11:40:26"}
+        ]}"#;
+        let osm = create_reader(json.as_bytes(), FileFormat::OsmJson)
+            .read()
+            .unwrap();
+
+        let timestamps: Vec<Option<i64>> = osm.nodes.iter().map(|n| n.meta.timestamp).collect();
+        assert_eq!(timestamps, vec![Some(1169984426); 3]);
+    }
+
+    #[test]
+    fn invalid_member_type() {
+        let json = r#"{"elements": [
+            {"type": "relation", "id": 1, "members": [{"type": "area", "ref": 2, "role": ""}]}
+        ]}"#;
+        let error = create_reader(json.as_bytes(), FileFormat::OsmJson)
+            .read()
+            .unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+        assert_eq!(
+            error.to_string(),
+            "The 'type' field contains invalid data 'area'."
+        );
+    }
+
+    #[test]
+    fn missing_elements() {
+        let error = create_reader(r#"{"version": 0.6}"#.as_bytes(), FileFormat::OsmJson)
+            .read()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Required field 'elements' missing.");
+    }
+}
@@ -0,0 +1,268 @@
+use super::serde_json::{json, Map, Value};
+use crate::geo::Boundary;
+use crate::osm_io::error::{Error, Result};
+use crate::osm_io::{format_timestamp, OsmWrite, WriteOptions};
+use crate::{Meta, Node, Osm, Relation, RelationMember, Tag, Way};
+use std::io::Write;
+
+const OSM_VERSION: f64 = 0.6;
+const OSM_GENERATOR: &str = "Vadeen OSM";
+
+/// A writer for the osm json format.
+pub struct JsonWriter<W: Write> {
+    inner: W,
+    pretty: bool,
+    options: WriteOptions,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(inner: W) -> JsonWriter<W> {
+        JsonWriter {
+            inner,
+            pretty: false,
+            options: WriteOptions::default(),
+        }
+    }
+
+    /// Set the options for writing, see `WriteOptions`.
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Consume the writer and return the inner writer, e.g. the buffer written to. Same as
+    /// `OsmWrite::into_inner`, but without boxing.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Toggle pretty printing. Disabled by default, since the output is mostly consumed by
+    /// programs.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Create the json document: `{"version": 0.6, "generator": ..., "elements": [...]}`.
+    fn create_document(&self, osm: &Osm) -> Result<Value> {
+        let mut document = Map::new();
+        document.insert("version".to_owned(), json!(OSM_VERSION));
+        document.insert("generator".to_owned(), json!(OSM_GENERATOR));
+
+        if let Some(boundary) = &osm.boundary {
+            document.insert("bounds".to_owned(), create_bounds(boundary));
+        }
+
+        let nodes = osm.nodes.iter().map(create_node);
+        let ways = osm
+            .ways
+            .iter()
+            .filter(|way| self.options.include_way(way))
+            .map(create_way);
+        let relations = osm
+            .relations
+            .iter()
+            .filter(|rel| self.options.include_relation(rel))
+            .map(create_relation);
+
+        let elements = nodes.chain(ways).chain(relations).collect::<Result<_>>()?;
+        document.insert("elements".to_owned(), Value::Array(elements));
+        Ok(Value::Object(document))
+    }
+}
+
+impl<W: Write> OsmWrite<W> for JsonWriter<W> {
+    fn write(&mut self, osm: &Osm) -> std::result::Result<(), Error> {
+        let document = self.create_document(osm)?;
+        if self.pretty {
+            serde_json::to_writer_pretty(&mut self.inner, &document)?;
+        } else {
+            serde_json::to_writer(&mut self.inner, &document)?;
+        }
+        Ok(())
+    }
+
+    fn into_inner(self: Box<Self>) -> W {
+        JsonWriter::into_inner(*self)
+    }
+}
+
+fn create_bounds(boundary: &Boundary) -> Value {
+    json!({
+        "minlat": boundary.min.lat(),
+        "minlon": boundary.min.lon(),
+        "maxlat": boundary.max.lat(),
+        "maxlon": boundary.max.lon(),
+    })
+}
+
+/// See: https://wiki.openstreetmap.org/wiki/Node
+fn create_node(node: &Node) -> Result<Value> {
+    let mut element = create_element("node", node.id);
+    element.insert("lat".to_owned(), json!(node.coordinate.lat()));
+    element.insert("lon".to_owned(), json!(node.coordinate.lon()));
+    add_meta(&mut element, &node.meta)?;
+    add_tags(&mut element, &node.meta.tags);
+    Ok(Value::Object(element))
+}
+
+/// See: https://wiki.openstreetmap.org/wiki/Way
+fn create_way(way: &Way) -> Result<Value> {
+    let mut element = create_element("way", way.id);
+    add_meta(&mut element, &way.meta)?;
+    element.insert("nodes".to_owned(), json!(way.refs));
+    add_tags(&mut element, &way.meta.tags);
+    Ok(Value::Object(element))
+}
+
+/// See: https://wiki.openstreetmap.org/wiki/Relation
+fn create_relation(rel: &Relation) -> Result<Value> {
+    let mut element = create_element("relation", rel.id);
+    add_meta(&mut element, &rel.meta)?;
+    let members = rel.members.iter().map(create_member).collect();
+    element.insert("members".to_owned(), Value::Array(members));
+    add_tags(&mut element, &rel.meta.tags);
+    Ok(Value::Object(element))
+}
+
+fn create_element(element_type: &str, id: i64) -> Map<String, Value> {
+    let mut element = Map::new();
+    element.insert("type".to_owned(), json!(element_type));
+    element.insert("id".to_owned(), json!(id));
+    element
+}
+
+fn create_member(mem: &RelationMember) -> Value {
+    let (mem_type, mem_ref, mem_role) = match mem {
+        RelationMember::Node(mem_ref, role) => ("node", mem_ref, role),
+        RelationMember::Way(mem_ref, role) => ("way", mem_ref, role),
+        RelationMember::Relation(mem_ref, role) => ("relation", mem_ref, role),
+    };
+    json!({"type": mem_type, "ref": mem_ref, "role": mem_role})
+}
+
+/// Add the meta fields to an element. Unlike the xml format, the version is optional.
+fn add_meta(element: &mut Map<String, Value>, meta: &Meta) -> Result<()> {
+    if let Some(author) = &meta.author {
+        element.insert(
+            "timestamp".to_owned(),
+            json!(format_timestamp(author.created)?),
+        );
+    } else if let Some(timestamp) = meta.timestamp {
+        element.insert("timestamp".to_owned(), json!(format_timestamp(timestamp)?));
+    }
+
+    if let Some(version) = meta.version {
+        element.insert("version".to_owned(), json!(version));
+    }
+
+    if let Some(author) = &meta.author {
+        element.insert("changeset".to_owned(), json!(author.change_set));
+        element.insert("user".to_owned(), json!(author.user));
+        element.insert("uid".to_owned(), json!(author.uid));
+    }
+
+    if let Some(visible) = meta.visible {
+        element.insert("visible".to_owned(), json!(visible));
+    }
+    Ok(())
+}
+
+/// See: https://wiki.openstreetmap.org/wiki/Tags
+fn add_tags(element: &mut Map<String, Value>, tags: &[Tag]) {
+    if tags.is_empty() {
+        return;
+    }
+
+    let tags = tags
+        .iter()
+        .map(|tag| (tag.key.to_owned(), json!(tag.value)))
+        .collect();
+    element.insert("tags".to_owned(), Value::Object(tags));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::Boundary;
+    use crate::osm_io::json::JsonWriter;
+    use crate::osm_io::OsmWrite;
+    use crate::{AuthorInformation, Meta, Node, Osm, Relation, RelationMember, Way};
+
+    #[test]
+    fn write_overpass_structure() {
+        let mut osm = Osm {
+            boundary: Some(Boundary::new((1.0, 2.0), (3.0, 4.0))),
+            ..Osm::default()
+        };
+        osm.add_node(Node {
+            id: 1,
+            coordinate: (1.5, 2.5).into(),
+            meta: Meta {
+                tags: vec![("amenity", "bench").into()],
+                version: Some(2),
+                author: Some(AuthorInformation {
+                    created: 1577836800,
+                    change_set: 42,
+                    uid: 7,
+                    user: "mapper".to_owned(),
+                }),
+                timestamp: None,
+                visible: None,
+                action: None,
+            },
+        });
+        osm.add_way(Way {
+            id: 2,
+            refs: vec![1, 3],
+            meta: Meta::default(),
+        });
+        osm.add_relation(Relation {
+            id: 5,
+            members: vec![RelationMember::Way(2, "outer".to_owned())],
+            meta: Meta::default(),
+        });
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.write(&osm).unwrap();
+        let json = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"version":0.6,"generator":"Vadeen OSM","#,
+                r#""bounds":{"minlat":1.0,"minlon":2.0,"maxlat":3.0,"maxlon":4.0},"#,
+                r#""elements":["#,
+                r#"{"type":"node","id":1,"lat":1.5,"lon":2.5,"timestamp":"2020-01-01T00:00:00Z","#,
+                r#""version":2,"changeset":42,"user":"mapper","uid":7,"tags":{"amenity":"bench"}},"#,
+                r#"{"type":"way","id":2,"nodes":[1,3]},"#,
+                r#"{"type":"relation","id":5,"members":[{"type":"way","ref":2,"role":"outer"}]}"#,
+                r#"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn write_timestamp_without_author() {
+        let mut osm = Osm::default();
+        osm.add_node(Node {
+            id: 1,
+            coordinate: (1.5, 2.5).into(),
+            meta: Meta {
+                timestamp: Some(1577934245),
+                ..Meta::default()
+            },
+        });
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.write(&osm).unwrap();
+        let json = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(json.contains(
+            r#"{"type":"node","id":1,"lat":1.5,"lon":2.5,"timestamp":"2020-01-02T03:04:05Z"}"#
+        ));
+        assert!(!json.contains("changeset"));
+
+        osm.nodes[0].meta.timestamp = Some(i64::MAX);
+        let mut writer = JsonWriter::new(Vec::new());
+        assert!(writer.write(&osm).is_err());
+    }
+}
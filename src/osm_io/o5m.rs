@@ -2,7 +2,7 @@
 //! See: https://wiki.openstreetmap.org/wiki/O5m
 
 mod reader;
-mod varint;
+pub mod varint;
 mod writer;
 
 use crate::osm_io::error::{Error, ErrorKind, Result};
@@ -29,6 +29,7 @@ const O5M_BOUNDING_BOX: u8 = 0xDB;
 #[derive(Debug)]
 struct StringReferenceTable {
     table: VecDeque<Vec<u8>>,
+    max_size: usize,
 }
 
 /// Represents a delta value, i.e. a value that is relative to it's last value.
@@ -65,8 +66,15 @@ struct DeltaState {
 
 impl StringReferenceTable {
     pub fn new() -> Self {
+        Self::with_max_size(MAX_STRING_TABLE_SIZE)
+    }
+
+    /// Create a table holding at most `max_size` strings. The oldest strings are evicted when
+    /// the table is full.
+    pub fn with_max_size(max_size: usize) -> Self {
         StringReferenceTable {
-            table: VecDeque::with_capacity(15000),
+            table: VecDeque::with_capacity(max_size),
+            max_size,
         }
     }
 
@@ -107,12 +115,12 @@ impl StringReferenceTable {
 
     /// Push string to table. The string is only added if it do not exceed 250 bytes in length.
     pub fn push(&mut self, bytes: &[u8]) {
-        if bytes.len() > MAX_STRING_REFERENCE_LENGTH {
+        if bytes.len() > MAX_STRING_REFERENCE_LENGTH || self.max_size == 0 {
             return;
         }
 
         // Pop the oldest one off if we are at the limit.
-        if self.table.len() == MAX_STRING_TABLE_SIZE {
+        if self.table.len() >= self.max_size {
             self.table.pop_back();
         }
 
@@ -198,4 +206,14 @@ mod test {
 
         assert_eq!(table.reference(vec![0x01, 0x01]), vec![0x01, 0x01]); // New
     }
+
+    #[test]
+    fn string_references_small_table() {
+        let mut table = StringReferenceTable::with_max_size(2);
+        assert_eq!(table.reference(vec![0x01, 0x01]), vec![0x01, 0x01]); // New
+        assert_eq!(table.reference(vec![0x02, 0x02]), vec![0x02, 0x02]); // New
+        assert_eq!(table.reference(vec![0x03, 0x03]), vec![0x03, 0x03]); // New, evicts 0x01
+        assert_eq!(table.reference(vec![0x01, 0x01]), vec![0x01, 0x01]); // New, evicts 0x02
+        assert_eq!(table.reference(vec![0x03, 0x03]), vec![0x02]); // Existing
+    }
 }
@@ -1,17 +1,24 @@
 use super::varint::ReadVarInt;
-use super::varint::VarInt;
+use super::varint::{VarInt, MAX_VARINT_BYTES};
 use super::*;
-use crate::geo::{Boundary, Coordinate};
+use crate::geo::{Boundary, Coordinate, COORD_PRECISION};
 use crate::osm_io::error::Result;
 use crate::osm_io::error::{Error, ErrorKind};
 use crate::osm_io::o5m::Delta::*;
-use crate::osm_io::OsmRead;
+use crate::osm_io::{validate_coordinate, ElementSink, OsmRead, ReadOptions};
 use crate::{AuthorInformation, Meta, Node, Osm, Relation, RelationMember, Tag, Way};
 use std::io::{BufRead, Read, Take};
 
 /// A reader for the o5m format.
 pub struct O5mReader<R: BufRead> {
     decoder: O5mDecoder<R>,
+    options: ReadOptions,
+    /// Type and number, starting at 1, of the data set currently being read. Used in errors.
+    dataset: Option<(&'static str, u64)>,
+    /// Number of node, way and relation data sets read.
+    dataset_counts: [u64; 3],
+    /// Union of the bounding boxes read so far.
+    boundary: Option<Boundary>,
 }
 
 /// Low level decoding from binary to data types.
@@ -28,6 +35,59 @@ impl<R: BufRead> O5mReader<R> {
     pub fn new(inner: R) -> Self {
         O5mReader {
             decoder: O5mDecoder::new(inner),
+            options: ReadOptions::default(),
+            dataset: None,
+            dataset_counts: [0; 3],
+            boundary: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the maximum number of strings in the string reference table, defaults to 15 000. Must
+    /// be at least the size used when writing the data, otherwise references may not be found.
+    pub fn with_string_table_size(mut self, size: usize) -> Self {
+        self.decoder.string_table = StringReferenceTable::with_max_size(size);
+        self
+    }
+
+    /// Read all data sets into `sink`, one at a time.
+    pub(crate) fn read_into<S: ElementSink>(&mut self, sink: &mut S) -> Result<()> {
+        // Empty input is an empty map, not a missing header.
+        if !self.decoder.has_more_data()? {
+            return Ok(());
+        }
+
+        loop {
+            let result = self.parse_next(sink);
+            let result = result.and_then(|more| {
+                self.options.check_element_count(sink.element_count())?;
+                Ok(more)
+            });
+            match result {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(mut error) => {
+                    if let Some(message) = error.message() {
+                        let message = match self.dataset {
+                            Some((name, number)) => format!(
+                                "Ending at byte {} in {} dataset #{}: {}",
+                                self.position(),
+                                name,
+                                number,
+                                message
+                            ),
+                            None => format!("Ending at byte {}: {}", self.position(), message),
+                        };
+                        error.set_message(message);
+                    }
+
+                    return Err(error);
+                }
+            }
         }
     }
 
@@ -37,31 +97,87 @@ impl<R: BufRead> O5mReader<R> {
     }
 
     /// Parse next data set, returns false when there is no more data.
-    fn parse_next(&mut self, osm: &mut Osm) -> Result<bool> {
+    fn parse_next<S: ElementSink>(&mut self, sink: &mut S) -> Result<bool> {
+        self.dataset = None;
         match self.read_set_type()? {
             O5M_NODE => {
+                self.begin_dataset("node", 0);
                 let node = self.read_node()?;
-                osm.add_node(node)
+                self.options.check_meta("node", node.id, &node.meta)?;
+                sink.add_node(node)?;
             }
-            O5M_WAY => osm.add_way(self.read_way()?),
-            O5M_RELATION => osm.add_relation(self.read_relation()?),
-            O5M_BOUNDING_BOX => osm.boundary = Some(self.read_boundary()?),
+            O5M_WAY => {
+                self.begin_dataset("way", 1);
+                let way = self.read_way()?;
+                self.options.check_meta("way", way.id, &way.meta)?;
+                self.options.check_way(&way)?;
+                if self.options.include(&way.meta) {
+                    sink.add_way(way)?;
+                }
+            }
+            O5M_RELATION => {
+                self.begin_dataset("relation", 2);
+                let relation = self.read_relation()?;
+                self.options
+                    .check_meta("relation", relation.id, &relation.meta)?;
+                if self.options.include(&relation.meta) {
+                    sink.add_relation(relation)?;
+                }
+            }
+            O5M_BOUNDING_BOX => {
+                let boundary = self.read_boundary()?;
+                let boundary = match self.boundary.take() {
+                    Some(current) => union(current, boundary),
+                    None => boundary,
+                };
+                self.boundary = Some(boundary.clone());
+                sink.set_boundary(boundary)?;
+            }
+            O5M_HEADER => self.read_header()?,
             O5M_RESET => self.decoder.reset(),
-            O5M_EOF => return Ok(false),
+            O5M_EOF => return self.decoder.has_more_data(),
             set_type => self.skip_dataset(set_type)?,
         }
         Ok(true)
     }
 
+    /// See: https://wiki.openstreetmap.org/wiki/O5m#Header
+    /// A header starts a new stream, so all state is reset.
+    fn read_header(&mut self) -> Result<()> {
+        self.decoder.read_limit()?;
+        let mut header = Vec::new();
+        self.decoder.inner.read_to_end(&mut header)?;
+
+        if header != b"o5m2" && header != b"o5c2" {
+            return Err(Error::new(
+                ErrorKind::ParseError,
+                Some(format!(
+                    "Unsupported o5m header '{}'.",
+                    String::from_utf8_lossy(&header)
+                )),
+            ));
+        }
+
+        self.decoder.reset();
+        Ok(())
+    }
+
+    /// Keep track of which data set is being read.
+    fn begin_dataset(&mut self, name: &'static str, index: usize) {
+        self.dataset_counts[index] += 1;
+        self.dataset = Some((name, self.dataset_counts[index]));
+    }
+
     /// See: https://wiki.openstreetmap.org/wiki/O5m#File
     fn read_set_type(&mut self) -> Result<u8> {
         self.decoder.set_limit(1);
-        Ok(self.decoder.read_u8()?)
+        self.decoder.read_u8()
     }
 
-    /// Skip a whole data set. Used when data set is unknown.
+    /// Skip a whole data set. Used when data set is unknown. Data sets below 0xF0 have a length,
+    /// the rest are single bytes.
     fn skip_dataset(&mut self, block_type: u8) -> Result<()> {
-        if block_type >= 0xF0 {
+        if block_type < 0xF0 {
             self.decoder.read_limit()?;
             self.decoder.skip_all()?;
         }
@@ -91,11 +207,11 @@ impl<R: BufRead> O5mReader<R> {
     /// See: https://wiki.openstreetmap.org/wiki/O5m#Node
     fn read_node(&mut self) -> Result<Node> {
         self.decoder.read_limit()?;
-        let mut node = Node::default();
-        node.id = self.decoder.read_delta(Id)?;
-        node.meta = self.read_meta()?;
-
-        node.coordinate = self.decoder.read_delta_coordinate()?;
+        let mut node = Node {
+            id: self.decoder.read_delta(Id)?,
+            meta: self.read_meta()?,
+            coordinate: self.decoder.read_delta_coordinate()?,
+        };
         node.meta.tags = self.decoder.read_tags()?;
 
         Ok(node)
@@ -105,9 +221,11 @@ impl<R: BufRead> O5mReader<R> {
     fn read_way(&mut self) -> Result<Way> {
         self.decoder.read_limit()?;
 
-        let mut way = Way::default();
-        way.id = self.decoder.read_delta(Id)?;
-        way.meta = self.read_meta()?;
+        let mut way = Way {
+            id: self.decoder.read_delta(Id)?,
+            meta: self.read_meta()?,
+            ..Way::default()
+        };
 
         let ref_size = self.decoder.read_uvarint()?;
         way.refs = self.decoder.read_way_references(ref_size)?;
@@ -120,9 +238,11 @@ impl<R: BufRead> O5mReader<R> {
     fn read_relation(&mut self) -> Result<Relation> {
         self.decoder.read_limit()?;
 
-        let mut relation = Relation::default();
-        relation.id = self.decoder.read_delta(Id)?;
-        relation.meta = self.read_meta()?;
+        let mut relation = Relation {
+            id: self.decoder.read_delta(Id)?,
+            meta: self.read_meta()?,
+            ..Relation::default()
+        };
 
         let ref_size = self.decoder.read_uvarint()?;
         relation.members = self.decoder.read_relation_members(ref_size)?;
@@ -142,6 +262,15 @@ impl<R: BufRead> O5mReader<R> {
             meta.author = self.decoder.read_author_info()?;
         }
 
+        // Change set 0 without a user is a timestamp without author information. This is how the
+        // writer stores a timestamp, an explicit author with these values is read the same way.
+        if let Some(author) = &meta.author {
+            if author.change_set == 0 && author.uid == 0 && author.user.is_empty() {
+                meta.timestamp = Some(author.created);
+                meta.author = None;
+            }
+        }
+
         Ok(meta)
     }
 }
@@ -180,12 +309,17 @@ impl<R: BufRead> O5mDecoder<R> {
 
     /// Sets limit of the reader by reading the limit from the stream.
     fn read_limit(&mut self) -> Result<()> {
-        self.set_limit(9);
+        self.set_limit(MAX_VARINT_BYTES as u64);
         let len = self.read_uvarint()?;
         self.set_limit(len);
         Ok(())
     }
 
+    /// Check if there is more data after the current position, ignoring the limit.
+    fn has_more_data(&mut self) -> Result<bool> {
+        Ok(!self.inner.get_mut().fill_buf()?.is_empty())
+    }
+
     /// Skip until limit or end of file is reached.
     fn skip_all(&mut self) -> Result<()> {
         let _ = self.read_until_eof(|r| {
@@ -197,9 +331,13 @@ impl<R: BufRead> O5mDecoder<R> {
 
     /// Read coordinate and delta decode values.
     fn read_delta_coordinate(&mut self) -> Result<Coordinate> {
-        let lon = self.read_delta(Lon)? as i32;
-        let lat = self.read_delta(Lat)? as i32;
-        Ok(Coordinate { lat, lon })
+        let lon = self.read_delta(Lon)?;
+        let lat = self.read_delta(Lat)?;
+        validate_coordinate(lat as f64 / COORD_PRECISION, lon as f64 / COORD_PRECISION)?;
+        Ok(Coordinate {
+            lat: lat as i32,
+            lon: lon as i32,
+        })
     }
 
     /// Wrapper for easy reading i64 varint.
@@ -244,25 +382,29 @@ impl<R: BufRead> O5mDecoder<R> {
         let reference = self.read_uvarint()?;
         if reference != 0 {
             let bytes = self.string_table.get(reference)?;
-            Ok(Self::bytes_to_user(bytes))
+            Self::bytes_to_user(bytes)
         } else {
             let bytes = self.read_string_bytes(2)?;
-            Ok(Self::bytes_to_user(&bytes))
+            Self::bytes_to_user(&bytes)
         }
     }
 
-    /// Turns bytes into uid and username.
-    fn bytes_to_user(bytes: &[u8]) -> (u64, String) {
-        let (uid_bytes, user_bytes) = Self::split_string_bytes(&bytes);
+    /// Turns bytes into uid and username. Empty bytes is an anonymous user with uid 0.
+    fn bytes_to_user(bytes: &[u8]) -> Result<(u64, String)> {
+        if bytes.is_empty() {
+            return Ok((0, String::new()));
+        }
+
+        let (uid_bytes, user_bytes) = Self::split_string_bytes(bytes)?;
         let uid: u64 = VarInt::new(Vec::from(uid_bytes)).into();
-        let user = String::from_utf8_lossy(&user_bytes).into_owned();
-        (uid, user)
+        let user = String::from_utf8_lossy(user_bytes).into_owned();
+        Ok((uid, user))
     }
 
     /// Read tags. There is no size or delimiter for tags, so they are read until there is no more
     /// data to read in the current limit.
     fn read_tags(&mut self) -> Result<Vec<Tag>> {
-        let pairs = self.read_until_eof(|r| Ok(r.read_string_pair()?))?;
+        let pairs = self.read_until_eof(|r| r.read_string_pair())?;
         let tags = pairs.into_iter().map(|s| s.into()).collect();
         Ok(tags)
     }
@@ -270,8 +412,9 @@ impl<R: BufRead> O5mDecoder<R> {
     /// Reads way references until `size` is consumed.
     fn read_way_references(&mut self, size: u64) -> Result<Vec<i64>> {
         let limit = self.inner.limit();
+        check_section_size(size, limit)?;
         self.set_limit(size);
-        let refs = self.read_until_eof(|r| Ok(r.read_delta(WayRef)?))?;
+        let refs = self.read_until_eof(|r| r.read_delta(WayRef))?;
         self.set_limit(limit - size);
         Ok(refs)
     }
@@ -279,8 +422,9 @@ impl<R: BufRead> O5mDecoder<R> {
     /// Reads relation members until `size` is consumed.
     fn read_relation_members(&mut self, size: u64) -> Result<Vec<RelationMember>> {
         let limit = self.inner.limit();
+        check_section_size(size, limit)?;
         self.set_limit(size);
-        let members = self.read_until_eof(|r| Ok(r.read_relation_member()?))?;
+        let members = self.read_until_eof(|r| r.read_relation_member())?;
         self.set_limit(limit - size);
         Ok(members)
     }
@@ -290,7 +434,7 @@ impl<R: BufRead> O5mDecoder<R> {
         let id = self.read_varint()?;
         let s = self.read_string()?;
 
-        if !s.is_char_boundary(1) || s.len() < 2 {
+        if s.is_empty() || !s.is_char_boundary(1) {
             return Err(Error::new(
                 ErrorKind::ParseError,
                 Some("Corrupt relation member reference data.".to_owned()),
@@ -325,10 +469,10 @@ impl<R: BufRead> O5mDecoder<R> {
         let reference: u64 = self.inner.read_varint()?.into();
         if reference != 0 {
             let bytes = self.string_table.get(reference)?;
-            Ok(Self::bytes_to_string_pair(bytes))
+            Self::bytes_to_string_pair(bytes)
         } else {
             let bytes = self.read_string_bytes(2)?;
-            Ok(Self::bytes_to_string_pair(&bytes))
+            Self::bytes_to_string_pair(&bytes)
         }
     }
 
@@ -340,25 +484,31 @@ impl<R: BufRead> O5mDecoder<R> {
             String::from_utf8_lossy(&value).into_owned()
         } else {
             let value = self.string_table.get(reference)?;
-            String::from_utf8_lossy(&value).into_owned()
+            String::from_utf8_lossy(value).into_owned()
         };
 
         Ok(value)
     }
 
     /// Turns bytes into two strings by splitting on first zero bytes and utf8 encode them.
-    fn bytes_to_string_pair(bytes: &[u8]) -> (String, String) {
-        let (key_bytes, value_bytes) = Self::split_string_bytes(bytes);
+    fn bytes_to_string_pair(bytes: &[u8]) -> Result<(String, String)> {
+        let (key_bytes, value_bytes) = Self::split_string_bytes(bytes)?;
         let key = String::from_utf8_lossy(key_bytes).into_owned();
         let value = String::from_utf8_lossy(value_bytes).into_owned();
-        (key, value)
+        Ok((key, value))
     }
 
     /// Splits bytes at the first zero byte.
-    /// Panics if 0-byte is not found.
-    fn split_string_bytes(bytes: &[u8]) -> (&[u8], &[u8]) {
-        let div = bytes.iter().position(|b| b == &0u8).unwrap();
-        (&bytes[0..div], &bytes[(div + 1)..])
+    /// Returns a parse error if 0-byte is not found, e.g. when a string pair refers to a single
+    /// string in the string table.
+    fn split_string_bytes(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+        match bytes.iter().position(|b| b == &0u8) {
+            Some(div) => Ok((&bytes[0..div], &bytes[(div + 1)..])),
+            None => Err(Error::new(
+                ErrorKind::ParseError,
+                Some("Malformed string, missing delimiter.".to_owned()),
+            )),
+        }
     }
 
     /// Reads string bytes from stream. A string can consist of 1 or more parts. Each part is
@@ -410,35 +560,56 @@ impl<R: BufRead> O5mDecoder<R> {
     }
 }
 
+/// Smallest boundary containing both boundaries.
+fn union(a: Boundary, b: Boundary) -> Boundary {
+    Boundary {
+        min: Coordinate {
+            lat: a.min.lat.min(b.min.lat),
+            lon: a.min.lon.min(b.min.lon),
+        },
+        max: Coordinate {
+            lat: a.max.lat.max(b.max.lat),
+            lon: a.max.lon.max(b.max.lon),
+        },
+        freeze: true,
+    }
+}
+
+/// Check that a reference section of `size` bytes fits in the `remaining` bytes of the dataset.
+fn check_section_size(size: u64, remaining: u64) -> Result<()> {
+    if size > remaining {
+        return Err(Error::new(
+            ErrorKind::ParseError,
+            Some(format!(
+                "Reference section length {} exceeds dataset bounds, {} bytes remaining.",
+                size, remaining
+            )),
+        ));
+    }
+    Ok(())
+}
+
+/// Multiple concatenated o5m streams are read as one map. Each header resets the state of the
+/// reader, and the bounding box of the map is the union of all bounding boxes.
 impl<R: BufRead> OsmRead for O5mReader<R> {
     fn read(&mut self) -> std::result::Result<Osm, Error> {
         let mut osm = Osm::default();
+        self.read_into(&mut osm)?;
 
-        loop {
-            match self.parse_next(&mut osm) {
-                Ok(true) => {}
-                Ok(false) => break,
-                Err(mut error) => {
-                    if let Some(message) = error.message() {
-                        let message = format!("Ending at byte {}: {}", self.position(), message);
-                        error.set_message(message);
-                    }
-
-                    return Err(error);
-                }
-            }
-        }
-
+        self.options.finish(&mut osm);
         Ok(osm)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::geo::Boundary;
     use crate::geo::Coordinate;
-    use crate::osm_io::o5m::O5mReader;
-    use crate::osm_io::OsmRead;
-    use crate::{AuthorInformation, Meta, Node, Relation, RelationMember, Way};
+    use crate::osm_io::error::ErrorKind;
+    use crate::osm_io::o5m::varint::VarInt;
+    use crate::osm_io::o5m::{O5mReader, O5mWriter};
+    use crate::osm_io::{OsmRead, OsmWrite, ReadOptions};
+    use crate::{AuthorInformation, Meta, Node, Osm, OsmBuilder, Relation, RelationMember, Way};
     use std::io::BufReader;
 
     #[test]
@@ -480,6 +651,110 @@ mod test {
         );
     }
 
+    #[test]
+    fn read_node_anonymous_user() {
+        let data: Vec<u8> = vec![
+            0x19, // length of following data of this node: 25 bytes
+            0xce, 0xad, 0x0f, // id: 0+125799=125799
+            0x05, // version: 5
+            0xe4, 0x8e, 0xa7, 0xca, 0x09, // timestamp: 2010-09-30T19:23:30Z
+            0x94, 0xfe, 0xd2, 0x05, // changeset: 0+5922698=5922698
+            0x00, 0x00, 0x00, // string pair: empty uid and empty user
+            0x86, 0x87, 0xe6, 0x53, // lon: 0+8.7867843=8.7867843
+            0xcc, 0xe2, 0x94, 0xfa, 0x03, // lat: 0+53.0749606=53.0749606
+        ];
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let node = reader.read_node().unwrap();
+        assert_eq!(
+            node.meta.author,
+            Some(AuthorInformation {
+                created: 1285874610,
+                change_set: 5922698,
+                uid: 0,
+                user: "".to_owned()
+            })
+        );
+        assert_eq!(node.coordinate, Coordinate::new(53.0749606, 8.7867843));
+    }
+
+    #[test]
+    fn read_node_change_set_zero_anonymous_user() {
+        let data: Vec<u8> = vec![
+            0x16, // length of following data of this node: 22 bytes
+            0xce, 0xad, 0x0f, // id: 0+125799=125799
+            0x05, // version: 5
+            0xe4, 0x8e, 0xa7, 0xca, 0x09, // timestamp: 2010-09-30T19:23:30Z
+            0x00, // changeset: 0+0=0
+            0x00, 0x00, 0x00, // string pair: empty uid and empty user
+            0x86, 0x87, 0xe6, 0x53, // lon: 0+8.7867843=8.7867843
+            0xcc, 0xe2, 0x94, 0xfa, 0x03, // lat: 0+53.0749606=53.0749606
+        ];
+
+        // Ambiguous, read as a timestamp without author information.
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let node = reader.read_node().unwrap();
+        assert_eq!(node.meta.author, None);
+        assert_eq!(node.meta.timestamp, Some(1285874610));
+        assert_eq!(node.meta.version, Some(5));
+        assert_eq!(node.coordinate, Coordinate::new(53.0749606, 8.7867843));
+    }
+
+    #[test]
+    fn anonymous_user_round_trip() {
+        let mut osm = Osm::default();
+        osm.add_node(Node {
+            id: 1,
+            coordinate: (1.0, 1.0).into(),
+            meta: Meta {
+                version: Some(1),
+                author: Some(AuthorInformation {
+                    created: 1285874610,
+                    change_set: 12,
+                    uid: 0,
+                    user: "".to_owned(),
+                }),
+                ..Meta::default()
+            },
+        });
+
+        let mut writer = Box::new(O5mWriter::new(Vec::new()));
+        writer.write(&osm).unwrap();
+        let data = writer.into_inner();
+
+        let result = O5mReader::new(BufReader::new(data.as_slice()))
+            .read()
+            .unwrap();
+        assert_eq!(result.nodes, osm.nodes);
+    }
+
+    #[test]
+    fn read_node_out_of_range_coordinate() {
+        let mut data: Vec<u8> = vec![
+            0x10, // node
+            0x08, // length of following data of this node: 8 bytes
+            0x02, // id: 0+1=1
+            0x00, // no version and no author information
+            0x00, // lon: 0+0=0
+        ];
+        data.append(&mut VarInt::create_bytes(1_000_000_000_i64)); // lat: 0+100=100
+        data.push(0xFE); // eof
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let error = reader.read().unwrap_err();
+        match error.kind() {
+            ErrorKind::InvalidCoordinate { lat, lon } => {
+                assert_eq!(*lat, 100.0);
+                assert_eq!(*lon, 0.0);
+            }
+            e => panic!("Unexpected kind {:?}", e),
+        }
+        assert_eq!(
+            error.to_string(),
+            "Ending at byte 10 in node dataset #1: Coordinate (lat 100, lon 0) is out of range."
+        );
+    }
+
     #[test]
     fn read_way() {
         let data: Vec<u8> = vec![
@@ -550,6 +825,46 @@ mod test {
         )
     }
 
+    #[test]
+    fn way_refs_exceed_dataset() {
+        let data: Vec<u8> = vec![
+            0x11, // way
+            0x05, // length of following data of this way: 5 bytes
+            0x02, // id: 0+1=1
+            0x00, // no version and no author information
+            0x20, // length of references section: 32 bytes, more than the dataset
+            0x02, 0x02, // refs
+        ];
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let error = reader.read().unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+        assert!(error.to_string().ends_with(
+            "in way dataset #1: Reference section length 32 exceeds dataset bounds, 2 bytes remaining."
+        ));
+    }
+
+    #[test]
+    fn string_pair_missing_delimiter() {
+        let data: Vec<u8> = vec![
+            0x12, // relation
+            0x11, // length of following data of this relation: 17 bytes
+            0x90, 0x2e, // id: 0+2952=2952
+            0x00, // no version and no author information
+            0x0c, // length of references section: 12 bytes
+            0xf4, 0x98, 0x83, 0x0b, // id: 0+11560506=11560506
+            0x00, 0x31, 0x69, 0x6e, 0x6e, 0x65, 0x72, 0x00, // type and role: "1inner"
+            0x01, // tag referring to "1inner", which is not a string pair
+        ];
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let error = reader.read().unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+        assert!(error
+            .to_string()
+            .ends_with("in relation dataset #1: Malformed string, missing delimiter."));
+    }
+
     #[test]
     fn invalid_relation_member_string() {
         let data: Vec<u8> = vec![
@@ -567,7 +882,7 @@ mod test {
         let error = reader.read().unwrap_err();
         assert_eq!(
             error.to_string(),
-            "Ending at byte 13: Corrupt relation member reference data."
+            "Ending at byte 13 in relation dataset #1: Corrupt relation member reference data."
         );
     }
 
@@ -590,7 +905,7 @@ mod test {
         let error = reader.read().unwrap_err();
         assert_eq!(
             error.to_string(),
-            "Ending at byte 18: Invalid relation member type '5'."
+            "Ending at byte 18 in relation dataset #1: Invalid relation member type '5'."
         );
     }
 
@@ -606,7 +921,7 @@ mod test {
         let error = reader.read().unwrap_err();
         assert_eq!(
             error.to_string(),
-            "Ending at byte 10: Varint overflow, read 9 bytes."
+            "Ending at byte 11 in relation dataset #1: Varint overflow, read 10 bytes."
         );
     }
 
@@ -627,7 +942,7 @@ mod test {
         let error = reader.read().unwrap_err();
         assert_eq!(
             error.to_string(),
-            "Ending at byte 11: String reference '3' not found in table with size '0'."
+            "Ending at byte 11 in relation dataset #1: String reference '3' not found in table with size '0'."
         );
     }
 
@@ -642,4 +957,146 @@ mod test {
         let error = reader.read().unwrap_err();
         assert_eq!(error.to_string(), "Unexpected end of file.");
     }
+
+    #[test]
+    fn concatenated_streams() {
+        let mut first = OsmBuilder::default();
+        first.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+        let mut second = OsmBuilder::default();
+        second.add_point((-1.0, 3.0), vec![("amenity", "bench")]);
+
+        let mut data = Vec::new();
+        for builder in [first, second] {
+            let mut writer = Box::new(O5mWriter::new(Vec::new()));
+            writer.write(&builder.build()).unwrap();
+            data.extend(writer.into_inner());
+        }
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let osm = reader.read().unwrap();
+
+        let coordinates: Vec<Coordinate> = osm.nodes.iter().map(|n| n.coordinate).collect();
+        assert_eq!(
+            coordinates,
+            vec![(1.0, 1.0).into(), (2.0, 2.0).into(), (-1.0, 3.0).into()]
+        );
+        assert_eq!(osm.ways.len(), 1);
+        assert_eq!(osm.nodes[2].meta.tags, vec![("amenity", "bench").into()]);
+
+        assert_eq!(osm.boundary, Some(Boundary::new((-1.0, 1.0), (2.0, 3.0))));
+    }
+
+    #[test]
+    fn unsupported_header() {
+        let data: Vec<u8> = vec![0xff, 0xe0, 0x04, 0x6f, 0x35, 0x6d, 0x33];
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let error = reader.read().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Ending at byte 7: Unsupported o5m header 'o5m3'."
+        );
+    }
+
+    #[test]
+    fn empty_input() {
+        let data: &[u8] = &[];
+        let osm = O5mReader::new(BufReader::new(data)).read().unwrap();
+        assert!(osm.is_empty());
+        assert_eq!(osm.boundary, Some(Boundary::inverted()));
+    }
+
+    #[test]
+    fn error_names_dataset() {
+        let data: Vec<u8> = vec![
+            0x10, // node
+            0x04, // length of following data of this node: 4 bytes
+            0x02, // id: 0+1=1
+            0x00, // no version and no author information
+            0x00, // lon: 0+0=0
+            0x00, // lat: 0+0=0
+            0x11, // way
+            0x04, // length of following data of this way: 4 bytes
+            0x04, // id: 0+2=2
+            0x00, // no version and no author information
+            0x00, // length of references section: 0 bytes
+            0x03, // Invalid string reference
+        ];
+
+        let mut reader = O5mReader::new(BufReader::new(data.as_slice()));
+        let error = reader.read().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Ending at byte 12 in way dataset #1: String reference '3' not found in table with size '0'."
+        );
+    }
+
+    #[test]
+    fn read_duplicate_tags() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(
+            vec![(1.0, 1.0), (2.0, 2.0)],
+            vec![("highway", "primary"), ("highway", "secondary")],
+        );
+        let mut writer = Box::new(O5mWriter::new(Vec::new()));
+        writer.write(&builder.build()).unwrap();
+        let data = writer.into_inner();
+
+        let options = ReadOptions {
+            strict_tags: true,
+            ..ReadOptions::default()
+        };
+        let error = O5mReader::new(BufReader::new(data.as_slice()))
+            .with_options(options)
+            .read()
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("in way dataset #1: Duplicate tag key 'highway' on way 3."));
+    }
+
+    #[test]
+    fn read_way_min_nodes() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(1.0, 1.0)], vec![("highway", "primary")]);
+        let mut writer = Box::new(O5mWriter::new(Vec::new()));
+        writer.write(&builder.build()).unwrap();
+        let data = writer.into_inner();
+
+        let options = ReadOptions {
+            min_way_nodes: 2,
+            ..ReadOptions::default()
+        };
+        let error = O5mReader::new(BufReader::new(data.as_slice()))
+            .with_options(options)
+            .read()
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("in way dataset #1: Way 2 has 1 nodes, at least 2 required."));
+    }
+
+    #[test]
+    fn read_max_elements() {
+        let mut builder = OsmBuilder::default();
+        for i in 0..3 {
+            builder.add_point((i as f64, 1.0), vec![("power", "tower")]);
+        }
+        let mut writer = Box::new(O5mWriter::new(Vec::new()));
+        writer.write(&builder.build()).unwrap();
+        let data = writer.into_inner();
+
+        let options = ReadOptions {
+            max_elements: Some(2),
+            ..ReadOptions::default()
+        };
+        let error = O5mReader::new(BufReader::new(data.as_slice()))
+            .with_options(options)
+            .read()
+            .unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::TooManyElements { limit: 2 }));
+        assert!(error
+            .to_string()
+            .ends_with("in node dataset #3: More than 2 elements."));
+    }
 }
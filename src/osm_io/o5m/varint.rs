@@ -14,11 +14,32 @@
 //!
 //! The trait `ReadVarInt` adds read_varint() to all readers for easy reading of varints.
 //!
+//! The trait `WriteVarInt` adds write_varint() to all writers in the same way.
+//!
 //! See: https://wiki.openstreetmap.org/wiki/O5m#Numbers
+//!
+//! # Examples
+//! ```
+//! # use vadeen_osm::osm_io::varint::{ReadVarInt, VarInt, WriteVarInt};
+//! let mut bytes = Vec::new();
+//! bytes.write_varint(-3_i64).unwrap();
+//! bytes.write_varint(323_u64).unwrap();
+//! assert_eq!(bytes, vec![0x05, 0xC3, 0x02]);
+//! assert_eq!(VarInt::create_bytes(323_u64), vec![0xC3, 0x02]);
+//!
+//! let mut reader = bytes.as_slice();
+//! let signed: i64 = reader.read_varint().unwrap().into();
+//! let unsigned: u64 = reader.read_varint().unwrap().into();
+//! assert_eq!(signed, -3);
+//! assert_eq!(unsigned, 323);
+//! ```
 
 use crate::osm_io::error::{Error, ErrorKind, Result};
 use std::io::{Read, Write};
 
+/// Maximum length of an encoded varint. 10 bytes hold 70 bits, enough for 64 bit integers.
+pub(crate) const MAX_VARINT_BYTES: usize = 10;
+
 /// Represents a variable integer (signed or unsigned).
 #[derive(Debug)]
 pub struct VarInt {
@@ -26,7 +47,9 @@ pub struct VarInt {
 }
 
 impl VarInt {
-    pub fn new(bytes: Vec<u8>) -> Self {
+    /// Create a varint from encoded bytes. `bytes` must not be empty, converting a varint without
+    /// bytes to `i64` panics.
+    pub(crate) fn new(bytes: Vec<u8>) -> Self {
         VarInt { bytes }
     }
 
@@ -39,6 +62,7 @@ impl VarInt {
         varint.into_bytes()
     }
 
+    /// The encoded bytes.
     pub fn into_bytes(self) -> Vec<u8> {
         self.bytes
     }
@@ -50,12 +74,11 @@ impl VarInt {
 pub trait ReadVarInt: Read {
     fn read_varint(&mut self) -> Result<VarInt> {
         let mut bytes = Vec::new();
-        for i in 0..10 {
-            // If we get to byte 9 we have more bits than 64.
-            if i == 9 {
+        for i in 0..=MAX_VARINT_BYTES {
+            if i == MAX_VARINT_BYTES {
                 return Err(Error::new(
                     ErrorKind::ParseError,
-                    Some("Varint overflow, read 9 bytes.".to_owned()),
+                    Some(format!("Varint overflow, read {} bytes.", i)),
                 ));
             }
 
@@ -75,6 +98,9 @@ pub trait ReadVarInt: Read {
 /// All types that implements the Read trait gets the ReadVarInt methods.
 impl<R: Read + ?Sized> ReadVarInt for R {}
 
+/// Extends [`Write`] with a method for writing varints.
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 pub trait WriteVarInt: Write {
     fn write_varint<T: Into<VarInt>>(&mut self, i: T) -> Result<()> {
         let varint: VarInt = i.into();
@@ -83,6 +109,7 @@ pub trait WriteVarInt: Write {
     }
 }
 
+/// All types that implements the Write trait gets the WriteVarInt methods.
 impl<W: Write + ?Sized> WriteVarInt for W {}
 
 impl From<VarInt> for i64 {
@@ -98,9 +125,10 @@ impl From<VarInt> for i64 {
             value |= (Into::<u64>::into(vi)) << 6;
         }
 
+        // The value has at most 63 bits, since the first byte holds the sign.
         let value = value as i64;
         if negative {
-            -value - 1
+            !value
         } else {
             value
         }
@@ -111,7 +139,7 @@ impl From<VarInt> for u64 {
     fn from(vi: VarInt) -> Self {
         let mut value = 0;
         for (n, _) in vi.bytes.iter().enumerate() {
-            // 9*7 = 63 bits. We can store 64 without overflow.
+            // 10*7 = 70 bits, the high bits of the 10th byte do not fit and are dropped.
             let byte = vi.bytes[n] as u64;
             value |= (byte & 0x7F) << (7 * (n as u64));
 
@@ -138,10 +166,7 @@ impl From<u64> for VarInt {
             value >>= 7;
         }
 
-        if value > 0 {
-            bytes.push(value as u8);
-        }
-
+        bytes.push(value as u8);
         VarInt::new(bytes)
     }
 }
@@ -158,8 +183,9 @@ impl From<i64> for VarInt {
         if value < 0 {
             sign_bit = 0x01;
 
-            // We handle the sign our selves, negative range is shifted by 1.
-            value = -value - 1;
+            // We handle the sign our selves, negative range is shifted by 1. Same as -value - 1,
+            // but does not overflow for i64::MIN.
+            value = !value;
         }
 
         let value = value as u64;
@@ -171,7 +197,7 @@ impl From<i64> for VarInt {
             bytes.push(least_significant | 0x80);
 
             // Only first byte is special, rest is same as uvarint.
-            let mut rest = Self::from((value >> 6) as u64);
+            let mut rest = Self::from(value >> 6);
             bytes.append(&mut rest.bytes);
         } else {
             bytes.push(least_significant);
@@ -232,9 +258,18 @@ mod test_from_bytes {
 
     #[test]
     fn too_many_bytes() {
-        let data = vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let data = vec![
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01,
+        ];
         let error = data.as_slice().read_varint().unwrap_err();
-        assert_eq!(error.to_string(), "Varint overflow, read 9 bytes.")
+        assert_eq!(error.to_string(), "Varint overflow, read 10 bytes.")
+    }
+
+    #[test]
+    fn read_ten_bytes_uvarint() {
+        let data = vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        let varint = data.as_slice().read_varint().unwrap();
+        assert_eq!(Into::<u64>::into(varint), u64::MAX);
     }
 }
 
@@ -242,51 +277,82 @@ mod test_from_bytes {
 mod test_to_bytes {
     use crate::osm_io::o5m::varint::VarInt;
 
+    #[test]
+    fn zero_uvarint() {
+        let varint = VarInt::from(0_u64);
+        assert_eq!(varint.bytes, vec![0x00]);
+    }
+
     #[test]
     fn one_byte_uvarint() {
-        let varint = VarInt::from(5 as u64);
+        let varint = VarInt::from(5_u64);
         assert_eq!(varint.bytes, vec![0x05]);
     }
 
     #[test]
     fn max_one_byte_uvarint() {
-        let varint = VarInt::from(127 as u64);
+        let varint = VarInt::from(127_u64);
         assert_eq!(varint.bytes, vec![0x7F]);
     }
 
     #[test]
     fn two_byte_uvarint() {
-        let varint = VarInt::from(323 as u64);
+        let varint = VarInt::from(323_u64);
         assert_eq!(varint.bytes, vec![0xC3, 0x02]);
     }
 
     #[test]
     fn three_byte_uvarint() {
-        let varint = VarInt::from(16384 as u64);
+        let varint = VarInt::from(16384_u64);
         assert_eq!(varint.bytes, vec![0x80, 0x80, 0x01]);
     }
 
     #[test]
     fn one_byte_positive_varint() {
-        let varint = VarInt::from(4 as i64);
+        let varint = VarInt::from(4_i64);
         assert_eq!(varint.bytes, vec![0x08]);
     }
 
     #[test]
     fn one_byte_negative_varint() {
-        let varint = VarInt::from(-3 as i64);
+        let varint = VarInt::from(-3_i64);
         assert_eq!(varint.bytes, vec![0x05]);
     }
 
     #[test]
     fn two_byte_positive_varint() {
-        let varint = VarInt::from(64 as i64);
+        let varint = VarInt::from(64_i64);
         assert_eq!(varint.bytes, vec![0x80, 0x01]);
     }
 
     #[test]
     fn two_byte_negative_varint() {
-        let varint = VarInt::from(-65 as i64);
+        let varint = VarInt::from(-65_i64);
         assert_eq!(varint.bytes, vec![0x81, 0x01]);
     }
 }
+
+#[cfg(test)]
+mod test_round_trip {
+    use crate::osm_io::o5m::varint::{ReadVarInt, WriteVarInt};
+
+    #[test]
+    fn extreme_uvarints() {
+        for value in [0, 1 << 63, u64::MAX] {
+            let mut bytes = Vec::new();
+            bytes.write_varint(value).unwrap();
+            let varint = bytes.as_slice().read_varint().unwrap();
+            assert_eq!(Into::<u64>::into(varint), value);
+        }
+    }
+
+    #[test]
+    fn extreme_varints() {
+        for value in [i64::MIN, i64::MIN + 1, -1, 0, i64::MAX] {
+            let mut bytes = Vec::new();
+            bytes.write_varint(value).unwrap();
+            let varint = bytes.as_slice().read_varint().unwrap();
+            assert_eq!(Into::<i64>::into(varint), value);
+        }
+    }
+}
@@ -8,7 +8,7 @@ use crate::osm_io::o5m::varint::WriteVarInt;
 use crate::osm_io::o5m::Delta::{
     ChangeSet, Id, Lat, Lon, RelNodeRef, RelRelRef, RelWayRef, Time, WayRef,
 };
-use crate::osm_io::OsmWrite;
+use crate::osm_io::{ElementSink, OsmWrite, WriteOptions};
 use crate::{Meta, Node, Osm, Relation, RelationMember, Tag, Way};
 
 /// A writer for the o5m binary format.
@@ -16,6 +16,7 @@ use crate::{Meta, Node, Osm, Relation, RelationMember, Tag, Way};
 pub struct O5mWriter<W> {
     inner: W,
     encoder: O5mEncoder,
+    options: WriteOptions,
 }
 
 /// Encodes data into bytes according the o5m specification. Keeps track of string references and
@@ -31,9 +32,30 @@ impl<W: Write> O5mWriter<W> {
         O5mWriter {
             inner: writer,
             encoder: O5mEncoder::new(),
+            options: WriteOptions::default(),
         }
     }
 
+    /// Set the maximum number of strings in the string reference table, defaults to 15 000.
+    /// Strings evicted from the table are written in full again instead of as references.
+    /// A reader of the output must use a table of at least the same size.
+    pub fn with_string_table_size(mut self, size: usize) -> Self {
+        self.encoder.string_table = StringReferenceTable::with_max_size(size);
+        self
+    }
+
+    /// Set the options for writing, see `WriteOptions`.
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Consume the writer and return the inner writer, e.g. the buffer written to. Same as
+    /// `OsmWrite::into_inner`, but without boxing.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
     /// See: https://wiki.openstreetmap.org/wiki/O5m#Reset
     fn reset(&mut self) -> io::Result<()> {
         self.inner.write_all(&[O5M_RESET])?;
@@ -96,22 +118,26 @@ impl<W: Write> OsmWrite<W> for O5mWriter<W> {
         self.inner.write_all(O5M_HEADER_DATA)?;
 
         if let Some(boundary) = &osm.boundary {
-            self.write_bounding_box(&boundary)?;
+            self.write_bounding_box(boundary)?;
         }
 
         self.reset()?;
         for node in &osm.nodes {
-            self.write_node(&node)?;
+            self.write_node(node)?;
         }
 
         self.reset()?;
         for way in &osm.ways {
-            self.write_way(&way)?;
+            if self.options.include_way(way) {
+                self.write_way(way)?;
+            }
         }
 
         self.reset()?;
         for rel in &osm.relations {
-            self.write_relation(&rel)?;
+            if self.options.include_relation(rel) {
+                self.write_relation(rel)?;
+            }
         }
 
         self.inner.write_all(&[O5M_EOF])?;
@@ -119,7 +145,86 @@ impl<W: Write> OsmWrite<W> for O5mWriter<W> {
     }
 
     fn into_inner(self: Box<Self>) -> W {
-        self.inner
+        O5mWriter::into_inner(*self)
+    }
+}
+
+/// Writes elements as they are read instead of a whole map, see `osm_io::convert`.
+///
+/// The delta coding is reset before each group of nodes, ways and relations, so elements read in
+/// the usual order give the same output as `O5mWriter::write`.
+pub(crate) struct O5mStreamWriter<W: Write> {
+    writer: O5mWriter<W>,
+    /// Index of the current group: 0 before any element, then 1 for nodes, 2 for ways and 3
+    /// for relations.
+    group: usize,
+    count: usize,
+}
+
+impl<W: Write> O5mStreamWriter<W> {
+    /// Create a stream writer, the header is written immediately.
+    pub(crate) fn new(mut writer: O5mWriter<W>) -> Result<Self> {
+        writer.reset()?;
+        writer.inner.write_all(&[O5M_HEADER])?;
+        writer.inner.write_all(O5M_HEADER_DATA)?;
+        Ok(O5mStreamWriter {
+            writer,
+            group: 0,
+            count: 0,
+        })
+    }
+
+    /// Reset the delta coding when an element of another group than the previous is written.
+    fn enter_group(&mut self, group: usize) -> Result<()> {
+        if group < self.group {
+            self.writer.reset()?;
+            self.group = group;
+        }
+        while self.group < group {
+            self.writer.reset()?;
+            self.group += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> ElementSink for O5mStreamWriter<W> {
+    fn set_boundary(&mut self, boundary: Boundary) -> Result<()> {
+        self.writer.write_bounding_box(&boundary)
+    }
+
+    fn add_node(&mut self, node: Node) -> Result<()> {
+        self.enter_group(1)?;
+        self.count += 1;
+        self.writer.write_node(&node)
+    }
+
+    fn add_way(&mut self, way: Way) -> Result<()> {
+        self.enter_group(2)?;
+        self.count += 1;
+        if self.writer.options.include_way(&way) {
+            self.writer.write_way(&way)?;
+        }
+        Ok(())
+    }
+
+    fn add_relation(&mut self, relation: Relation) -> Result<()> {
+        self.enter_group(3)?;
+        self.count += 1;
+        if self.writer.options.include_relation(&relation) {
+            self.writer.write_relation(&relation)?;
+        }
+        Ok(())
+    }
+
+    fn element_count(&self) -> usize {
+        self.count
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.enter_group(3)?;
+        self.writer.inner.write_all(&[O5M_EOF])?;
+        Ok(())
     }
 }
 
@@ -149,7 +254,7 @@ impl O5mEncoder {
         writer.write_varint(delta_coordinate.lat)?;
 
         for tag in &node.meta.tags {
-            self.write_tag(writer, &tag)?;
+            self.write_tag(writer, tag)?;
         }
 
         Ok(())
@@ -168,7 +273,7 @@ impl O5mEncoder {
         writer.write_all(&ref_bytes)?;
 
         for tag in &way.meta.tags {
-            self.write_tag(writer, &tag)?;
+            self.write_tag(writer, tag)?;
         }
 
         Ok(())
@@ -195,7 +300,7 @@ impl O5mEncoder {
         writer.write_all(&mem_bytes)?;
 
         for tag in &rel.meta.tags {
-            self.write_tag(writer, &tag)?;
+            self.write_tag(writer, tag)?;
         }
 
         Ok(())
@@ -214,8 +319,8 @@ impl O5mEncoder {
 
             let mut mem_bytes = Vec::new();
             mem_bytes.write_all(&[0x00])?;
-            mem_bytes.write_all(&mem_type.as_bytes())?;
-            mem_bytes.write_all(&mem_role.as_bytes())?;
+            mem_bytes.write_all(mem_type.as_bytes())?;
+            mem_bytes.write_all(mem_role.as_bytes())?;
             mem_bytes.write_all(&[0x00])?;
 
             writer.write_varint(delta)?;
@@ -225,8 +330,20 @@ impl O5mEncoder {
     }
 
     /// Writes meta to `writer`. It's positioned directly after the id of the element.
+    ///
+    /// Version 0 means there is no author information, so elements with an author but without a
+    /// version are written with version 1, the same default as the xml writer.
+    ///
+    /// A timestamp without author is written as change set 0 with an anonymous user. This is
+    /// lossy: an author with change set 0, uid 0 and no user is read back as only a timestamp.
     pub fn write_meta<W: Write>(&mut self, writer: &mut W, meta: &Meta) -> Result<()> {
-        if let Some(version) = meta.version {
+        let has_time = meta.author.is_some() || meta.timestamp.is_some();
+        let version = match meta.version {
+            None if has_time => Some(1),
+            version => version,
+        };
+
+        if let Some(version) = version {
             writer.write_varint(version)?;
 
             if let Some(author) = meta.author.as_ref() {
@@ -236,6 +353,15 @@ impl O5mEncoder {
                 writer.write_varint(delta_time)?;
                 writer.write_varint(delta_change_set)?;
                 self.write_user(writer, author.uid, &author.user)?;
+            } else if let Some(timestamp) = meta.timestamp {
+                // A timestamp must be followed by author info, write change set 0 and no user.
+                // The reader can't tell this apart from an explicit author with the same values.
+                let delta_time = self.delta.encode(Time, timestamp);
+                let delta_change_set = self.delta.encode(ChangeSet, 0);
+
+                writer.write_varint(delta_time)?;
+                writer.write_varint(delta_change_set)?;
+                self.write_user(writer, 0, "")?;
             } else {
                 writer.write_all(&[0x00])?; // No author info.
             }
@@ -252,12 +378,15 @@ impl O5mEncoder {
         Ok(())
     }
 
-    /// Converts a user to a byte vector that can be written to file.
+    /// Converts a user to a byte vector that can be written to file. Anonymous users, i.e. uid 0,
+    /// are written with an empty uid since the varint for 0 would be read as a delimiter.
     /// See: https://wiki.openstreetmap.org/wiki/O5m#Strings
     fn write_user<W: Write>(&mut self, writer: &mut W, uid: u64, username: &str) -> Result<()> {
         let mut bytes = Vec::new();
         bytes.push(0);
-        bytes.write_varint(uid)?;
+        if uid != 0 {
+            bytes.write_varint(uid)?;
+        }
 
         bytes.push(0);
         for byte in username.bytes() {
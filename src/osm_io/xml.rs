@@ -3,9 +3,11 @@
 
 extern crate quick_xml;
 
+mod osc_writer;
 mod reader;
 mod writer;
 
+pub use self::osc_writer::*;
 pub use self::reader::*;
 pub use self::writer::*;
 use crate::osm_io::error::Error;
@@ -31,7 +33,23 @@ mod test {
             .read()
             .unwrap_err();
         assert_eq!(
-            "Line 3: Expecting </osm> found </wrong-element>",
+            "Line 3, column 15: Expecting </osm> found </wrong-element>",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn error_column_single_line() {
+        let xml =
+            r#"<osm><node id="1" lat="1.0" lon="2.0"/><node id="2" lat="X" lon="2.0"/></osm>"#;
+        let error = create_reader(xml.as_bytes(), FileFormat::Xml)
+            .read()
+            .unwrap_err();
+
+        assert_eq!(error.line(), Some(1));
+        assert_eq!(error.column(), Some(72));
+        assert_eq!(
+            "Line 1, column 72: The 'lat' attribute contains invalid data 'X'.",
             error.to_string()
         );
     }
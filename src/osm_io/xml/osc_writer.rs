@@ -0,0 +1,158 @@
+use super::writer::XmlWriter;
+use crate::osm_io::error::Result;
+use crate::{Element, Osm, OsmChange};
+use std::io::Write;
+
+/// A writer for the [`osmChange`] format, i.e. `.osc` files.
+///
+/// Created and modified elements are written in full, the same way as by the `XmlWriter`. Deleted
+/// elements are written in minimal form, with only the id, meta attributes and for nodes the
+/// coordinate. Deletions are written relations first, so that no element is deleted before the
+/// elements referring to it.
+///
+/// # Example
+/// ```
+/// # use vadeen_osm::{Node, OsmChange};
+/// # use vadeen_osm::osm_io::OscWriter;
+/// let mut change = OsmChange::default();
+/// change.create.add_node(Node::default());
+///
+/// let mut writer = OscWriter::new(Vec::new());
+/// writer.write(&change).unwrap();
+/// let osc = String::from_utf8(writer.into_inner()).unwrap();
+/// assert!(osc.contains("<create>"));
+/// ```
+///
+/// [`osmChange`]: https://wiki.openstreetmap.org/wiki/OsmChange
+pub struct OscWriter<W: Write> {
+    writer: XmlWriter<W>,
+}
+
+impl<W: Write> OscWriter<W> {
+    pub fn new(inner: W) -> OscWriter<W> {
+        OscWriter {
+            writer: XmlWriter::new(inner),
+        }
+    }
+
+    /// Set the generator written in the `<osmChange>` element. Defaults to "Vadeen OSM".
+    pub fn with_generator(mut self, generator: &str) -> Self {
+        self.writer = self.writer.with_generator(generator);
+        self
+    }
+
+    /// Toggle pretty printing, see `XmlWriter::pretty`.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.writer = self.writer.pretty(pretty);
+        self
+    }
+
+    /// Write the change. Blocks without elements are left out.
+    pub fn write(&mut self, change: &OsmChange) -> Result<()> {
+        self.writer.write_root_start(b"osmChange", &[])?;
+        self.write_block(b"create", &change.create)?;
+        self.write_block(b"modify", &change.modify)?;
+        self.write_delete_block(&change.delete)?;
+        self.writer.write_end_tag(b"osmChange")?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    fn write_block(&mut self, name: &[u8], osm: &Osm) -> Result<()> {
+        if osm.is_empty() {
+            return Ok(());
+        }
+
+        self.write_block_start(name)?;
+        for element in osm {
+            match element {
+                Element::Node(node) => self.writer.write_node(node)?,
+                Element::Way(way) => self.writer.write_way(way)?,
+                Element::Relation(rel) => self.writer.write_relation(rel)?,
+            }
+        }
+        self.write_block_end(name)
+    }
+
+    fn write_delete_block(&mut self, osm: &Osm) -> Result<()> {
+        if osm.is_empty() {
+            return Ok(());
+        }
+
+        self.write_block_start(b"delete")?;
+        let relations = osm.relations.iter().map(Element::Relation);
+        let ways = osm.ways.iter().map(Element::Way);
+        let nodes = osm.nodes.iter().map(Element::Node);
+        for element in relations.chain(ways).chain(nodes) {
+            self.writer.write_deleted(element)?;
+        }
+        self.write_block_end(b"delete")
+    }
+
+    /// Write the start of a block, elements after it are indented one more level.
+    fn write_block_start(&mut self, name: &[u8]) -> Result<()> {
+        self.writer.write_indent(1)?;
+        self.writer.write_start_tag(name)?;
+        self.writer.write_newline()?;
+        self.writer.depth = 1;
+        Ok(())
+    }
+
+    fn write_block_end(&mut self, name: &[u8]) -> Result<()> {
+        self.writer.depth = 0;
+        self.writer.write_indent(1)?;
+        self.writer.write_end_tag(name)?;
+        self.writer.write_newline()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::osm_io::xml::OscWriter;
+    use crate::{Meta, Node, OsmChange, Way};
+
+    #[test]
+    fn write_create_and_delete() {
+        let mut change = OsmChange::default();
+        change.create.add_node(Node {
+            id: -1,
+            coordinate: (1.5, 2.5).into(),
+            meta: Meta {
+                tags: vec![("amenity", "bench").into()],
+                ..Meta::default()
+            },
+        });
+        change.delete.add_way(Way {
+            id: 10,
+            refs: vec![1, 2],
+            meta: Meta {
+                tags: vec![("highway", "primary").into()],
+                version: Some(3),
+                ..Meta::default()
+            },
+        });
+
+        let mut writer = OscWriter::new(Vec::new());
+        writer.write(&change).unwrap();
+        let osc = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(
+            osc,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <osmChange version=\"0.6\" generator=\"Vadeen OSM\">\n\
+             \t<create>\n\
+             \t\t<node id=\"-1\" lat=\"1.5\" lon=\"2.5\" version=\"1\">\n\
+             \t\t\t<tag k=\"amenity\" v=\"bench\"/>\n\
+             \t\t</node>\n\
+             \t</create>\n\
+             \t<delete>\n\
+             \t\t<way id=\"10\" version=\"3\"/>\n\
+             \t</delete>\n\
+             </osmChange>"
+        );
+    }
+}
@@ -1,9 +1,8 @@
-use super::super::chrono::{DateTime, Utc};
 use super::quick_xml::Reader;
 use crate::geo::{Boundary, Coordinate};
 use crate::osm_io::error::ErrorKind::ParseError;
 use crate::osm_io::error::{Error, Result};
-use crate::osm_io::OsmRead;
+use crate::osm_io::{parse_timestamp, validate_coordinate, ElementSink, OsmRead, ReadOptions};
 use crate::{AuthorInformation, Meta, Node, Osm, Relation, RelationMember, Tag, Way};
 use quick_xml::events::{BytesStart, Event};
 use std::collections::HashMap;
@@ -13,7 +12,9 @@ use std::str::FromStr;
 /// A reader for the xml format.
 pub struct XmlReader<R: BufRead> {
     reader: Reader<R>,
+    options: ReadOptions,
     line: u32,
+    line_start: usize,
 }
 
 /// Abstract representation of the attributes of an XML element.
@@ -27,14 +28,12 @@ impl Attributes {
     /// Create from quick_xml attributes data.
     fn from(attributes: super::quick_xml::events::attributes::Attributes) -> Self {
         let mut map = HashMap::new();
-        for attr in attributes {
-            if let Ok(attr) = attr {
-                if let Ok(value) = attr.unescaped_value() {
-                    map.insert(
-                        String::from_utf8_lossy(attr.key).into_owned(),
-                        String::from_utf8_lossy(value.as_ref()).into_owned(),
-                    );
-                }
+        for attr in attributes.flatten() {
+            if let Ok(value) = attr.unescaped_value() {
+                map.insert(
+                    String::from_utf8_lossy(attr.key).into_owned(),
+                    String::from_utf8_lossy(value.as_ref()).into_owned(),
+                );
             }
         }
         Attributes { map }
@@ -46,12 +45,12 @@ impl Attributes {
 
     /// Same as normal get, but returns error instead of option.
     fn get_required(&self, val: &str) -> Result<&String> {
-        Ok(self.get(val).ok_or_else(|| {
+        self.get(val).ok_or_else(|| {
             Error::new(
                 ParseError,
                 Some(format!("Required attribute '{}' missing.", val)),
             )
-        })?)
+        })
     }
 
     /// Get element (with get_required) and parse data into F.
@@ -60,9 +59,10 @@ impl Attributes {
         self.parse(field, s)
     }
 
-    /// Get element (with get_required) and parse data into F.
+    /// Parse data into F. Surrounding whitespace, e.g. line breaks, is ignored since it is only
+    /// used for numbers and booleans.
     fn parse<F: FromStr>(&self, field: &str, s: &str) -> Result<F> {
-        str::parse(s).map_err(|_| {
+        str::parse(s.trim()).map_err(|_| {
             Error::new(
                 ParseError,
                 Some(format!(
@@ -85,10 +85,10 @@ impl Attributes {
 
     /// Try to create a `Coordinate` from attribute values.
     fn create_coordinate(&self) -> Result<Coordinate> {
-        Ok(Coordinate::new(
-            self.get_parse("lat")?,
-            self.get_parse("lon")?,
-        ))
+        let lat = self.get_parse("lat")?;
+        let lon = self.get_parse("lon")?;
+        validate_coordinate(lat, lon)?;
+        Ok(Coordinate::new(lat, lon))
     }
 
     /// Try to create a `Boundary` from attribute values.
@@ -100,6 +100,31 @@ impl Attributes {
         })
     }
 
+    /// Try to create a `Boundary` from the `box` attribute of the legacy `<bound>` element, which
+    /// is formatted as `minlat,minlon,maxlat,maxlon`.
+    fn create_legacy_boundary(&self) -> Result<Boundary> {
+        let value = self.get_required("box")?;
+        let values = value
+            .split(',')
+            .map(|v| self.parse("box", v))
+            .collect::<Result<Vec<f64>>>()?;
+        if values.len() != 4 {
+            return Err(Error::new(
+                ParseError,
+                Some(format!(
+                    "The 'box' attribute contains invalid data '{}'.",
+                    value
+                )),
+            ));
+        }
+
+        Ok(Boundary {
+            min: Coordinate::new(values[0], values[1]),
+            max: Coordinate::new(values[2], values[3]),
+            freeze: true,
+        })
+    }
+
     /// Try to create a `Tag` from attribute values.
     fn create_tag(&self) -> Result<Tag> {
         Ok(Tag {
@@ -121,29 +146,42 @@ impl Attributes {
             None
         };
 
+        let timestamp = if author.is_none() && self.get("timestamp").is_some() {
+            Some(self.get_timestamp()?)
+        } else {
+            None
+        };
+
         let version = if let Some(version) = self.get("version") {
             Some(self.parse("version", version)?)
         } else {
             None
         };
 
+        let visible = if let Some(visible) = self.get("visible") {
+            Some(self.parse("visible", visible)?)
+        } else {
+            None
+        };
+
+        let action = if let Some(action) = self.get("action") {
+            Some(self.parse("action", action)?)
+        } else {
+            None
+        };
+
         Ok(Meta {
             version,
             author,
+            timestamp,
+            visible,
+            action,
             ..Meta::default()
         })
     }
 
     fn get_timestamp(&self) -> Result<i64> {
-        let time_str = self.get_required("timestamp")?;
-        if let Ok(time) = time_str.parse::<DateTime<Utc>>() {
-            Ok(time.timestamp())
-        } else {
-            Err(Error::new(
-                ParseError,
-                Some(format!("Invalid timestamp '{}'", time_str)),
-            ))
-        }
+        parse_timestamp(self.get_required("timestamp")?)
     }
 
     /// Try to create a `RelationMember` from attribute values.
@@ -172,24 +210,80 @@ impl<R: BufRead> XmlReader<R> {
     pub fn new(inner: R) -> XmlReader<R> {
         XmlReader {
             reader: Reader::from_reader(inner),
+            options: ReadOptions::default(),
             line: 1,
+            line_start: 0,
+        }
+    }
+
+    pub fn with_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Read all elements into `sink`, one at a time.
+    pub(crate) fn read_into<S: ElementSink>(&mut self, sink: &mut S) -> Result<()> {
+        loop {
+            let result = self.parse_event(sink);
+            let result = result.and_then(|more| {
+                self.options.check_element_count(sink.element_count())?;
+                Ok(more)
+            });
+            match result {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(mut error) => {
+                    error.set_position(self.line, self.column());
+                    return Err(error);
+                }
+            }
         }
     }
 
     /// Parse next xml element. Returns false if end of file was reached.
-    fn parse_event(&mut self, osm: &mut Osm) -> Result<bool> {
+    fn parse_event<S: ElementSink>(&mut self, sink: &mut S) -> Result<bool> {
         let mut buf = Vec::new();
-        match self.reader.read_event(&mut buf)? {
-            Event::Start(ref event) => self.parse_element(osm, event)?,
-            Event::Empty(ref event) => self.parse_empty_element(osm, event)?,
+        let event = self.reader.read_event(&mut buf)?;
+        self.track_position(&event);
+
+        match event {
+            Event::Start(ref event) => self.parse_element(sink, event)?,
+            Event::Empty(ref event) => self.parse_empty_element(sink, event)?,
             Event::Eof => return Ok(false),
             _ => { /* Ignore all other events. */ }
         }
-
-        self.line += buf.iter().filter(|b| **b == b'\n').count() as u32;
         Ok(true)
     }
 
+    /// Count lines in the last read event and keep track of the byte position where the current
+    /// line starts, so errors can be reported with both line and column.
+    fn track_position(&mut self, event: &Event) {
+        // Text ends where the next element starts. Elements are followed by closing characters
+        // which are not part of the event data.
+        let position = self.reader.buffer_position();
+        let end = match event {
+            Event::Text(_) | Event::Eof => position,
+            Event::Empty(_) => position.saturating_sub(2),
+            Event::Comment(_) => position.saturating_sub(3),
+            _ => position.saturating_sub(1),
+        };
+
+        let start = end.saturating_sub(event.len());
+        for (i, _) in event.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+            self.line += 1;
+            self.line_start = start + i + 1;
+        }
+    }
+
+    /// Current column, i.e. number of bytes since the start of the current line.
+    fn column(&self) -> u32 {
+        (self
+            .reader
+            .buffer_position()
+            .saturating_sub(self.line_start)
+            + 1) as u32
+    }
+
     /// Read until and end element, or end of file is reached.
     /// Only empty elements are returned, the rest is ignored. This limitation since OSM only use
     /// empty element in a nested context within the <osm> tag.
@@ -197,10 +291,14 @@ impl<R: BufRead> XmlReader<R> {
     /// TODO Corruption if nested elements are encountered:
     /// This should return error if non empty element is encountered. The end of the nested element
     /// will terminate this read and possibly corrupt the flow.
-    fn read_element_content(&mut self, mut buf: &mut Vec<u8>) -> Result<Vec<BytesStart>> {
+    fn read_element_content(&mut self) -> Result<Vec<BytesStart<'static>>> {
         let mut events = Vec::new();
         loop {
-            match self.reader.read_event(&mut buf)? {
+            let mut buf = Vec::new();
+            let event = self.reader.read_event(&mut buf)?;
+            self.track_position(&event);
+
+            match event {
                 Event::Empty(ref e) => events.push(e.to_owned()),
                 Event::End(_) => break,
                 Event::Eof => break,
@@ -210,48 +308,74 @@ impl<R: BufRead> XmlReader<R> {
         Ok(events)
     }
 
-    /// Parse empty top level element. (<node.../>, <bounds.../>)
-    fn parse_empty_element(&mut self, osm: &mut Osm, event: &BytesStart) -> Result<()> {
+    /// Parse empty top level element. (<node.../>, <bounds.../>, <bound.../>)
+    fn parse_empty_element<S: ElementSink>(
+        &mut self,
+        sink: &mut S,
+        event: &BytesStart,
+    ) -> Result<()> {
         match event.name() {
-            b"node" => osm.add_node(parse_node(&event)?),
-            b"bounds" => osm.boundary = Some(parse_boundary(&event)?),
+            b"node" => {
+                let node = parse_node(event)?;
+                self.options.check_meta("node", node.id, &node.meta)?;
+                sink.add_node(node)?;
+            }
+            b"bounds" => sink.set_boundary(parse_boundary(event)?)?,
+            b"bound" => {
+                let attributes = Attributes::from(event.attributes());
+                sink.set_boundary(attributes.create_legacy_boundary()?)?;
+            }
             _ => {}
         }
         Ok(())
     }
 
     /// Parse non empty elements. (<node...>, <way...>, ...)
-    fn parse_element(&mut self, osm: &mut Osm, event: &BytesStart) -> Result<()> {
+    /// The attributes are parsed before the content is read, so errors are reported on the
+    /// position of the element rather than the end of it.
+    fn parse_element<S: ElementSink>(&mut self, sink: &mut S, event: &BytesStart) -> Result<()> {
         // We only work on one indentation level. To do this we must ignore <osm> since it
         // introduces another one.
         if event.name() == b"osm" {
+            sink.set_attributes(parse_extra_attributes(event))?;
             return Ok(());
         }
 
-        let mut buf = Vec::new();
-        let event_content = self.read_element_content(&mut buf)?;
         match event.name() {
             b"node" => {
-                let mut node = parse_node(&event)?;
+                let mut node = parse_node(event)?;
+                let event_content = self.read_element_content()?;
                 node.meta.tags = create_tags(&event_content)?;
-                osm.add_node(node);
+                self.options.check_meta("node", node.id, &node.meta)?;
+                sink.add_node(node)?;
             }
             b"way" => {
-                let mut way = parse_way(&event)?;
+                let mut way = parse_way(event)?;
+                let event_content = self.read_element_content()?;
                 way.refs = create_way_refs(&event_content)?;
                 way.meta.tags = create_tags(&event_content)?;
-                osm.add_way(way);
+                self.options.check_meta("way", way.id, &way.meta)?;
+                self.options.check_way(&way)?;
+                if self.options.include(&way.meta) {
+                    sink.add_way(way)?;
+                }
             }
             b"relation" => {
-                let mut relation = parse_relation(&event)?;
+                let mut relation = parse_relation(event)?;
+                let event_content = self.read_element_content()?;
                 relation.members = create_relation_members(&event_content)?;
                 relation.meta.tags = create_tags(&event_content)?;
-                osm.add_relation(relation);
+                self.options
+                    .check_meta("relation", relation.id, &relation.meta)?;
+                if self.options.include(&relation.meta) {
+                    sink.add_relation(relation)?;
+                }
+            }
+            _ => {
+                // Ignore unknown elements.
+                self.read_element_content()?;
             }
-            _ => { /* Ignore unknown elements. */ }
         }
-
-        self.line += buf.iter().filter(|b| **b == b'\n').count() as u32;
         Ok(())
     }
 }
@@ -259,32 +383,25 @@ impl<R: BufRead> XmlReader<R> {
 impl<R: BufRead> OsmRead for XmlReader<R> {
     fn read(&mut self) -> std::result::Result<Osm, Error> {
         let mut osm = Osm::default();
-        loop {
-            match self.parse_event(&mut osm) {
-                Ok(true) => {}
-                Ok(false) => break,
-                Err(mut error) => {
-                    if let Some(message) = error.message() {
-                        let message = format!("Line {}: {}", self.line, message);
-                        error.set_message(message);
-                    }
-
-                    return Err(error);
-                }
-            }
-        }
-
-        if let Some(boundary) = osm.boundary.as_mut() {
-            boundary.freeze = false;
-        }
+        self.read_into(&mut osm)?;
 
+        self.options.finish(&mut osm);
         Ok(osm)
     }
 }
 
+/// Attributes of the <osm> element other than the version and generator, which are set by the
+/// writer.
+fn parse_extra_attributes(event: &BytesStart) -> HashMap<String, String> {
+    let mut attributes = Attributes::from(event.attributes()).map;
+    attributes.remove("version");
+    attributes.remove("generator");
+    attributes
+}
+
 fn parse_boundary(event: &BytesStart) -> Result<Boundary> {
     let attributes = Attributes::from(event.attributes());
-    Ok(attributes.create_boundary()?)
+    attributes.create_boundary()
 }
 
 fn parse_node(event: &BytesStart) -> Result<Node> {
@@ -342,9 +459,17 @@ fn create_relation_members(events: &[BytesStart]) -> Result<Vec<RelationMember>>
 mod tests {
     use crate::geo::{Boundary, Coordinate};
     use crate::osm_io::error::ErrorKind;
-    use crate::osm_io::xml::XmlReader;
-    use crate::osm_io::OsmRead;
-    use crate::{AuthorInformation, Meta, Node, Relation, RelationMember, Way};
+    use crate::osm_io::xml::{XmlReader, XmlWriter};
+    use crate::osm_io::{OsmRead, OsmWrite, ReadOptions};
+    use crate::{Action, AuthorInformation, Meta, Node, Relation, RelationMember, Way};
+
+    #[test]
+    fn empty_input() {
+        let data: &[u8] = &[];
+        let osm = XmlReader::new(data).read().unwrap();
+        assert!(osm.is_empty());
+        assert_eq!(osm.boundary, Some(Boundary::inverted()));
+    }
 
     #[test]
     fn read_boundary() {
@@ -362,6 +487,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_attributes_with_whitespace() {
+        let xml = "<node id=\" 1\" lat=\" 51.5 \" lon=\"-0.14\r\n\" version=\"2 \">
+                       <tag k=\" name\" v=\"A \"/>
+                   </node>";
+        let osm = XmlReader::new(xml.as_bytes()).read().unwrap();
+
+        let node = &osm.nodes[0];
+        assert_eq!(node.id, 1);
+        assert_eq!(node.coordinate, (51.5, -0.14).into());
+        assert_eq!(node.meta.version, Some(2));
+        assert_eq!(node.meta.tags, vec![(" name", "A ").into()]);
+    }
+
+    #[test]
+    fn read_legacy_boundary() {
+        let xml = r#"<bound box="58.24,15.16,62.18,17.34" origin="osmosis"/>"#;
+        let mut reader = XmlReader::new(xml.as_bytes());
+        let osm = reader.read().unwrap();
+
+        assert_eq!(
+            osm.boundary,
+            Some(Boundary {
+                min: (58.24, 15.16).into(),
+                max: (62.18, 17.34).into(),
+                freeze: false,
+            })
+        );
+
+        let xml = r#"<bound box="58.24,15.16,62.18"/>"#;
+        assert!(XmlReader::new(xml.as_bytes()).read().is_err());
+    }
+
     #[test]
     fn read_node() {
         let xml = r#"<node id="25496583" lat="51.5173639" lon="-0.140043" version="1"
@@ -387,12 +545,56 @@ mod tests {
                         user: "80n".to_owned(),
                         change_set: 203496,
                     }),
+                    visible: Some(true),
                     ..Meta::default()
                 }
             }
         );
     }
 
+    #[test]
+    fn read_timestamps_with_offset_and_fraction() {
+        let xml = r#"<osm>
+            <node id="1" lat="1" lon="1" timestamp="2007-01-28T13:40:26+02:00"/>
+            <node id="2" lat="1" lon="1" timestamp="2007-01-28T11:40:26.75Z"/>
+            <node id="3" lat="1" lon="1" timestamp="2007-01-28T11:40:26"/>
+        </osm>"#;
+        let osm = XmlReader::new(xml.as_bytes()).read().unwrap();
+
+        let timestamps: Vec<Option<i64>> = osm.nodes.iter().map(|n| n.meta.timestamp).collect();
+        assert_eq!(timestamps, vec![Some(1169984426); 3]);
+
+        let xml = r#"<node id="1" lat="1" lon="1" timestamp="2007-01-28"/>"#;
+        let error = XmlReader::new(xml.as_bytes()).read().unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+    }
+
+    #[test]
+    fn read_write_node_not_visible() {
+        let xml = r#"<node id="1" lat="51.5" lon="-0.14" visible="false" version="2"/>"#;
+        let osm = XmlReader::new(xml.as_bytes()).read().unwrap();
+        assert_eq!(osm.nodes[0].meta.visible, Some(false));
+
+        let mut writer = XmlWriter::new(Vec::new());
+        writer.write(&osm).unwrap();
+        let output = Box::new(writer).into_inner();
+
+        let osm = XmlReader::new(output.as_slice()).read().unwrap();
+        assert_eq!(osm.nodes[0].meta.visible, Some(false));
+        assert_eq!(osm.nodes[0].meta.version, Some(2));
+    }
+
+    #[test]
+    fn read_node_action() {
+        let xml = r#"<node id="-1" action="modify" lat="51.5" lon="-0.14"/>"#;
+        let osm = XmlReader::new(xml.as_bytes()).read().unwrap();
+        assert_eq!(osm.nodes[0].meta.action, Some(Action::Modify));
+
+        let xml = r#"<node id="-1" action="remove" lat="51.5" lon="-0.14"/>"#;
+        let error = XmlReader::new(xml.as_bytes()).read().unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+    }
+
     #[test]
     fn read_node_with_tags() {
         let xml = r#"<node id="25496583" lat="51.5173639" lon="-0.140043" version="1"
@@ -423,9 +625,9 @@ mod tests {
         let missing_lat = r#"<node id="1" lon="22.14" version="1" />"#;
         let missing_lon = r#"<node id="1" lat="51.12" version="1" />"#;
         let data = vec![
-            ("id", missing_id),
-            ("lat", missing_lat),
-            ("lon", missing_lon),
+            ("id", 45, missing_id),
+            ("lat", 40, missing_lat),
+            ("lon", 40, missing_lon),
         ];
 
         validate_missing_attributes(data);
@@ -438,15 +640,39 @@ mod tests {
         let invalid_lon = r#"<node id="1" lat="51.12" lon="INVALID.14" version="1" />"#;
         let invalid_version = r#"<node id="1" lat="51.12" lon="22.14" version="" />"#;
         let data = vec![
-            ("id", "123.22", invalid_id),
-            ("lat", "51.INVALID", invalid_lat),
-            ("lon", "INVALID.14", invalid_lon),
-            ("version", "", invalid_version),
+            ("id", "123.22", 57, invalid_id),
+            ("lat", "51.INVALID", 57, invalid_lat),
+            ("lon", "INVALID.14", 57, invalid_lon),
+            ("version", "", 51, invalid_version),
         ];
 
         validate_invalid_attributes(data);
     }
 
+    #[test]
+    fn read_node_out_of_range_coordinate() {
+        let xml = r#"<node id="1" lat="999" lon="22.14" version="1" />"#;
+        let error = XmlReader::new(xml.as_bytes()).read().unwrap_err();
+        match error.kind() {
+            ErrorKind::InvalidCoordinate { lat, lon } => {
+                assert_eq!(*lat, 999.0);
+                assert_eq!(*lon, 22.14);
+            }
+            e => panic!("Unexpected kind {:?}", e),
+        }
+        assert_eq!(
+            error.message(),
+            Some(&"Coordinate (lat 999, lon 22.14) is out of range.".to_owned())
+        );
+
+        let xml = r#"<node id="1" lat="51.12" lon="NaN" version="1" />"#;
+        let error = XmlReader::new(xml.as_bytes()).read().unwrap_err();
+        match error.kind() {
+            ErrorKind::InvalidCoordinate { lon, .. } => assert!(lon.is_nan()),
+            e => panic!("Unexpected kind {:?}", e),
+        }
+    }
+
     #[test]
     fn read_way() {
         let xml = r#"<way id="5090250" version="1" changeset="203496" user="80n" uid="1238"
@@ -478,7 +704,9 @@ mod tests {
                         user: "80n".to_owned(),
                         change_set: 203496,
                     }),
-                    ..Meta::default()
+                    timestamp: None,
+                    visible: Some(true),
+                    action: None,
                 }
             }
         );
@@ -491,10 +719,10 @@ mod tests {
         let missing_tag_k = r#"<way id="1" version="1"><tag v="value"/></way>"#;
         let missing_tag_v = r#"<way id="1" version="1"><tag k="key"/></way>"#;
         let data = vec![
-            ("id", missing_id),
-            ("ref", missing_nd_ref),
-            ("k", missing_tag_k),
-            ("v", missing_tag_v),
+            ("id", 18, missing_id),
+            ("ref", 36, missing_nd_ref),
+            ("k", 47, missing_tag_k),
+            ("v", 45, missing_tag_v),
         ];
 
         validate_missing_attributes(data);
@@ -506,9 +734,9 @@ mod tests {
         let invalid_version = r#"<way id="1" version=""></way>"#;
         let invalid_nd_ref = r#"<way id="1" version="1"><nd ref="INVALID"/></way>"#;
         let data = vec![
-            ("id", "INVALID", invalid_id),
-            ("version", "", invalid_version),
-            ("ref", "INVALID", invalid_nd_ref),
+            ("id", "INVALID", 31, invalid_id),
+            ("version", "", 24, invalid_version),
+            ("ref", "INVALID", 50, invalid_nd_ref),
         ];
 
         validate_invalid_attributes(data);
@@ -549,7 +777,9 @@ mod tests {
                         user: "80n".to_owned(),
                         change_set: 203496,
                     }),
-                    ..Meta::default()
+                    timestamp: None,
+                    visible: Some(true),
+                    action: None,
                 }
             }
         );
@@ -565,6 +795,10 @@ mod tests {
         let osm = reader.read().unwrap();
 
         assert_eq!(osm.relations.len(), 1);
+        assert_eq!(
+            osm.relations[0].members,
+            vec![RelationMember::Relation(821603, "outer".to_owned())]
+        );
     }
 
     #[test]
@@ -573,9 +807,9 @@ mod tests {
         let missing_mem_ref = r#"<relation id="1" version="1"><member type="way"/></relation>"#;
         let missing_mem_type = r#"<relation id="1" version="1"><member ref="22"/></relation>"#;
         let data = vec![
-            ("id", missing_id),
-            ("ref", missing_mem_ref),
-            ("type", missing_mem_type),
+            ("id", 23, missing_id),
+            ("ref", 61, missing_mem_ref),
+            ("type", 59, missing_mem_type),
         ];
 
         validate_missing_attributes(data);
@@ -590,41 +824,121 @@ mod tests {
         let invalid_mem_type =
             r#"<relation id="1" version="1"><member type="INVALID" ref="2"/></relation>"#;
         let data = vec![
-            ("id", "INVALID", invalid_id),
-            ("version", "INVALID", invalid_version),
-            ("ref", "INVALID", invalid_mem_ref),
-            ("type", "INVALID", invalid_mem_type),
+            ("id", "INVALID", 36, invalid_id),
+            ("version", "INVALID", 36, invalid_version),
+            ("ref", "INVALID", 75, invalid_mem_ref),
+            ("type", "INVALID", 73, invalid_mem_type),
         ];
 
         validate_invalid_attributes(data);
     }
 
-    fn validate_missing_attributes(data: Vec<(&str, &str)>) {
-        for (field, xml) in data.iter() {
+    fn validate_missing_attributes(data: Vec<(&str, usize, &str)>) {
+        for (field, column, xml) in data.iter() {
             let error = XmlReader::new(xml.as_bytes()).read().unwrap_err();
             match error.kind() {
                 ErrorKind::ParseError => assert_eq!(
                     error.to_string(),
-                    format!("Line 1: Required attribute '{}' missing.", field)
+                    format!(
+                        "Line 1, column {}: Required attribute '{}' missing.",
+                        column, field
+                    )
                 ),
                 e => panic!("Unexpected kind {:?}", e),
             }
         }
     }
 
-    fn validate_invalid_attributes(data: Vec<(&str, &str, &str)>) {
-        for (field, value, xml) in data.iter() {
+    fn validate_invalid_attributes(data: Vec<(&str, &str, usize, &str)>) {
+        for (field, value, column, xml) in data.iter() {
             let error = XmlReader::new(xml.as_bytes()).read().unwrap_err();
             match error.kind() {
                 ErrorKind::ParseError => assert_eq!(
                     error.to_string(),
                     format!(
-                        "Line 1: The '{}' attribute contains invalid data '{}'.",
-                        field, value
+                        "Line 1, column {}: The '{}' attribute contains invalid data '{}'.",
+                        column, field, value
                     )
                 ),
                 e => panic!("Unexpected kind {:?}", e),
             }
         }
     }
+
+    #[test]
+    fn read_duplicate_tags() {
+        let xml = r#"<node id="1" lat="1" lon="1">
+            <tag k="name" v="First"/>
+            <tag k="name" v="Second"/>
+        </node>"#;
+
+        let osm = XmlReader::new(xml.as_bytes()).read().unwrap();
+        assert_eq!(osm.nodes[0].meta.tags.len(), 2);
+
+        let options = ReadOptions {
+            strict_tags: true,
+            ..ReadOptions::default()
+        };
+        let error = XmlReader::new(xml.as_bytes())
+            .with_options(options)
+            .read()
+            .unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+        assert_eq!(
+            error.message(),
+            Some(&"Duplicate tag key 'name' on node 1.".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_way_min_nodes() {
+        let xml = r#"<way id="7"><nd ref="1"/></way>"#;
+
+        let osm = XmlReader::new(xml.as_bytes()).read().unwrap();
+        assert_eq!(osm.ways[0].refs, vec![1]);
+
+        let options = ReadOptions {
+            min_way_nodes: 2,
+            ..ReadOptions::default()
+        };
+        let error = XmlReader::new(xml.as_bytes())
+            .with_options(options)
+            .read()
+            .unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::ParseError));
+        assert_eq!(
+            error.message(),
+            Some(&"Way 7 has 1 nodes, at least 2 required.".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_max_elements() {
+        let xml = r#"<osm>
+            <node id="1" lat="1" lon="1"/>
+            <node id="2" lat="2" lon="2"/>
+            <node id="3" lat="3" lon="3"/>
+        </osm>"#;
+
+        let options = ReadOptions {
+            max_elements: Some(3),
+            ..ReadOptions::default()
+        };
+        let osm = XmlReader::new(xml.as_bytes())
+            .with_options(options)
+            .read()
+            .unwrap();
+        assert_eq!(osm.nodes.len(), 3);
+
+        let options = ReadOptions {
+            max_elements: Some(2),
+            ..ReadOptions::default()
+        };
+        let error = XmlReader::new(xml.as_bytes())
+            .with_options(options)
+            .read()
+            .unwrap_err();
+        assert!(error.kind_eq(&ErrorKind::TooManyElements { limit: 2 }));
+        assert_eq!(error.line(), Some(4));
+    }
 }
@@ -1,48 +1,208 @@
-use super::super::chrono::{TimeZone, Utc};
 use super::quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use super::quick_xml::Writer;
 use crate::geo::Boundary;
 use crate::osm_io::error::{Error, Result};
-use crate::osm_io::OsmWrite;
-use crate::{Meta, Node, Osm, Relation, RelationMember, Tag, Way};
+use crate::osm_io::{format_timestamp, ElementSink, OsmWrite, WriteOptions};
+use crate::{Element, Meta, Node, Osm, Relation, RelationMember, Tag, Way};
+use std::collections::HashMap;
 use std::io::Write;
 
 const OSM_VERSION: &str = "0.6";
 const OSM_GENERATOR: &str = "Vadeen OSM";
-const XML_VERSION: &[u8] = b"1.0";
-const XML_ENCODING: &[u8] = b"UTF-8";
+const XML_VERSION: &str = "1.0";
+const XML_ENCODING: &str = "UTF-8";
 
 /// A writer for the xml format.
 pub struct XmlWriter<W: Write> {
     writer: Writer<W>,
+    pretty: bool,
+    generator: String,
+    api_version: String,
+    xml_version: String,
+    encoding: Option<String>,
+    standalone: Option<bool>,
+    attributes: Vec<(String, String)>,
+    options: WriteOptions,
+    /// Extra indentation of elements, when they are nested in the blocks of an osmChange file.
+    pub(super) depth: usize,
 }
 
 impl<W: Write> XmlWriter<W> {
     pub fn new(inner: W) -> XmlWriter<W> {
         XmlWriter {
             writer: Writer::new(inner),
+            pretty: true,
+            generator: OSM_GENERATOR.to_owned(),
+            api_version: OSM_VERSION.to_owned(),
+            xml_version: XML_VERSION.to_owned(),
+            encoding: Some(XML_ENCODING.to_owned()),
+            standalone: None,
+            attributes: Vec::new(),
+            options: WriteOptions::default(),
+            depth: 0,
         }
     }
 
-    /// Write the start tags: Xml header and <osm>-tag.
-    fn write_start(&mut self) -> Result<()> {
+    /// Set the options for writing, see `WriteOptions`.
+    pub fn with_options(mut self, options: WriteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Consume the writer and return the inner writer, e.g. the buffer written to. Same as
+    /// `OsmWrite::into_inner`, but without boxing.
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    /// Set the generator written in the `<osm>` element. Defaults to "Vadeen OSM".
+    pub fn with_generator(mut self, generator: &str) -> Self {
+        self.generator = generator.to_owned();
+        self
+    }
+
+    /// Set the OSM API version written in the `<osm>` element. Defaults to "0.6".
+    pub fn with_api_version(mut self, api_version: &str) -> Self {
+        self.api_version = api_version.to_owned();
+        self
+    }
+
+    /// Set the xml declaration written first in the output. Defaults to version "1.0", encoding
+    /// "UTF-8" and no standalone declaration. The output is always UTF-8, whatever encoding is
+    /// declared.
+    ///
+    /// # Example
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// # use vadeen_osm::osm_io::{OsmWrite, XmlWriter};
+    /// let osm = OsmBuilder::default().build();
+    /// let mut writer = XmlWriter::new(Vec::new())
+    ///     .with_declaration("1.0", Some("UTF-8"), Some(true));
+    /// writer.write(&osm).unwrap();
+    ///
+    /// let xml = String::from_utf8(writer.into_inner()).unwrap();
+    /// assert!(xml.starts_with(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#));
+    /// ```
+    pub fn with_declaration(
+        mut self,
+        version: &str,
+        encoding: Option<&str>,
+        standalone: Option<bool>,
+    ) -> Self {
+        self.xml_version = version.to_owned();
+        self.encoding = encoding.map(str::to_owned);
+        self.standalone = standalone;
+        self
+    }
+
+    /// Add an attribute to the `<osm>` element, e.g. a namespace declaration such as
+    /// `xmlns:xlink`. Written in the order added, after the extra attributes of the map. Replaces
+    /// an extra attribute of the map, or an attribute added before, with the same name. The
+    /// `version` and `generator` attributes are set as by `with_api_version` and
+    /// `with_generator`.
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        match name {
+            "version" => return self.with_api_version(value),
+            "generator" => return self.with_generator(value),
+            _ => {}
+        }
+
+        if let Some(attribute) = self.attributes.iter_mut().find(|(n, _)| n == name) {
+            attribute.1 = value.to_owned();
+        } else {
+            self.attributes.push((name.to_owned(), value.to_owned()));
+        }
+        self
+    }
+
+    /// Toggle pretty printing. When enabled (default) elements are written on separate lines and
+    /// indented with tabs. When disabled no whitespace is written between elements.
+    ///
+    /// # Example
+    /// ```
+    /// # use vadeen_osm::OsmBuilder;
+    /// # use vadeen_osm::osm_io::{OsmWrite, XmlWriter};
+    /// let osm = OsmBuilder::default().build();
+    /// let mut writer = XmlWriter::new(Vec::new()).pretty(false);
+    /// writer.write(&osm).unwrap();
+    /// ```
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Write indentation, if pretty printing is enabled.
+    pub(super) fn write_indent(&mut self, level: usize) -> Result<()> {
+        if self.pretty {
+            self.writer.write(&b"\t".repeat(level + self.depth))?;
+        }
+        Ok(())
+    }
+
+    /// Write line break, if pretty printing is enabled.
+    pub(super) fn write_newline(&mut self) -> Result<()> {
+        if self.pretty {
+            self.writer.write(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write the start tags: Xml header and <osm>-tag. Extra attributes are written after the
+    /// version and generator, sorted by name, followed by the attributes added to the writer.
+    /// Extra attributes named `version` or `generator` are skipped, since those are always
+    /// written.
+    fn write_start(&mut self, extra_attributes: &HashMap<String, String>) -> Result<()> {
+        let mut extra: Vec<(String, String)> = extra_attributes
+            .iter()
+            .filter(|(k, _)| *k != "version" && *k != "generator")
+            .filter(|(k, _)| self.attributes.iter().all(|(name, _)| name != *k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        extra.sort_unstable();
+        extra.extend(self.attributes.iter().cloned());
+
+        let extra: Vec<(&str, &str)> = extra
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        self.write_root_start(b"osm", &extra)
+    }
+
+    /// Write the xml header and the start of the root element, e.g. <osm> or <osmChange>.
+    pub(super) fn write_root_start(&mut self, name: &[u8], extra: &[(&str, &str)]) -> Result<()> {
+        let standalone = self.standalone.map(|s| if s { "yes" } else { "no" });
         self.writer.write_event(Event::Decl(BytesDecl::new(
-            XML_VERSION,
-            Some(XML_ENCODING),
-            None,
+            self.xml_version.as_bytes(),
+            self.encoding.as_ref().map(|e| e.as_bytes()),
+            standalone.map(str::as_bytes),
         )))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
 
-        let elem = BytesStart::owned_name(b"osm".to_vec())
-            .with_attributes(vec![("version", OSM_VERSION), ("generator", OSM_GENERATOR)]);
+        let elem = BytesStart::owned_name(name.to_vec()).with_attributes(vec![
+            ("version", self.api_version.as_ref()),
+            ("generator", self.generator.as_ref()),
+        ]);
+        let elem = elem.with_attributes(extra.iter().cloned());
         self.writer.write_event(Event::Start(elem))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
         Ok(())
     }
 
     /// Write end of osm: </osm>.
     fn write_end(&mut self) -> Result<()> {
-        let elem = BytesEnd::owned(b"osm".to_vec());
+        self.write_end_tag(b"osm")
+    }
+
+    /// Write a start tag without attributes, e.g. <create>.
+    pub(super) fn write_start_tag(&mut self, name: &[u8]) -> Result<()> {
+        let elem = BytesStart::owned_name(name.to_vec());
+        self.writer.write_event(Event::Start(elem))?;
+        Ok(())
+    }
+
+    /// Write an end tag, e.g. </osm>.
+    pub(super) fn write_end_tag(&mut self, name: &[u8]) -> Result<()> {
+        let elem = BytesEnd::owned(name.to_vec());
         self.writer.write_event(Event::End(elem))?;
         Ok(())
     }
@@ -56,94 +216,118 @@ impl<W: Write> XmlWriter<W> {
             ("maxlon", bounds.max.lon().to_string().as_ref()),
         ]);
 
-        self.writer.write(b"\t")?;
+        self.write_indent(1)?;
         self.writer.write_event(Event::Empty(elem))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
         Ok(())
     }
 
     /// See: https://wiki.openstreetmap.org/wiki/Node
-    fn write_node(&mut self, node: &Node) -> Result<()> {
+    pub(super) fn write_node(&mut self, node: &Node) -> Result<()> {
         let mut elem = BytesStart::owned_name(b"node".to_vec()).with_attributes(vec![
             ("id", node.id.to_string().as_ref()),
             ("lat", node.coordinate.lat().to_string().as_ref()),
             ("lon", node.coordinate.lon().to_string().as_ref()),
         ]);
 
-        add_meta_attributes(&mut elem, &node.meta);
+        add_meta_attributes(&mut elem, &node.meta)?;
 
         if node.meta.tags.is_empty() {
-            self.writer.write(b"\t")?;
+            self.write_indent(1)?;
             self.writer.write_event(Event::Empty(elem))?;
         } else {
-            self.writer.write(b"\t")?;
+            self.write_indent(1)?;
             self.writer.write_event(Event::Start(elem))?;
-            self.writer.write(b"\n")?;
+            self.write_newline()?;
 
             self.write_tags(&node.meta.tags)?;
 
-            self.writer.write(b"\t")?;
+            self.write_indent(1)?;
             self.writer
                 .write_event(Event::End(BytesEnd::owned(b"node".to_vec())))?;
         }
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
         Ok(())
     }
 
     /// See: https://wiki.openstreetmap.org/wiki/Way
-    fn write_way(&mut self, way: &Way) -> Result<()> {
+    pub(super) fn write_way(&mut self, way: &Way) -> Result<()> {
         let mut elem = BytesStart::owned_name(b"way".to_vec());
         elem.push_attribute(("id", way.id.to_string().as_ref()));
 
-        add_meta_attributes(&mut elem, &way.meta);
+        add_meta_attributes(&mut elem, &way.meta)?;
 
-        self.writer.write(b"\t")?;
+        self.write_indent(1)?;
         self.writer.write_event(Event::Start(elem))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
 
         for r in &way.refs {
             let mut nd = BytesStart::owned_name(b"nd".to_vec());
             nd.push_attribute(("ref", r.to_string().as_ref()));
-            self.writer.write(b"\t\t")?;
+            self.write_indent(2)?;
             self.writer.write_event(Event::Empty(nd))?;
-            self.writer.write(b"\n")?;
+            self.write_newline()?;
         }
 
         self.write_tags(&way.meta.tags)?;
 
-        self.writer.write(b"\t")?;
+        self.write_indent(1)?;
         self.writer
             .write_event(Event::End(BytesEnd::owned(b"way".to_vec())))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
         Ok(())
     }
 
     /// See: https://wiki.openstreetmap.org/wiki/Relation
-    fn write_relation(&mut self, rel: &Relation) -> Result<()> {
+    pub(super) fn write_relation(&mut self, rel: &Relation) -> Result<()> {
         let mut elem = BytesStart::owned_name(b"relation".to_vec());
         elem.push_attribute(("id", rel.id.to_string().as_ref()));
 
-        add_meta_attributes(&mut elem, &rel.meta);
+        add_meta_attributes(&mut elem, &rel.meta)?;
 
-        self.writer.write(b"\t")?;
+        self.write_indent(1)?;
         self.writer.write_event(Event::Start(elem))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
 
         for m in &rel.members {
             let mut mem = BytesStart::owned_name(b"member".to_vec());
             add_member_attributes(&mut mem, m);
 
-            self.writer.write(b"\t\t")?;
+            self.write_indent(2)?;
             self.writer.write_event(Event::Empty(mem))?;
-            self.writer.write(b"\n")?;
+            self.write_newline()?;
         }
 
         self.write_tags(&rel.meta.tags)?;
 
-        self.writer.write(b"\t")?;
+        self.write_indent(1)?;
         self.writer
             .write_event(Event::End(BytesEnd::owned(b"relation".to_vec())))?;
-        self.writer.write(b"\n")?;
+        self.write_newline()?;
+        Ok(())
+    }
+
+    /// Write an element in the minimal form used in the delete block of osmChange files: only the
+    /// id, the meta attributes and for nodes the coordinate. Tags, node refs and members are left
+    /// out.
+    pub(super) fn write_deleted(&mut self, element: Element) -> Result<()> {
+        let mut elem = match element {
+            Element::Node(node) => BytesStart::owned_name(b"node".to_vec()).with_attributes(vec![
+                ("id", node.id.to_string().as_ref()),
+                ("lat", node.coordinate.lat().to_string().as_ref()),
+                ("lon", node.coordinate.lon().to_string().as_ref()),
+            ]),
+            Element::Way(way) => BytesStart::owned_name(b"way".to_vec())
+                .with_attributes(vec![("id", way.id.to_string().as_ref())]),
+            Element::Relation(rel) => BytesStart::owned_name(b"relation".to_vec())
+                .with_attributes(vec![("id", rel.id.to_string().as_ref())]),
+        };
+
+        add_meta_attributes(&mut elem, element.meta())?;
+
+        self.write_indent(1)?;
+        self.writer.write_event(Event::Empty(elem))?;
+        self.write_newline()?;
         Ok(())
     }
 
@@ -153,9 +337,9 @@ impl<W: Write> XmlWriter<W> {
             let tag_elem = BytesStart::owned_name(b"tag".to_vec())
                 .with_attributes(vec![("k", tag.key.as_ref()), ("v", tag.value.as_ref())]);
 
-            self.writer.write(b"\t\t")?;
+            self.write_indent(2)?;
             self.writer.write_event(Event::Empty(tag_elem))?;
-            self.writer.write(b"\n")?;
+            self.write_newline()?;
         }
         Ok(())
     }
@@ -163,7 +347,7 @@ impl<W: Write> XmlWriter<W> {
 
 impl<W: Write> OsmWrite<W> for XmlWriter<W> {
     fn write(&mut self, osm: &Osm) -> std::result::Result<(), Error> {
-        self.write_start()?;
+        self.write_start(&osm.extra_attributes)?;
 
         if let Some(boundary) = &osm.boundary {
             self.write_bounds(boundary)?;
@@ -174,11 +358,15 @@ impl<W: Write> OsmWrite<W> for XmlWriter<W> {
         }
 
         for way in &osm.ways {
-            self.write_way(way)?;
+            if self.options.include_way(way) {
+                self.write_way(way)?;
+            }
         }
 
         for rel in &osm.relations {
-            self.write_relation(rel)?;
+            if self.options.include_relation(rel) {
+                self.write_relation(rel)?;
+            }
         }
 
         self.write_end()?;
@@ -186,7 +374,81 @@ impl<W: Write> OsmWrite<W> for XmlWriter<W> {
     }
 
     fn into_inner(self: Box<Self>) -> W {
-        self.writer.into_inner()
+        XmlWriter::into_inner(*self)
+    }
+}
+
+/// Writes elements as they are read instead of a whole map, see `osm_io::convert`. The `<osm>`
+/// element is started by the first element or boundary, so the attributes of the input can be
+/// written to it.
+pub(crate) struct XmlStreamWriter<W: Write> {
+    writer: XmlWriter<W>,
+    attributes: HashMap<String, String>,
+    started: bool,
+    count: usize,
+}
+
+impl<W: Write> XmlStreamWriter<W> {
+    pub(crate) fn new(writer: XmlWriter<W>) -> Self {
+        XmlStreamWriter {
+            writer,
+            attributes: HashMap::new(),
+            started: false,
+            count: 0,
+        }
+    }
+
+    fn start(&mut self) -> Result<()> {
+        if !self.started {
+            self.started = true;
+            self.writer.write_start(&self.attributes)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> ElementSink for XmlStreamWriter<W> {
+    fn set_attributes(&mut self, attributes: HashMap<String, String>) -> Result<()> {
+        self.attributes = attributes;
+        Ok(())
+    }
+
+    fn set_boundary(&mut self, boundary: Boundary) -> Result<()> {
+        self.start()?;
+        self.writer.write_bounds(&boundary)
+    }
+
+    fn add_node(&mut self, node: Node) -> Result<()> {
+        self.start()?;
+        self.count += 1;
+        self.writer.write_node(&node)
+    }
+
+    fn add_way(&mut self, way: Way) -> Result<()> {
+        self.start()?;
+        self.count += 1;
+        if self.writer.options.include_way(&way) {
+            self.writer.write_way(&way)?;
+        }
+        Ok(())
+    }
+
+    fn add_relation(&mut self, relation: Relation) -> Result<()> {
+        self.start()?;
+        self.count += 1;
+        if self.writer.options.include_relation(&relation) {
+            self.writer.write_relation(&relation)?;
+        }
+        Ok(())
+    }
+
+    fn element_count(&self) -> usize {
+        self.count
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.start()?;
+        self.writer.write_end()
     }
 }
 
@@ -206,37 +468,95 @@ fn add_member_attributes(elem: &mut BytesStart, mem: &RelationMember) {
 }
 
 /// Add the meta attributes to an element.
-fn add_meta_attributes(elem: &mut BytesStart, meta: &Meta) {
+fn add_meta_attributes(elem: &mut BytesStart, meta: &Meta) -> Result<()> {
+    if let Some(action) = meta.action {
+        elem.push_attribute(("action", action.as_str()));
+    }
+
+    if let Some(visible) = meta.visible {
+        elem.push_attribute(("visible", visible.to_string().as_ref()));
+    }
+
     let version = meta.version;
     elem.push_attribute(("version", version.unwrap_or(1).to_string().as_ref()));
 
     if let Some(author) = &meta.author {
-        let dt = Utc.timestamp(author.created, 0);
-        let time_str = dt.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string();
+        let time_str = format_timestamp(author.created)?;
         elem.extend_attributes(vec![
             ("uid", author.uid.to_string().as_ref()),
             ("user", author.user.as_ref()),
             ("changeset", author.change_set.to_string().as_ref()),
             ("timestamp", time_str.as_ref()),
         ]);
+    } else if let Some(timestamp) = meta.timestamp {
+        elem.push_attribute(("timestamp", format_timestamp(timestamp)?.as_ref()));
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::io::Cursor;
 
     use crate::geo::Boundary;
     use crate::osm_io::xml::XmlWriter;
-    use crate::{AuthorInformation, Meta, Node, Relation, RelationMember, Way};
+    use crate::osm_io::{OsmWrite, WriteOptions};
+    use crate::{AuthorInformation, Meta, Node, Osm, Relation, RelationMember, Way};
 
     use super::OSM_GENERATOR;
     use super::OSM_VERSION;
 
+    #[test]
+    fn write_start_with_declaration_and_attributes() {
+        let mut attributes = HashMap::new();
+        attributes.insert("upload".to_owned(), "false".to_owned());
+        attributes.insert("xmlns:xlink".to_owned(), "old".to_owned());
+
+        let mut writer = XmlWriter::new(Cursor::new(Vec::new()))
+            .with_declaration("1.0", Some("UTF-8"), Some(true))
+            .with_attribute("xmlns:xlink", "http://www.w3.org/1999/xlink");
+        writer.write_start(&attributes).unwrap();
+
+        let xml = writer.writer.into_inner().into_inner();
+        assert_eq!(
+            String::from_utf8_lossy(&xml),
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+                 <osm version=\"{}\" generator=\"{}\" upload=\"false\" \
+                 xmlns:xlink=\"http://www.w3.org/1999/xlink\">\n",
+                OSM_VERSION, OSM_GENERATOR
+            )
+        );
+    }
+
+    #[test]
+    fn write_start_without_duplicate_attributes() {
+        let mut attributes = HashMap::new();
+        attributes.insert("generator".to_owned(), "Other".to_owned());
+        attributes.insert("version".to_owned(), "0.5".to_owned());
+
+        let mut writer = XmlWriter::new(Cursor::new(Vec::new()))
+            .with_attribute("upload", "true")
+            .with_attribute("generator", "My Tool 1.0")
+            .with_attribute("upload", "false");
+        writer.write_start(&attributes).unwrap();
+
+        let xml = writer.writer.into_inner().into_inner();
+        assert_eq!(
+            String::from_utf8_lossy(&xml),
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <osm version=\"{}\" generator=\"My Tool 1.0\" upload=\"false\">\n",
+                OSM_VERSION
+            )
+        );
+    }
+
     #[test]
     fn write_start() {
         let mut writer = XmlWriter::new(Cursor::new(Vec::new()));
-        writer.write_start().unwrap();
+        writer.write_start(&HashMap::new()).unwrap();
 
         let xml = writer.writer.into_inner().into_inner();
         assert_eq!(
@@ -249,6 +569,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn write_start_custom_generator() {
+        let mut writer = XmlWriter::new(Cursor::new(Vec::new()))
+            .with_generator("My Tool 1.0")
+            .with_api_version("0.7");
+        writer.write_start(&HashMap::new()).unwrap();
+
+        let xml = writer.writer.into_inner().into_inner();
+        assert_eq!(
+            String::from_utf8_lossy(&xml),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <osm version=\"0.7\" generator=\"My Tool 1.0\">\n"
+        );
+    }
+
     #[test]
     fn write_end() {
         let mut writer = XmlWriter::new(Cursor::new(Vec::new()));
@@ -287,6 +622,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn write_node_with_timestamp_without_author() {
+        let mut writer = XmlWriter::new(Cursor::new(Vec::new()));
+        let mut node = Node {
+            id: 10,
+            coordinate: (65.12, 55.21).into(),
+            meta: Meta {
+                timestamp: Some(1577934245),
+                ..Meta::default()
+            },
+        };
+        writer.write_node(&node).unwrap();
+
+        let xml = writer.writer.into_inner().into_inner();
+        assert_eq!(
+            String::from_utf8_lossy(&xml),
+            "\t<node id=\"10\" lat=\"65.12\" lon=\"55.21\" version=\"1\" \
+             timestamp=\"2020-01-02T03:04:05Z\"/>\n"
+        );
+
+        node.meta.timestamp = Some(i64::MAX);
+        let mut writer = XmlWriter::new(Cursor::new(Vec::new()));
+        assert!(writer.write_node(&node).is_err());
+    }
+
     #[test]
     fn write_node_with_tags() {
         let mut writer = XmlWriter::new(Cursor::new(Vec::new()));
@@ -322,6 +682,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn write_node_compact() {
+        let mut writer = XmlWriter::new(Cursor::new(Vec::new())).pretty(false);
+        writer
+            .write_node(&Node {
+                id: 10,
+                coordinate: (65.12, 55.21).into(),
+                meta: Meta {
+                    tags: vec![("name", "Neu Broderstorf").into()],
+                    version: Some(1),
+                    author: None,
+                    ..Meta::default()
+                },
+            })
+            .unwrap();
+
+        let xml = writer.writer.into_inner().into_inner();
+        assert_eq!(
+            String::from_utf8_lossy(&xml),
+            "<node id=\"10\" lat=\"65.12\" lon=\"55.21\" version=\"1\">\
+             <tag k=\"name\" v=\"Neu Broderstorf\"/>\
+             </node>"
+        );
+    }
+
     #[test]
     fn write_way() {
         let mut writer = XmlWriter::new(Cursor::new(Vec::new()));
@@ -413,4 +798,50 @@ mod tests {
             "\t<bounds minlat=\"-90\" minlon=\"-180\" maxlat=\"90\" maxlon=\"180\"/>\n"
         )
     }
+
+    #[test]
+    fn write_skip_empty() {
+        let mut osm = Osm::default();
+        osm.add_way(Way {
+            id: 1,
+            refs: vec![],
+            meta: Meta::default(),
+        });
+        osm.add_way(Way {
+            id: 2,
+            refs: vec![3, 4],
+            meta: Meta::default(),
+        });
+        osm.add_relation(Relation {
+            id: 5,
+            members: vec![],
+            meta: Meta::default(),
+        });
+
+        let options = WriteOptions::default().skip_empty(true);
+        let mut writer = XmlWriter::new(Vec::new()).with_options(options);
+        writer.write(&osm).unwrap();
+
+        let xml = String::from_utf8(writer.writer.into_inner()).unwrap();
+        assert!(!xml.contains("<way id=\"1\""));
+        assert!(xml.contains("<way id=\"2\""));
+        assert!(!xml.contains("<relation"));
+    }
+
+    #[test]
+    fn into_inner_without_box() {
+        let mut osm = Osm::default();
+        osm.add_node(Node {
+            id: 1,
+            coordinate: (1.0, 2.0).into(),
+            meta: Meta::default(),
+        });
+
+        let mut writer = XmlWriter::new(Vec::new()).pretty(false);
+        writer.write(&osm).unwrap();
+        let bytes: Vec<u8> = writer.into_inner();
+
+        let xml = String::from_utf8(bytes).unwrap();
+        assert!(xml.ends_with(r#"<node id="1" lat="1" lon="2" version="1"/></osm>"#));
+    }
 }
@@ -0,0 +1,279 @@
+//! Simple rendering of maps, intended for visual debugging.
+use crate::geo::{Boundary, Coordinate};
+use crate::{Node, Osm, Tag, Way};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Keys of tags that makes a closed way an area.
+const AREA_KEYS: [&str; 7] = [
+    "area", "building", "landuse", "natural", "leisure", "amenity", "water",
+];
+
+/// Colors of elements by tag, as key, value and RGB color. The value `*` matches any value.
+/// Rules are checked in order, so more specific rules are listed before the general ones. Used
+/// by [`default_color`].
+///
+/// [`default_color`]: fn.default_color.html
+pub const STYLE_COLORS: [(&str, &str, (u8, u8, u8)); 9] = [
+    ("natural", "water", (0xa0, 0xc8, 0xf0)),
+    ("waterway", "*", (0x60, 0x90, 0xd0)),
+    ("natural", "wood", (0x90, 0xc0, 0x80)),
+    ("landuse", "forest", (0x90, 0xc0, 0x80)),
+    ("landuse", "grass", (0xb0, 0xd8, 0x90)),
+    ("landuse", "*", (0xe0, 0xe0, 0xc8)),
+    ("building", "*", (0xc8, 0xa8, 0x98)),
+    ("highway", "motorway", (0xe0, 0x80, 0x50)),
+    ("highway", "*", (0x80, 0x80, 0x80)),
+];
+
+/// Color of elements without any tag in [`STYLE_COLORS`].
+///
+/// [`STYLE_COLORS`]: constant.STYLE_COLORS.html
+pub const DEFAULT_COLOR: (u8, u8, u8) = (0x40, 0x40, 0x40);
+
+/// Color of an element with the given tags, from the first rule in [`STYLE_COLORS`] matching any
+/// of the tags. Elements without a matching tag get [`DEFAULT_COLOR`].
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::render::{default_color, DEFAULT_COLOR};
+/// assert_eq!(default_color(&[("building", "yes").into()]), (0xc8, 0xa8, 0x98));
+/// assert_eq!(default_color(&[]), DEFAULT_COLOR);
+/// ```
+///
+/// [`STYLE_COLORS`]: constant.STYLE_COLORS.html
+/// [`DEFAULT_COLOR`]: constant.DEFAULT_COLOR.html
+pub fn default_color(tags: &[Tag]) -> (u8, u8, u8) {
+    color_for(tags, &STYLE_COLORS)
+}
+
+/// Color of an element with the given tags, from the first of `rules` matching any of the tags.
+/// The rules have the same format as [`STYLE_COLORS`]. Elements without a matching tag get
+/// [`DEFAULT_COLOR`].
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::render::{color_for, DEFAULT_COLOR};
+/// let rules = [("amenity", "bench", (0x80, 0x40, 0x00)), ("amenity", "*", (0xff, 0, 0))];
+/// assert_eq!(color_for(&[("amenity", "bench").into()], &rules), (0x80, 0x40, 0x00));
+/// assert_eq!(color_for(&[("amenity", "cafe").into()], &rules), (0xff, 0, 0));
+/// assert_eq!(color_for(&[("building", "yes").into()], &rules), DEFAULT_COLOR);
+/// ```
+///
+/// [`STYLE_COLORS`]: constant.STYLE_COLORS.html
+/// [`DEFAULT_COLOR`]: constant.DEFAULT_COLOR.html
+pub fn color_for(tags: &[Tag], rules: &[(&str, &str, (u8, u8, u8))]) -> (u8, u8, u8) {
+    rules
+        .iter()
+        .find(|(key, value, _)| {
+            tags.iter()
+                .any(|t| t.key == *key && (*value == "*" || t.value == *value))
+        })
+        .map(|(_, _, color)| *color)
+        .unwrap_or(DEFAULT_COLOR)
+}
+
+/// Render a map as an SVG image `width` pixels wide. The height is set so the aspect ratio of
+/// the map boundary is preserved.
+///
+/// Ways are drawn as poly lines, closed ways tagged as areas are drawn as filled polygons. Nodes
+/// with tags are drawn as small circles. Ways are colored by [`default_color`].
+///
+/// [`default_color`]: fn.default_color.html
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::OsmBuilder;
+/// # use vadeen_osm::render::to_svg;
+/// let mut builder = OsmBuilder::default();
+/// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+///
+/// let svg = to_svg(&builder.build(), 100);
+/// assert!(svg.starts_with("<svg"));
+/// ```
+pub fn to_svg(osm: &Osm, width: u32) -> String {
+    to_svg_with_colors(osm, width, &STYLE_COLORS)
+}
+
+/// Render a map as an SVG image like [`to_svg`], with ways colored by `rules` instead of
+/// [`STYLE_COLORS`]. See [`color_for`].
+///
+/// [`to_svg`]: fn.to_svg.html
+/// [`STYLE_COLORS`]: constant.STYLE_COLORS.html
+/// [`color_for`]: fn.color_for.html
+pub fn to_svg_with_colors(osm: &Osm, width: u32, rules: &[(&str, &str, (u8, u8, u8))]) -> String {
+    let projection = Projection::new(osm, width);
+    let nodes: HashMap<i64, &Node> = osm.nodes.iter().map(|n| (n.id, n)).collect();
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        projection.width, projection.height, projection.width, projection.height
+    )
+    .unwrap();
+
+    for way in &osm.ways {
+        let points: Vec<String> = way
+            .refs
+            .iter()
+            .filter_map(|id| nodes.get(id))
+            .map(|node| {
+                let (x, y) = projection.project(node.coordinate);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect();
+
+        let (r, g, b) = color_for(&way.meta.tags, rules);
+        if is_area(way) {
+            writeln!(
+                svg,
+                r##"  <polygon points="{}" fill="#{:02x}{:02x}{:02x}" stroke="#404040" />"##,
+                points.join(" "),
+                r,
+                g,
+                b
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                svg,
+                r##"  <polyline points="{}" fill="none" stroke="#{:02x}{:02x}{:02x}" />"##,
+                points.join(" "),
+                r,
+                g,
+                b
+            )
+            .unwrap();
+        }
+    }
+
+    for node in osm.nodes.iter().filter(|n| !n.meta.tags.is_empty()) {
+        let (x, y) = projection.project(node.coordinate);
+        writeln!(
+            svg,
+            r##"  <circle cx="{:.2}" cy="{:.2}" r="2" fill="#c03030" />"##,
+            x, y
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A closed way with any of the area tags.
+fn is_area(way: &Way) -> bool {
+    if way.refs.len() < 4 || way.refs.first() != way.refs.last() {
+        return false;
+    }
+
+    way.meta.tags.iter().any(|tag| {
+        AREA_KEYS.contains(&tag.key.as_str()) && !(tag.key == "area" && tag.value == "no")
+    })
+}
+
+/// Projects coordinates within a boundary to pixels.
+struct Projection {
+    boundary: Boundary,
+    width: u32,
+    height: u32,
+    scale: f64,
+}
+
+impl Projection {
+    fn new(osm: &Osm, width: u32) -> Self {
+        let boundary = match &osm.boundary {
+            Some(b) if b.min.lat <= b.max.lat && b.min.lon <= b.max.lon => b.clone(),
+            _ => Boundary::from_nodes(&osm.nodes),
+        };
+
+        let lon_span = (boundary.max.lon() - boundary.min.lon()).max(0.0);
+        let lat_span = (boundary.max.lat() - boundary.min.lat()).max(0.0);
+
+        let scale = if lon_span > 0.0 {
+            width as f64 / lon_span
+        } else if lat_span > 0.0 {
+            width as f64 / lat_span
+        } else {
+            1.0
+        };
+        let height = (lat_span * scale).round().max(1.0) as u32;
+
+        Projection {
+            boundary,
+            width,
+            height,
+            scale,
+        }
+    }
+
+    /// Latitude increases upwards, but the svg y axis increases downwards, so it is flipped.
+    fn project(&self, coordinate: Coordinate) -> (f64, f64) {
+        let x = (coordinate.lon() - self.boundary.min.lon()) * self.scale;
+        let y = (self.boundary.max.lat() - coordinate.lat()) * self.scale;
+        (x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::{color_for, default_color, to_svg, to_svg_with_colors, DEFAULT_COLOR};
+    use crate::{OsmBuilder, Tag};
+
+    #[test]
+    fn style_colors() {
+        let (r, g, b) = default_color(&[("name", "Lake").into(), ("natural", "water").into()]);
+        assert!(b > r && b > g, "water is not blue");
+
+        let tags: Vec<Tag> = vec![("shop", "bakery").into(), ("natural", "peak").into()];
+        assert_eq!(default_color(&tags), DEFAULT_COLOR);
+
+        // Specific values have priority over any value.
+        let tags: Vec<Tag> = vec![("highway", "motorway").into()];
+        assert_eq!(default_color(&tags), (0xe0, 0x80, 0x50));
+    }
+
+    #[test]
+    fn custom_colors() {
+        let rules = [("highway", "*", (0x12, 0x34, 0x56))];
+        let tags: Vec<Tag> = vec![("highway", "motorway").into()];
+        assert_eq!(color_for(&tags, &rules), (0x12, 0x34, 0x56));
+        assert_eq!(color_for(&tags, &[]), DEFAULT_COLOR);
+
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(0.0, 0.0), (1.0, 2.0)], vec![("highway", "primary")]);
+        let svg = to_svg_with_colors(&builder.build(), 200, &rules);
+        assert!(svg.contains(r##"stroke="#123456""##));
+    }
+
+    #[test]
+    fn render_way() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(0.0, 0.0), (1.0, 2.0)], vec![("highway", "primary")]);
+
+        let svg = to_svg(&builder.build(), 200);
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 200 100">"#
+        ));
+        assert!(svg.contains(
+            r##"<polyline points="0.00,100.00 200.00,0.00" fill="none" stroke="#808080" />"##
+        ));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn render_area_and_point() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polygon(
+            vec![vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.0, 0.0)]],
+            vec![("building", "yes")],
+        );
+        builder.add_point((0.5, 0.5), vec![("amenity", "bench")]);
+
+        let svg = to_svg(&builder.build(), 10);
+        assert!(svg.contains(r#"<polygon points="0.00,10.00 10.00,10.00 10.00,0.00 0.00,10.00""#));
+        assert!(svg.contains(r#"<circle cx="5.00" cy="5.00" r="2""#));
+        assert!(!svg.contains("<polyline"));
+    }
+}
@@ -0,0 +1,165 @@
+//! A read only view of a map with cached geometry, for repeated geometric processing.
+use crate::geo::{centroid, Boundary, Coordinate};
+use crate::geometry::{line_bounds, line_length};
+use crate::{Osm, Way};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+
+/// A map with the node coordinates indexed by id, and the coordinates of each way resolved the
+/// first time they are requested and cached after that.
+///
+/// Use this when the geometry of the same ways is needed many times, e.g. when rendering and
+/// measuring the same map, instead of the helpers on [`Osm`] that resolve coordinates on each
+/// call.
+///
+/// # Examples
+/// ```
+/// # use vadeen_osm::{OsmBuilder, ResolvedOsm};
+/// let mut builder = OsmBuilder::default();
+/// builder.add_polyline(vec![(1.0, 1.0), (2.0, 2.0)], vec![("highway", "primary")]);
+/// let osm = builder.build();
+///
+/// let resolved = ResolvedOsm::new(&osm);
+/// for way in &osm.ways {
+///     assert_eq!(resolved.way_geometry(way), [(1.0, 1.0).into(), (2.0, 2.0).into()]);
+/// }
+/// ```
+///
+/// [`Osm`]: struct.Osm.html
+pub struct ResolvedOsm<'a> {
+    osm: &'a Osm,
+    coordinates: HashMap<i64, Coordinate>,
+    way_index: HashMap<i64, usize>,
+    geometries: Vec<OnceCell<Vec<Coordinate>>>,
+}
+
+impl<'a> ResolvedOsm<'a> {
+    /// Index the nodes of `osm`. No way geometry is resolved until it is requested.
+    pub fn new(osm: &'a Osm) -> Self {
+        ResolvedOsm {
+            osm,
+            coordinates: osm.node_coordinates(),
+            way_index: osm
+                .ways
+                .iter()
+                .enumerate()
+                .map(|(i, w)| (w.id, i))
+                .collect(),
+            geometries: osm.ways.iter().map(|_| OnceCell::new()).collect(),
+        }
+    }
+
+    /// The map this view was created from.
+    pub fn osm(&self) -> &'a Osm {
+        self.osm
+    }
+
+    /// Coordinate of a node, if it is in the map.
+    pub fn node_coordinate(&self, id: i64) -> Option<Coordinate> {
+        self.coordinates.get(&id).cloned()
+    }
+
+    /// Coordinates of the nodes of a way, nodes missing from the map are skipped. Ways are looked
+    /// up by id, a way that is not in the map has no geometry.
+    pub fn way_geometry(&self, way: &Way) -> &[Coordinate] {
+        let index = match self.way_index.get(&way.id) {
+            Some(index) => *index,
+            None => return &[],
+        };
+
+        self.geometries[index].get_or_init(|| {
+            self.osm.ways[index]
+                .refs
+                .iter()
+                .filter_map(|id| self.coordinates.get(id).cloned())
+                .collect()
+        })
+    }
+
+    /// Length of a way in meters, see [`Osm::way_length`].
+    ///
+    /// [`Osm::way_length`]: struct.Osm.html#method.way_length
+    pub fn way_length(&self, way: &Way) -> f64 {
+        line_length(self.way_geometry(way))
+    }
+
+    /// Centroid of a way, see [`Osm::way_centroid`].
+    ///
+    /// [`Osm::way_centroid`]: struct.Osm.html#method.way_centroid
+    pub fn way_centroid(&self, way: &Way) -> Coordinate {
+        centroid(self.way_geometry(way))
+    }
+
+    /// Smallest boundary containing the nodes of a way, see [`Osm::way_bounds`].
+    ///
+    /// [`Osm::way_bounds`]: struct.Osm.html#method.way_bounds
+    pub fn way_bounds(&self, way: &Way) -> Option<Boundary> {
+        line_bounds(self.way_geometry(way))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::Boundary;
+    use crate::{Meta, OsmBuilder, ResolvedOsm, Way};
+
+    const NO_TAGS: Vec<(&str, &str)> = Vec::new();
+
+    #[test]
+    fn cached_way_geometry() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polyline(vec![(0.0, 0.0), (1.0, 1.0)], vec![("highway", "primary")]);
+        builder.add_polyline(vec![(1.0, 1.0), (2.0, 0.0)], vec![("highway", "primary")]);
+        let osm = builder.build();
+
+        let resolved = ResolvedOsm::new(&osm);
+        let first = resolved.way_geometry(&osm.ways[0]);
+        let second = resolved.way_geometry(&osm.ways[0]);
+        assert_eq!(first, [(0.0, 0.0).into(), (1.0, 1.0).into()]);
+        assert!(std::ptr::eq(first, second));
+
+        let other = resolved.way_geometry(&osm.ways[1]);
+        assert_eq!(other, [(1.0, 1.0).into(), (2.0, 0.0).into()]);
+        assert_eq!(first, resolved.way_geometry(&osm.ways[0]));
+    }
+
+    #[test]
+    fn measure_ways() {
+        let mut builder = OsmBuilder::default();
+        builder.add_closed_polyline(vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0)], NO_TAGS);
+        builder.add_polyline(vec![(5.0, 5.0), (6.0, 7.0)], NO_TAGS);
+        let osm = builder.build();
+
+        let resolved = ResolvedOsm::new(&osm);
+        for way in &osm.ways {
+            assert_eq!(resolved.way_length(way), osm.way_length(way));
+            assert_eq!(resolved.way_centroid(way), osm.way_centroid(way));
+            assert_eq!(resolved.way_bounds(way), osm.way_bounds(way));
+        }
+        assert_eq!(
+            resolved.way_bounds(&osm.ways[1]),
+            Some(Boundary::new((5.0, 5.0), (6.0, 7.0)))
+        );
+    }
+
+    #[test]
+    fn unknown_way_and_missing_nodes() {
+        let mut osm = OsmBuilder::default().build();
+        osm.add_way(Way {
+            id: 1,
+            refs: vec![10, 11],
+            meta: Meta::default(),
+        });
+
+        let resolved = ResolvedOsm::new(&osm);
+        assert!(resolved.way_geometry(&osm.ways[0]).is_empty());
+        assert_eq!(resolved.node_coordinate(10), None);
+
+        let unknown = Way {
+            id: 2,
+            refs: vec![],
+            meta: Meta::default(),
+        };
+        assert!(resolved.way_geometry(&unknown).is_empty());
+    }
+}
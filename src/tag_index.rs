@@ -0,0 +1,106 @@
+//! Index of elements by tag, for answering many tag queries on the same map.
+use crate::{ElementRef, Osm};
+use std::collections::HashMap;
+
+/// Elements by tag value, for a set of tag keys. Built with [`Osm::build_tag_index`].
+///
+/// [`Osm::build_tag_index`]: struct.Osm.html#method.build_tag_index
+#[derive(Debug, Default, Clone)]
+pub struct TagIndex {
+    keys: HashMap<String, HashMap<String, Vec<ElementRef>>>,
+}
+
+impl TagIndex {
+    /// Elements with the tag, in the order they appear in the map: nodes, ways and relations.
+    /// Empty if no element has the tag or the key is not indexed.
+    pub fn get(&self, key: &str, value: &str) -> &[ElementRef] {
+        self.keys
+            .get(key)
+            .and_then(|values| values.get(value))
+            .map_or(&[], |elements| elements.as_slice())
+    }
+
+    /// True if `key` is one of the keys the index was built for.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys.contains_key(key)
+    }
+}
+
+impl Osm {
+    /// Index all elements with tags of the given keys. Only the given keys are indexed, to limit
+    /// the memory used.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{ElementRef, OsmBuilder};
+    /// let mut builder = OsmBuilder::default();
+    /// let bench = builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+    /// builder.add_point((2.0, 2.0), vec![("amenity", "cafe")]);
+    ///
+    /// let index = builder.build().build_tag_index(&["amenity"]);
+    /// assert_eq!(index.get("amenity", "bench"), [ElementRef::Node(bench)]);
+    /// ```
+    pub fn build_tag_index(&self, keys: &[&str]) -> TagIndex {
+        let mut index = TagIndex {
+            keys: keys
+                .iter()
+                .map(|key| ((*key).to_owned(), HashMap::new()))
+                .collect(),
+        };
+
+        for element in self {
+            let element_ref = element.element_ref();
+            for tag in &element.meta().tags {
+                if let Some(values) = index.keys.get_mut(&tag.key) {
+                    let elements = values.entry(tag.value.clone()).or_insert_with(Vec::new);
+                    if elements.last() != Some(&element_ref) {
+                        elements.push(element_ref);
+                    }
+                }
+            }
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ElementRef, OsmBuilder};
+
+    #[test]
+    fn index_matches_scan() {
+        let mut builder = OsmBuilder::default();
+        builder.add_point((1.0, 1.0), vec![("amenity", "bench")]);
+        builder.add_point((2.0, 2.0), vec![("amenity", "cafe"), ("name", "Corner")]);
+        builder.add_polygon(
+            vec![vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.0, 0.0)]],
+            vec![("amenity", "parking")],
+        );
+        builder.add_point((3.0, 3.0), vec![("amenity", "bench"), ("amenity", "bench")]);
+        let osm = builder.build();
+
+        let index = osm.build_tag_index(&["amenity"]);
+        for value in &["bench", "cafe", "parking", "school"] {
+            let expected: Vec<ElementRef> = osm
+                .iter()
+                .filter_map(|element| {
+                    let found = element
+                        .meta()
+                        .tags
+                        .iter()
+                        .any(|t| t.key == "amenity" && t.value == *value);
+                    if found {
+                        Some(element.element_ref())
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            assert_eq!(index.get("amenity", value), expected.as_slice());
+        }
+
+        assert_eq!(index.get("amenity", "bench").len(), 2);
+        assert!(index.get("name", "Corner").is_empty());
+        assert!(!index.contains_key("name"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Validation of the references and areas of a map, and warnings for suspicious geometry.
+use crate::geo::{is_simple, Coordinate};
+use crate::geometry::assemble_rings;
+use crate::network::{is_restriction, resolve_restriction};
+use crate::{ElementRef, Osm, RelationMember, Way};
+use std::collections::{HashMap, HashSet};
+
+/// A problem found by [`Osm::validate`].
+///
+/// [`Osm::validate`]: struct.Osm.html#method.validate
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ValidationError {
+    /// A way refers to a node that is not in the map.
+    MissingNode { way_id: i64, node_id: i64 },
+    /// A relation has a member that is not in the map.
+    MissingMember {
+        relation_id: i64,
+        member: ElementRef,
+    },
+    /// The `outer` or `inner` ways of a multipolygon relation can not be joined into closed
+    /// rings.
+    UnclosedRing { relation_id: i64 },
+    /// A `type=restriction` relation without a `restriction` tag or without `from`, `via` and
+    /// `to` members, see [`Osm::turn_restrictions`].
+    ///
+    /// [`Osm::turn_restrictions`]: struct.Osm.html#method.turn_restrictions
+    InvalidRestriction { relation_id: i64 },
+    /// A closed way crosses or touches itself, see [`geo::is_simple`].
+    ///
+    /// [`geo::is_simple`]: geo/fn.is_simple.html
+    SelfIntersection { way_id: i64 },
+}
+
+/// Suspicious geometry found by [`Osm::warnings`]. Unlike a [`ValidationError`] the map is
+/// still valid, but the data is probably wrong.
+///
+/// [`Osm::warnings`]: struct.Osm.html#method.warnings
+/// [`ValidationError`]: enum.ValidationError.html
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Warning {
+    /// A way refers to the same node twice in a row.
+    DuplicateConsecutiveRef { way_id: i64, node_id: i64 },
+    /// Two consecutive nodes of a way are different nodes at the same coordinate.
+    ZeroLengthSegment { way_id: i64, from: i64, to: i64 },
+    /// A node at exactly (0, 0), usually the result of missing coordinates.
+    NullIsland { node_id: i64 },
+}
+
+impl Osm {
+    /// Check that all referenced elements are in the map, that closed ways do not intersect
+    /// themselves, that restriction relations are complete and that the ways of each
+    /// `type=multipolygon` relation form closed rings.
+    /// Returns all problems found, in the order of the elements.
+    ///
+    /// Ways without a role are treated as `outer`. Multipolygons with missing ways are only
+    /// reported as `MissingMember`, since their rings can not be checked.
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{Meta, Osm, ValidationError, Way};
+    /// let mut osm = Osm::default();
+    /// osm.add_way(Way {
+    ///     id: 1,
+    ///     refs: vec![2],
+    ///     meta: Meta::default(),
+    /// });
+    ///
+    /// let errors = osm.validate();
+    /// assert_eq!(errors, vec![ValidationError::MissingNode { way_id: 1, node_id: 2 }]);
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let nodes: HashSet<i64> = self.nodes.iter().map(|n| n.id).collect();
+        let ways: HashMap<i64, &Way> = self.ways.iter().map(|w| (w.id, w)).collect();
+        let relations: HashSet<i64> = self.relations.iter().map(|r| r.id).collect();
+
+        let mut errors = Vec::new();
+        for way in &self.ways {
+            for id in way.refs.iter().filter(|id| !nodes.contains(id)) {
+                errors.push(ValidationError::MissingNode {
+                    way_id: way.id,
+                    node_id: *id,
+                });
+            }
+        }
+
+        let coordinates: HashMap<i64, Coordinate> =
+            self.nodes.iter().map(|n| (n.id, n.coordinate)).collect();
+        let closed = |w: &&Way| w.refs.len() > 3 && w.refs.first() == w.refs.last();
+        for way in self.ways.iter().filter(closed) {
+            let ring: Option<Vec<Coordinate>> = way
+                .refs
+                .iter()
+                .map(|id| coordinates.get(id).copied())
+                .collect();
+            if let Some(ring) = ring {
+                if !is_simple(&ring) {
+                    errors.push(ValidationError::SelfIntersection { way_id: way.id });
+                }
+            }
+        }
+
+        for relation in &self.relations {
+            let mut complete = true;
+            for member in &relation.members {
+                let (member, found) = match member {
+                    RelationMember::Node(id, _) => (ElementRef::Node(*id), nodes.contains(id)),
+                    RelationMember::Way(id, _) => (ElementRef::Way(*id), ways.contains_key(id)),
+                    RelationMember::Relation(id, _) => {
+                        (ElementRef::Relation(*id), relations.contains(id))
+                    }
+                };
+                if !found {
+                    complete = false;
+                    errors.push(ValidationError::MissingMember {
+                        relation_id: relation.id,
+                        member,
+                    });
+                }
+            }
+
+            if is_restriction(relation) && resolve_restriction(relation).is_none() {
+                errors.push(ValidationError::InvalidRestriction {
+                    relation_id: relation.id,
+                });
+            }
+
+            let multipolygon = relation
+                .meta
+                .tags
+                .iter()
+                .any(|t| t.key == "type" && t.value == "multipolygon");
+            if !multipolygon || !complete {
+                continue;
+            }
+
+            let ways_with_role = |roles: &[&str]| -> Vec<&[i64]> {
+                relation
+                    .members
+                    .iter()
+                    .filter_map(|m| match m {
+                        RelationMember::Way(id, role) if roles.contains(&role.as_str()) => {
+                            Some(ways[id].refs.as_slice())
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            };
+
+            let outer = assemble_rings(ways_with_role(&["outer", ""]));
+            let inner = assemble_rings(ways_with_role(&["inner"]));
+            if outer.is_none() || inner.is_none() {
+                errors.push(ValidationError::UnclosedRing {
+                    relation_id: relation.id,
+                });
+            }
+        }
+        errors
+    }
+
+    /// Find suspicious geometry, nodes first and then ways, in map order. Nodes missing from
+    /// the map are ignored, see [`validate`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use vadeen_osm::{Osm, OsmBuilder, Warning};
+    /// let mut builder = OsmBuilder::default();
+    /// builder.add_point((0.0, 0.0), vec![("amenity", "bench")]);
+    ///
+    /// let osm = builder.build();
+    /// assert_eq!(osm.warnings(), vec![Warning::NullIsland { node_id: 1 }]);
+    /// ```
+    ///
+    /// [`validate`]: #method.validate
+    pub fn warnings(&self) -> Vec<Warning> {
+        let null_island = Coordinate { lat: 0, lon: 0 };
+        let mut warnings: Vec<Warning> = self
+            .nodes
+            .iter()
+            .filter(|n| n.coordinate == null_island)
+            .map(|n| Warning::NullIsland { node_id: n.id })
+            .collect();
+
+        let coordinates: HashMap<i64, Coordinate> =
+            self.nodes.iter().map(|n| (n.id, n.coordinate)).collect();
+        for way in &self.ways {
+            for pair in way.refs.windows(2) {
+                let (from, to) = (pair[0], pair[1]);
+                if from == to {
+                    warnings.push(Warning::DuplicateConsecutiveRef {
+                        way_id: way.id,
+                        node_id: from,
+                    });
+                    continue;
+                }
+
+                if let (Some(a), Some(b)) = (coordinates.get(&from), coordinates.get(&to)) {
+                    if a == b {
+                        warnings.push(Warning::ZeroLengthSegment {
+                            way_id: way.id,
+                            from,
+                            to,
+                        });
+                    }
+                }
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ElementRef, Meta, Node, Osm, OsmBuilder, ValidationError, Warning, Way};
+
+    const NO_TAGS: Vec<(&str, &str)> = Vec::new();
+
+    #[test]
+    fn valid_multipolygon() {
+        let mut builder = OsmBuilder::default();
+        builder.add_polygon(
+            vec![
+                vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (0.0, 0.0)],
+                vec![(1.0, 1.0), (1.0, 2.0), (2.0, 2.0), (1.0, 1.0)],
+            ],
+            vec![("landuse", "forest")],
+        );
+
+        assert_eq!(builder.build().validate(), vec![]);
+    }
+
+    #[test]
+    fn self_intersecting_way() {
+        let mut builder = OsmBuilder::default();
+        builder.add_closed_polyline(
+            vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)],
+            NO_TAGS,
+        );
+        let bowtie = builder.add_closed_polyline(
+            vec![(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)],
+            NO_TAGS,
+        );
+
+        assert_eq!(
+            builder.build().validate(),
+            vec![ValidationError::SelfIntersection { way_id: bowtie }]
+        );
+    }
+
+    #[test]
+    fn invalid_restriction() {
+        let mut builder = OsmBuilder::default();
+        let from = builder.add_polyline(vec![(0.0, 0.0), (0.0, 1.0)], NO_TAGS);
+        let to = builder.add_polyline(vec![(0.0, 1.0), (1.0, 1.0)], NO_TAGS);
+        let via = builder.add_point((0.0, 1.0), NO_TAGS);
+        builder.add_relation(
+            vec![
+                (ElementRef::Way(from), "from"),
+                (ElementRef::Node(via), "via"),
+                (ElementRef::Way(to), "to"),
+            ],
+            vec![("type", "restriction"), ("restriction", "no_left_turn")],
+        );
+        let missing_via = builder.add_relation(
+            vec![(ElementRef::Way(from), "from"), (ElementRef::Way(to), "to")],
+            vec![("type", "restriction"), ("restriction", "no_left_turn")],
+        );
+
+        let osm = builder.build();
+        assert_eq!(osm.turn_restrictions().len(), 1);
+        assert_eq!(
+            osm.validate(),
+            vec![ValidationError::InvalidRestriction {
+                relation_id: missing_via
+            }]
+        );
+    }
+
+    #[test]
+    fn multipolygon_with_gap() {
+        let mut builder = OsmBuilder::default();
+        let first = builder.add_polyline(vec![(0.0, 0.0), (0.0, 1.0), (1.0, 1.0)], NO_TAGS);
+        let second = builder.add_polyline(vec![(1.0, 0.5), (0.0, 0.0)], NO_TAGS);
+        let relation = builder.add_relation(
+            vec![
+                (ElementRef::Way(first), "outer"),
+                (ElementRef::Way(second), "outer"),
+            ],
+            vec![("type", "multipolygon")],
+        );
+
+        let errors = builder.build().validate();
+        assert_eq!(
+            errors,
+            vec![ValidationError::UnclosedRing {
+                relation_id: relation
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_references() {
+        let mut builder = OsmBuilder::default();
+        let way = builder.add_polyline(vec![(0.0, 0.0), (1.0, 1.0)], NO_TAGS);
+        let relation = builder.add_relation(
+            vec![
+                (ElementRef::Way(way), "outer"),
+                (ElementRef::Way(100), "outer"),
+            ],
+            vec![("type", "multipolygon")],
+        );
+        let mut osm = builder.build();
+        osm.ways[0].refs.push(50);
+
+        let errors = osm.validate();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::MissingNode {
+                    way_id: way,
+                    node_id: 50
+                },
+                ValidationError::MissingMember {
+                    relation_id: relation,
+                    member: ElementRef::Way(100)
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn geometry_warnings() {
+        let mut osm = Osm::default();
+        for (id, lat) in [(1, 0.0), (2, 1.0), (3, 1.0)] {
+            osm.add_node(Node {
+                id,
+                coordinate: (lat, 0.0).into(),
+                meta: Meta::default(),
+            });
+        }
+        osm.add_way(Way {
+            id: 10,
+            refs: vec![1, 2, 2, 3, 4],
+            meta: Meta::default(),
+        });
+
+        assert_eq!(
+            osm.warnings(),
+            vec![
+                Warning::NullIsland { node_id: 1 },
+                Warning::DuplicateConsecutiveRef {
+                    way_id: 10,
+                    node_id: 2
+                },
+                Warning::ZeroLengthSegment {
+                    way_id: 10,
+                    from: 2,
+                    to: 3
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use vadeen_osm::{Meta, Node, Osm, Relation, RelationMember, Way};
+
+/// Map with nodes, a way and a relation with negative ids, as exported by editors. Versions are
+/// set since the xml writer defaults missing versions to 1, so the map round trips through all
+/// formats.
+pub fn negative_id_map() -> Osm {
+    let mut osm = Osm::default();
+    osm.add_node(Node {
+        id: -1,
+        coordinate: (1.0, 1.0).into(),
+        meta: Meta {
+            version: Some(1),
+            ..Meta::default()
+        },
+    });
+    osm.add_node(Node {
+        id: -2,
+        coordinate: (2.0, 2.0).into(),
+        meta: Meta {
+            version: Some(1),
+            ..Meta::default()
+        },
+    });
+    osm.add_way(Way {
+        id: -3,
+        refs: vec![-1, -2],
+        meta: Meta {
+            tags: vec![("highway", "primary").into()],
+            version: Some(1),
+            ..Meta::default()
+        },
+    });
+    osm.add_relation(Relation {
+        id: -4,
+        members: vec![
+            RelationMember::Node(-1, "stop".to_owned()),
+            RelationMember::Way(-3, "".to_owned()),
+        ],
+        meta: Meta {
+            version: Some(1),
+            ..Meta::default()
+        },
+    });
+    osm
+}
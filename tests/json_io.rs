@@ -0,0 +1,26 @@
+use std::io::BufReader;
+use vadeen_osm::osm_io::{create_reader, create_writer, read, FileFormat};
+
+/// Round trip generated.osm through the json format.
+#[test]
+fn xml_to_json_round_trip() {
+    let osm = read("./tests/test_data/generated.osm").unwrap();
+
+    let mut writer = create_writer(Vec::new(), FileFormat::OsmJson);
+    writer.write(&osm).unwrap();
+    let json = writer.into_inner();
+
+    let mut reader = create_reader(BufReader::new(json.as_slice()), FileFormat::OsmJson);
+    let result = reader.read().unwrap();
+
+    assert_eq!(result.boundary, osm.boundary);
+    assert_eq!(result.nodes.len(), osm.nodes.len());
+    assert_eq!(result.ways.len(), osm.ways.len());
+    assert_eq!(result.relations.len(), osm.relations.len());
+
+    let way = &osm.ways[0];
+    let result_way = result.ways.iter().find(|w| w.id == way.id).unwrap();
+    assert_eq!(result_way.refs, way.refs);
+    assert_eq!(result_way.meta, way.meta);
+    assert_eq!(result.relations, osm.relations);
+}
@@ -3,8 +3,14 @@ use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
 use vadeen_osm::geo::Coordinate;
-use vadeen_osm::osm_io::{create_reader, create_writer, read, FileFormat};
+use vadeen_osm::osm_io::{
+    convert, create_filtered_reader, create_reader, create_writer, read, FileFormat, O5mReader,
+    O5mWriter, OsmRead, OsmWrite, TagFilter,
+};
 use vadeen_osm::RelationMember::Way;
+use vadeen_osm::{AuthorInformation, Meta, Node, Osm};
+
+mod common;
 
 /// real_map.o5m is real_map.osm converted with osmconvert. There seems to be coordinate drifting
 /// in that converter, so coordinates do not match up with the .osm version.
@@ -122,3 +128,268 @@ fn write_o5m_file() {
 
     assert_eq!(writer.into_inner(), expected_output);
 }
+
+/// Converting element by element gives the same result as reading the whole map and writing it.
+#[test]
+fn convert_o5m_file() {
+    let input = std::fs::read("./tests/test_data/generated.osm").unwrap();
+    let osm = create_reader(&input[..], FileFormat::Xml).read().unwrap();
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&osm).unwrap();
+    let expected_output = writer.into_inner();
+
+    let mut output = Vec::new();
+    convert(&input[..], FileFormat::Xml, &mut output, FileFormat::O5m).unwrap();
+    assert_eq!(output, expected_output);
+
+    // And back again.
+    let mut xml = Vec::new();
+    convert(&output[..], FileFormat::O5m, &mut xml, FileFormat::Xml).unwrap();
+    let result = create_reader(&xml[..], FileFormat::Xml).read().unwrap();
+    assert_eq!(result.nodes, osm.nodes);
+    assert_eq!(result.ways, osm.ways);
+    assert_eq!(result.relations, osm.relations);
+    assert_eq!(result.boundary, osm.boundary);
+}
+
+/// Timestamps must survive a round trip from xml, through o5m and back to xml.
+#[test]
+fn xml_o5m_xml_timestamp_round_trip() {
+    let original = read("./tests/test_data/real_map.osm").unwrap();
+
+    // Write and read o5m
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&original).unwrap();
+    let o5m = writer.into_inner();
+    let osm = create_reader(BufReader::new(&o5m[..]), FileFormat::O5m)
+        .read()
+        .unwrap();
+
+    let node = osm.nodes.iter().find(|r| r.id == 60686436).unwrap();
+    assert_eq!(1295564363, node.meta.author.as_ref().unwrap().created);
+
+    // Write and read xml
+    let mut writer = create_writer(Vec::new(), FileFormat::Xml);
+    writer.write(&osm).unwrap();
+    let xml = String::from_utf8(writer.into_inner()).unwrap();
+
+    let line = xml.lines().find(|l| l.contains("id=\"60686436\"")).unwrap();
+    assert!(line.contains("timestamp=\"2011-01-20T22:59:23Z\""));
+
+    let osm = create_reader(BufReader::new(xml.as_bytes()), FileFormat::Xml)
+        .read()
+        .unwrap();
+    let node = osm.nodes.iter().find(|r| r.id == 60686436).unwrap();
+    assert_eq!(1295564363, node.meta.author.as_ref().unwrap().created);
+
+    // All timestamps should be intact, not only the one asserted above.
+    let created = |meta: &vadeen_osm::Meta| meta.author.as_ref().map(|a| a.created);
+    for (a, b) in original.nodes.iter().zip(osm.nodes.iter()) {
+        assert_eq!(created(&a.meta), created(&b.meta));
+    }
+    for (a, b) in original.ways.iter().zip(osm.ways.iter()) {
+        assert_eq!(created(&a.meta), created(&b.meta));
+    }
+    for (a, b) in original.relations.iter().zip(osm.relations.iter()) {
+        assert_eq!(created(&a.meta), created(&b.meta));
+    }
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn read_bzip2_o5m_file() {
+    use bzip2::write::BzEncoder;
+    use bzip2::Compression;
+    use std::io::Write;
+
+    let mut input = Vec::new();
+    File::open("./tests/test_data/generated.o5m")
+        .unwrap()
+        .read_to_end(&mut input)
+        .unwrap();
+
+    let mut encoder = BzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&input).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let path = std::env::temp_dir().join("vadeen_osm_read_bzip2.o5m.bz2");
+    File::create(&path).unwrap().write_all(&compressed).unwrap();
+    let osm = read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let expected = read("./tests/test_data/generated.o5m").unwrap();
+    assert!(!osm.is_empty());
+    assert_eq!(osm.boundary, expected.boundary);
+    assert_eq!(osm.nodes, expected.nodes);
+    assert_eq!(osm.ways, expected.ways);
+    assert_eq!(osm.relations, expected.relations);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn read_zstd_o5m_stream() {
+    use vadeen_osm::osm_io::{create_reader_compressed, Compression};
+
+    let input = std::fs::read("./tests/test_data/generated.o5m").unwrap();
+    let compressed = zstd::encode_all(&input[..], 0).unwrap();
+    let mut reader =
+        create_reader_compressed(&compressed[..], FileFormat::O5m, Compression::Zstd).unwrap();
+    let osm = reader.read().unwrap();
+
+    let expected = read("./tests/test_data/generated.o5m").unwrap();
+    assert!(!osm.is_empty());
+    assert_eq!(osm, expected);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn gzip_o5m_round_trip() {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use vadeen_osm::osm_io::{create_reader_compressed, Compression};
+
+    let expected = read("./tests/test_data/generated.o5m").unwrap();
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&expected).unwrap();
+
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&writer.into_inner()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let mut reader =
+        create_reader_compressed(&compressed[..], FileFormat::O5m, Compression::Gzip).unwrap();
+    let osm = reader.read().unwrap();
+    assert!(!osm.is_empty());
+    assert_eq!(osm, expected);
+}
+
+/// Strings evicted from a small string table must be written in full again, not as references
+/// to strings the reader no longer has.
+#[test]
+fn small_string_table_round_trip() {
+    let osm = read("./tests/test_data/real_map.osm").unwrap();
+
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&osm).unwrap();
+    let default_output = writer.into_inner();
+
+    let mut writer = Box::new(O5mWriter::new(Vec::new()).with_string_table_size(4));
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+    assert!(output.len() > default_output.len());
+
+    let expected = O5mReader::new(BufReader::new(&default_output[..]))
+        .read()
+        .unwrap();
+    let mut reader = O5mReader::new(BufReader::new(&output[..])).with_string_table_size(4);
+    let result = reader.read().unwrap();
+    assert_eq!(result.nodes, expected.nodes);
+    assert_eq!(result.ways, expected.ways);
+    assert_eq!(result.relations, expected.relations);
+}
+
+#[test]
+fn negative_ids_round_trip() {
+    let osm = common::negative_id_map();
+
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+
+    let result = create_reader(BufReader::new(&output[..]), FileFormat::O5m)
+        .read()
+        .unwrap();
+    assert_eq!(result.nodes, osm.nodes);
+    assert_eq!(result.ways, osm.ways);
+    assert_eq!(result.relations, osm.relations);
+}
+
+#[test]
+fn read_filtered_o5m_file() {
+    let path = "./tests/test_data/real_map.o5m";
+    let all = read(path).unwrap();
+
+    let file = BufReader::new(File::open(path).unwrap());
+    let filter = TagFilter::default().tag("amenity", "parking");
+    let osm = create_filtered_reader(file, FileFormat::O5m, filter)
+        .read()
+        .unwrap();
+
+    assert_eq!(osm.nodes.len(), all.nodes.len());
+    assert!(osm.relations.is_empty());
+    assert_eq!(osm.ways.len(), 1);
+    assert!(osm.ways[0]
+        .meta
+        .tags
+        .contains(&("amenity", "parking").into()));
+
+    let file = BufReader::new(File::open(path).unwrap());
+    let filter = TagFilter::default()
+        .tag("amenity", "parking")
+        .prune_nodes(true);
+    let pruned = create_filtered_reader(file, FileFormat::O5m, filter)
+        .read()
+        .unwrap();
+    assert!(pruned.nodes.len() < all.nodes.len());
+    for id in &pruned.ways[0].refs {
+        assert!(pruned.nodes.iter().any(|n| n.id == *id));
+    }
+}
+
+/// The o5m format has no author information without a version, so a version is added.
+#[test]
+fn author_without_version_round_trip() {
+    let author = AuthorInformation {
+        created: 1285874610,
+        change_set: 5922698,
+        uid: 45445,
+        user: "UScha".to_owned(),
+    };
+    let mut osm = Osm::default();
+    osm.add_node(Node {
+        id: 1,
+        coordinate: (1.0, 2.0).into(),
+        meta: Meta {
+            author: Some(author.clone()),
+            ..Meta::default()
+        },
+    });
+
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+
+    let result = create_reader(BufReader::new(&output[..]), FileFormat::O5m)
+        .read()
+        .unwrap();
+    assert_eq!(result.nodes[0].meta.author, Some(author));
+    assert_eq!(result.nodes[0].meta.version, Some(1));
+}
+
+/// A timestamp without author information is written with change set 0 and an empty user.
+#[test]
+fn timestamp_without_author_round_trip() {
+    let mut osm = Osm::default();
+    for (id, timestamp) in [(1, 1285874610), (2, 1285874620)].iter() {
+        osm.add_node(Node {
+            id: *id,
+            coordinate: (1.0, 2.0).into(),
+            meta: Meta {
+                version: Some(3),
+                timestamp: Some(*timestamp),
+                ..Meta::default()
+            },
+        });
+    }
+
+    let mut writer = create_writer(Vec::new(), FileFormat::O5m);
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+
+    let result = create_reader(BufReader::new(&output[..]), FileFormat::O5m)
+        .read()
+        .unwrap();
+    assert_eq!(result.nodes, osm.nodes);
+    assert_eq!(result.nodes[1].meta.timestamp, Some(1285874620));
+    assert_eq!(result.nodes[1].meta.author, None);
+}
@@ -3,8 +3,11 @@ use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
 use vadeen_osm::geo::Coordinate;
-use vadeen_osm::osm_io::{create_reader, create_writer, read};
+use vadeen_osm::osm_io::{create_reader, create_writer, read, FileFormat};
 use vadeen_osm::RelationMember::Way;
+use vadeen_osm::{Action, Meta, Osm, OsmBuilder};
+
+mod common;
 
 #[test]
 fn read_osm_file() {
@@ -115,3 +118,151 @@ fn read_write_osm_file() {
 
     assert_eq!(input, writer.into_inner());
 }
+
+#[test]
+fn extend_osm_file_with_builder() {
+    let osm = read("./tests/test_data/real_map.osm").unwrap();
+    let max_id = osm
+        .nodes
+        .iter()
+        .map(|n| n.id)
+        .chain(osm.ways.iter().map(|w| w.id))
+        .chain(osm.relations.iter().map(|r| r.id))
+        .max()
+        .unwrap();
+    let node_count = osm.nodes.len();
+
+    let mut builder = OsmBuilder::from_osm(osm);
+    let existing = Coordinate::new(60.6763366, 17.1421725);
+    let way_id = builder.add_polyline(
+        vec![existing, (60.7, 17.2).into()],
+        vec![("highway", "path")],
+    );
+
+    let osm = builder.build();
+    assert!(way_id > max_id);
+
+    // The existing node is reused, only one node is added.
+    assert_eq!(osm.nodes.len(), node_count + 1);
+
+    let way = osm.ways.iter().find(|w| w.id == way_id).unwrap();
+    assert_eq!(way.refs[0], 60686436);
+    assert!(way.refs[1] > max_id);
+}
+
+#[test]
+fn outer_ring_of_building() {
+    let osm = read("./tests/test_data/real_map.osm").unwrap();
+    let relation = osm.relations.iter().find(|r| r.id == 1604937).unwrap();
+    let outer = osm.ways.iter().find(|w| w.id == 115494554).unwrap();
+
+    let expected: Vec<Coordinate> = outer
+        .refs
+        .iter()
+        .map(|id| osm.nodes.iter().find(|n| n.id == *id).unwrap().coordinate)
+        .collect();
+    assert_eq!(osm.outer_ring(relation), Some(expected));
+}
+
+#[test]
+fn negative_ids_round_trip() {
+    let osm = common::negative_id_map();
+
+    let mut writer = create_writer(Vec::new(), FileFormat::Xml);
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+
+    let result = create_reader(BufReader::new(&output[..]), FileFormat::Xml)
+        .read()
+        .unwrap();
+    assert_eq!(result.nodes, osm.nodes);
+    assert_eq!(result.ways, osm.ways);
+    assert_eq!(result.relations, osm.relations);
+}
+
+#[test]
+fn way_bounds_of_real_map() {
+    let osm = read("./tests/test_data/real_map.osm").unwrap();
+    let way = osm.ways.iter().find(|w| w.id == 20451003).unwrap();
+
+    let bounds = osm.way_bounds(way).unwrap();
+    assert_eq!(bounds.min, (60.674315, 17.1380977).into());
+    assert_eq!(bounds.max, (60.675263, 17.1391041).into());
+
+    let missing = vadeen_osm::Way {
+        id: 1,
+        refs: vec![-1, -2],
+        meta: Meta::default(),
+    };
+    assert_eq!(osm.way_bounds(&missing), None);
+}
+
+/// Ways crossing the declared boundary of the extract are kept whole, so the data extends outside
+/// of it.
+#[test]
+fn data_extent_of_real_map() {
+    let osm = read("./tests/test_data/real_map.osm").unwrap();
+    let declared = osm.boundary.clone().unwrap();
+    let extent = osm.data_extent().unwrap();
+
+    assert_ne!(extent, declared);
+    assert!(extent.min.lat < declared.min.lat);
+    assert!(extent.max.lat > declared.max.lat);
+    let min_lat = osm.nodes.iter().map(|n| n.coordinate.lat).min().unwrap();
+    let max_lon = osm.nodes.iter().map(|n| n.coordinate.lon).max().unwrap();
+    assert_eq!(extent.min.lat, min_lat);
+    assert_eq!(extent.max.lon, max_lon);
+
+    assert_eq!(Osm::default().data_extent(), None);
+}
+
+#[test]
+fn tag_key_cardinality_of_real_map() {
+    let osm = read("./tests/test_data/real_map.osm").unwrap();
+    let cardinality = osm.tag_key_cardinality();
+
+    let count = |key: &str| cardinality.iter().find(|(k, _)| k == key).unwrap().1;
+    assert!(count("highway") > count("collection_times"));
+    assert!(cardinality.windows(2).all(|w| w[0].1 >= w[1].1));
+}
+
+#[test]
+fn action_round_trip() {
+    let mut osm = Osm::default();
+    osm.add_way(vadeen_osm::Way {
+        id: 10,
+        refs: vec![1, 2],
+        meta: Meta {
+            version: Some(3),
+            action: Some(Action::Delete),
+            ..Meta::default()
+        },
+    });
+
+    let mut writer = create_writer(Vec::new(), FileFormat::Xml);
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+    assert!(String::from_utf8_lossy(&output).contains(r#"<way id="10" action="delete""#));
+
+    let result = create_reader(BufReader::new(&output[..]), FileFormat::Xml)
+        .read()
+        .unwrap();
+    assert_eq!(result.ways, osm.ways);
+}
+
+#[test]
+fn extra_osm_attributes_round_trip() {
+    let input = r#"<osm version="0.6" generator="test" foo="bar" upload="false"></osm>"#;
+    let osm = create_reader(BufReader::new(input.as_bytes()), FileFormat::Xml)
+        .read()
+        .unwrap();
+    assert_eq!(osm.extra_attributes.len(), 2);
+    assert_eq!(osm.extra_attributes["foo"], "bar");
+    assert_eq!(osm.extra_attributes["upload"], "false");
+
+    let mut writer = create_writer(Vec::new(), FileFormat::Xml);
+    writer.write(&osm).unwrap();
+    let output = writer.into_inner();
+    assert!(String::from_utf8_lossy(&output)
+        .contains(r#"generator="Vadeen OSM" foo="bar" upload="false">"#));
+}